@@ -0,0 +1,182 @@
+//how long the renderer takes, whole frames and the CRITICAL pieces on their own.
+//only std timing: every benchmark runs for about TARGET, in a few samples, and the median sample is what gets reported.
+//cargo bench -- hydra runs just the ones with hydra in their name
+
+use std::env;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use dimers::auxiliary::{self, COLOURINGS};
+use dimers::colour_format::LinearCol;
+use dimers::dihedral_translation::{EvenD8Translation, IDENTITY};
+use dimers::fractal_specification::{self, PRESETS};
+use dimers::interval_colouring::{SplitInterval, ConstantInterval, FunctionInterval, CachedFunctionInterval, DepthLimitedInterval};
+use dimers::pixel_sink::PixelSink;
+use dimers::relevance_getter::{self, Splitter};
+use dimers::render::{self, View};
+use dimers::square::UncroppedSquare;
+use dimers::triangle::Triangle;
+use dimers::trimer;
+use dimers::trimer_specification::TRIMER_PRESETS;
+
+const TARGET: Duration = Duration::from_millis(600);
+const SAMPLES: u32 = 5;
+
+//throws the pixels away, so only the drawing gets measured
+struct NullSink;
+
+impl PixelSink for NullSink {
+	fn put(&self, x: usize, y: usize, colour: LinearCol) {
+		black_box((x, y, colour));
+	}
+}
+
+struct Bencher {
+	filters: Vec<String>,
+}
+
+impl Bencher {
+	fn bench<F: FnMut()>(&self, name: &str, mut routine: F) {
+		if !self.filters.is_empty() && !self.filters.iter().any(|filter| name.contains(filter.as_str())) {
+			return;
+		}
+
+		//one run to warm up and see roughly how long it takes
+		let start = Instant::now();
+		routine();
+		let once = start.elapsed().max(Duration::from_nanos(1));
+		let iterations = ((TARGET / SAMPLES).as_nanos() / once.as_nanos()).clamp(1, 10_000_000) as u32;
+
+		let mut samples: Vec<Duration> = (0..SAMPLES).map(|_| {
+			let start = Instant::now();
+			for _ in 0..iterations {
+				routine();
+			}
+			start.elapsed() / iterations
+		}).collect();
+		samples.sort();
+		println!(
+			"{:<36} {:>12} per iteration  (fastest {}, {} x {} runs)",
+			name,
+			format_duration(samples[samples.len() / 2]),
+			format_duration(samples[0]),
+			SAMPLES,
+			iterations,
+		);
+	}
+}
+
+fn format_duration(duration: Duration) -> String {
+	let nanos = duration.as_nanos();
+	if nanos >= 1_000_000_000 {
+		format!("{:.2}s", duration.as_secs_f64())
+	} else if nanos >= 1_000_000 {
+		format!("{:.2}ms", nanos as f64 / 1e6)
+	} else if nanos >= 1_000 {
+		format!("{:.2}us", nanos as f64 / 1e3)
+	} else {
+		format!("{}ns", nanos)
+	}
+}
+
+fn main() {
+	//cargo passes --bench along, the rest is up to us
+	let bencher = Bencher {
+		filters: env::args().skip(1).filter(|arg| !arg.starts_with("--")).collect(),
+	};
+	let view = View::centred(256, 256, 6);
+
+	//whole frames, every preset with one colouring, then every colouring on one preset
+	for (name, fractal) in PRESETS.iter() {
+		bench_frame(&bencher, &format!("frame/{}", name), fractal, view, auxiliary::two_colouring);
+	}
+	let hydra = fractal_specification::preset("hydra").expect("hydra is a preset");
+	for (name, colouring) in COLOURINGS.iter() {
+		bench_frame(&bencher, &format!("colouring/{}", name), hydra, view, *colouring);
+	}
+	//a colour function slow enough to matter, plain and through the wrappers that are meant to make it cheap
+	bench_frame(&bencher, "slow_colouring/plain", hydra, view, slow_colouring);
+	bench_frame(&bencher, "slow_colouring/cached", hydra, view, cached_slow_colouring);
+	bench_frame(&bencher, "slow_colouring/depth_limited", hydra, view, depth_limited_slow_colouring);
+	for (name, fractal) in TRIMER_PRESETS.iter() {
+		bencher.bench(&format!("trimer/{}", name), || {
+			black_box(trimer::render_antialiased(fractal, view, trimer::three_colouring, 1));
+		});
+	}
+
+	//the CRITICAL functions on their own, on hydra with the rainbow colouring (so the intervals have something to do)
+	let relevance_list = relevance_getter::get_relevance_list(hydra).expect("hydra is a working preset");
+	let requirement_list = relevance_getter::to_requirement_list(&relevance_list);
+	let splitter_list = relevance_getter::get_splitter_list(hydra, &requirement_list).expect("hydra is a working preset");
+	let triangle_weights = relevance_getter::get_weights(hydra, &relevance_list).expect("hydra is a working preset");
+	let splitting_type = hydra.get_splitting_type();
+
+	let triangle = Triangle::new(IDENTITY, auxiliary::rainbow_colouring, &requirement_list);
+	bencher.bench("triangle/split", || {
+		black_box(triangle.split(&splitter_list));
+	});
+	let deep = split_deeply(&triangle, &splitter_list, 12);
+	bencher.bench("triangle/split_deep", || {
+		black_box(deep.split(&splitter_list));
+	});
+	bencher.bench("triangle/get_colour", || {
+		black_box(deep.get_colour(&triangle_weights));
+	});
+
+	let square = || {
+		let config = hydra.get_square_config(0, 0, &requirement_list, &mut auxiliary::rainbow_colouring);
+		UncroppedSquare::new(0, 0, 64, config)
+	};
+	bencher.bench("square/new", || {
+		black_box(square());
+	});
+	//these two include making the square, so take square/new off them
+	bencher.bench("square/split", || {
+		black_box(square().split(&splitter_list, splitting_type));
+	});
+	bencher.bench("square/draw_64", || {
+		square().draw(&NullSink, &triangle_weights, &splitter_list, splitting_type);
+	});
+}
+
+fn bench_frame<F>(bencher: &Bencher, name: &str, fractal: &fractal_specification::FractalSpecification, view: View, colouring: F)
+	where F: Fn(EvenD8Translation) -> Box<dyn SplitInterval> + Copy {
+	bencher.bench(name, || {
+		black_box(render::render(fractal, view, colouring));
+	});
+}
+
+//the first child, depth times over, so splits are measured somewhere typical rather than at the top
+fn split_deeply(triangle: &Triangle, splitter_list: &[Splitter], depth: usize) -> Triangle {
+	let (mut current, _) = triangle.split(splitter_list);
+	for _ in 1..depth {
+		current = current.split(splitter_list).0;
+	}
+	current
+}
+
+//a rainbow worked out the long way round, standing in for an expensive user colour function
+fn slow_colour(x: f64) -> LinearCol {
+	let position = (0..50).fold(x, |position, step| position + (position * step as f64).sin() * 1e-9);
+	auxiliary::gradient(&auxiliary::RAINBOW_STOPS, position)
+}
+
+fn slow_colouring(transform: EvenD8Translation) -> Box<dyn SplitInterval> {
+	if transform != IDENTITY {
+		return Box::<ConstantInterval>::new(LinearCol::new(0.0, 0.0, 0.0, 0.0).into());
+	}
+	let interval: FunctionInterval<_> = (slow_colour as fn(f64) -> LinearCol).into();
+	Box::new(interval)
+}
+
+fn cached_slow_colouring(transform: EvenD8Translation) -> Box<dyn SplitInterval> {
+	if transform != IDENTITY {
+		return Box::<ConstantInterval>::new(LinearCol::new(0.0, 0.0, 0.0, 0.0).into());
+	}
+	Box::new(CachedFunctionInterval::new(slow_colour as fn(f64) -> LinearCol, 1 << 16))
+}
+
+//a piece 8 splits down is a 256th of the rainbow, so it hardly changes along its length
+fn depth_limited_slow_colouring(transform: EvenD8Translation) -> Box<dyn SplitInterval> {
+	Box::new(DepthLimitedInterval::new(slow_colouring(transform), 8))
+}
@@ -0,0 +1,8 @@
+//serves tiles over http for a browser viewer, see dimers::tile_server
+pub fn main() {
+	let args: Vec<String> = std::env::args().skip(1).collect();
+	if let Err(message) = dimers::tile_server::run(&args) {
+		eprintln!("{}", message);
+		std::process::exit(1);
+	}
+}
@@ -0,0 +1,208 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+use super::auxiliary::{COLOURINGS, FIRE_STOPS, RAINBOW_STOPS};
+use super::colour_format::LinearCol;
+use super::dihedral_translation::{EvenD8Translation, IDENTITY};
+use super::dithering::Dithering;
+use super::fractal_specification::FractalSpecification;
+use super::image_output::{ApngWriter, GifWriter};
+use super::interval_colouring::{ConstantInterval, FunctionInterval, HueRotatedInterval, SplitInterval};
+use super::pixel_format::PixelFormat;
+use super::progress::ProgressTracker;
+use super::render::{self, Image, View};
+use super::scene::Scene;
+use super::zoom::{self, Zoom};
+
+//animations of a scene, with something about it changing from frame to frame.
+//t goes from 0 up to (not including) 1 over the frames, so the frame after the last one would be the first again.
+//zooms are the exception: they don't come back round, so their t goes all the way to 1 (see Motion::t).
+//the fractal itself stays the same throughout.
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Motion {
+	//the colouring's gradient slides along the identity's interval and wraps round (see cycling_colouring).
+	//only for the colourings in CYCLING_STOPS
+	Cycle,
+	//every colour's hue goes once round the colour wheel (see HueRotatedInterval), for any colouring
+	Hue,
+	//the origin goes from where the scene has it through each of these offsets from there, and back, at an even speed
+	Pan(Vec<(isize, isize)>),
+	//in on a point, a scale level or more (see zoom)
+	Zoom(Zoom),
+}
+
+impl Motion {
+	//where frame is in the animation
+	pub fn t(&self, frame: u32, frames: u32) -> f64 {
+		match self {
+			Motion::Zoom(_) if frames > 1 => frame as f64 / (frames - 1) as f64,
+			Motion::Zoom(_) => 0.0,
+			_ => frame as f64 / frames as f64,
+		}
+	}
+}
+
+//how many pixels frames frames of motion render, for a ProgressTracker
+pub fn pixel_total(scene: &Scene, motion: &Motion, frames: u32) -> usize {
+	match motion {
+		Motion::Zoom(zoom) => (0..frames)
+			.map(|frame| zoom.frame_view(scene.view(), motion.t(frame, frames)).0)
+			.map(|view| view.width * view.height)
+			.sum(),
+		_ => frames as usize * scene.width * scene.height,
+	}
+}
+
+//the gradients of the colourings that can be cycled
+pub static CYCLING_STOPS: [(&str, &[LinearCol]); 3] = [
+	("continuum", &[LinearCol::new(1.0, 0.0, 0.0, 0.0), LinearCol::new(0.0, 0.0, 1.0, 0.0)]),
+	("rainbow", &RAINBOW_STOPS),
+	("fire", &FIRE_STOPS),
+];
+
+pub fn cycling_stops(colouring_name: &str) -> Option<&'static [LinearCol]> {
+	CYCLING_STOPS
+		.iter()
+		.find(|(name, _)| *name == colouring_name)
+		.map(|(_, stops)| *stops)
+}
+
+//evenly spaced stops going round in a loop, so the last one leads back into the first. Any x is fine
+pub fn looped_gradient(stops: &[LinearCol], x: f64) -> LinearCol {
+	let position = x.rem_euclid(1.0) * stops.len() as f64;
+	let index = (position as usize).min(stops.len() - 1);
+	let along = position - index as f64;
+	(1.0 - along) * stops[index] + along * stops[(index + 1) % stops.len()]
+}
+
+//like auxiliary::rainbow_colouring, but the identity's interval is [phase, phase + 1] of a looped gradient
+pub fn cycling_colouring(stops: &'static [LinearCol], phase: f64, transform: EvenD8Translation) -> Box<dyn SplitInterval> {
+	let background = LinearCol::new(0.0, 0.0, 0.0, 0.0);
+	if transform != IDENTITY {
+		Box::<ConstantInterval>::new(background.into())
+	} else {
+		let func = move |x: f64| looped_gradient(stops, x);
+		Box::new(FunctionInterval::new(func, phase, phase + 1.0))
+	}
+}
+
+//the point t of the way round the closed path through points, going by distance
+pub fn along_path(points: &[(isize, isize)], t: f64) -> (isize, isize) {
+	let segment = |index: usize| (points[index], points[(index + 1) % points.len()]);
+	let length = |(a, b): ((isize, isize), (isize, isize))| ((b.0 - a.0) as f64).hypot((b.1 - a.1) as f64);
+	let total: f64 = (0..points.len()).map(|index| length(segment(index))).sum();
+	if total == 0.0 {
+		return points[0];
+	}
+
+	let mut left = t.rem_euclid(1.0) * total;
+	for index in 0..points.len() {
+		let (a, b) = segment(index);
+		let here = length((a, b));
+		if left < here {
+			let along = left / here;
+			return (
+				a.0 + ((b.0 - a.0) as f64 * along).round() as isize,
+				a.1 + ((b.1 - a.1) as f64 * along).round() as isize,
+			);
+		}
+		left -= here;
+	}
+	points[0]
+}
+
+//the scene at t (counting pixels into tracker, if there is one)
+pub fn render_frame(scene: &Scene, motion: &Motion, t: f64, tracker: Option<Arc<ProgressTracker>>) -> Image {
+	let fractal = scene.fractal.specification();
+	match motion {
+		Motion::Cycle => {
+			let stops = cycling_stops(COLOURINGS[scene.colouring].0).expect("only cycling colourings can cycle (49101)");
+			draw(fractal, scene.view(), |transform| cycling_colouring(stops, t, transform), scene.antialiasing, tracker)
+		},
+		Motion::Hue => {
+			let colouring = scene.triangle_colouring();
+			let rotated = |transform| -> Box<dyn SplitInterval> {Box::new(HueRotatedInterval::new(colouring(transform), t))};
+			draw(fractal, scene.view(), rotated, scene.antialiasing, tracker)
+		},
+		Motion::Pan(offsets) => {
+			let points: Vec<(isize, isize)> = std::iter::once((0, 0)).chain(offsets.iter().copied()).collect();
+			let offset = along_path(&points, t);
+			let view = View {origin: (scene.origin.0 + offset.0, scene.origin.1 + offset.1), ..scene.view()};
+			draw(fractal, view, scene.triangle_colouring(), scene.antialiasing, tracker)
+		},
+		Motion::Zoom(zoom) => {
+			let (view, offset, ratio) = zoom.frame_view(scene.view(), t);
+			let image = draw(fractal, view, scene.triangle_colouring(), scene.antialiasing, tracker);
+			zoom::resampled(&image, offset, ratio, scene.width, scene.height)
+		},
+	}
+}
+
+fn draw<F>(fractal: &FractalSpecification, view: View, colouring: F, antialiasing: usize, tracker: Option<Arc<ProgressTracker>>) -> Image
+	where F: FnMut(EvenD8Translation) -> Box<dyn SplitInterval> {
+	match tracker {
+		Some(tracker) => render::render_tracked(fractal, view, colouring, antialiasing, tracker),
+		None => render::render_antialiased(fractal, view, colouring, antialiasing),
+	}
+}
+
+enum AnimatedFile {
+	Gif(GifWriter<BufWriter<File>>),
+	Apng(ApngWriter<BufWriter<File>>),
+}
+
+//renders frames frames (render gets each one's number) and writes them out as numbered pngs in frame_directory,
+//and/or one animated file (a gif, or an apng for anything ending in .png). Each frame is shown for delay milliseconds.
+//format is for the pngs, the gif always gets squashed into its own palette
+#[allow(clippy::too_many_arguments)]
+pub fn export<F>(
+	frames: u32,
+	delay: u16,
+	(width, height): (usize, usize),
+	format: PixelFormat,
+	dithering: Dithering,
+	frame_directory: Option<&Path>,
+	animated: Option<&Path>,
+	mut render: F,
+) -> io::Result<()> where F: FnMut(u32) -> Image {
+	if let Some(directory) = frame_directory {
+		fs::create_dir_all(directory)?;
+	}
+	let mut animated_file = match animated {
+		Some(path) => {
+			let extension = path.extension().and_then(|extension| extension.to_str()).map(|extension| extension.to_ascii_lowercase());
+			let file = BufWriter::new(File::create(path)?);
+			match extension.as_deref() {
+				//gif delays are in hundredths of a second
+				Some("gif") => Some(AnimatedFile::Gif(GifWriter::new(file, width, height, delay.div_ceil(10), dithering)?)),
+				Some("png") => Some(AnimatedFile::Apng(ApngWriter::new(file, width, height, format, frames, delay)?)),
+				_ => return Err(io::Error::new(io::ErrorKind::InvalidInput, "animations can only be saved as gif or png")),
+			}
+		},
+		None => None,
+	};
+
+	//enough digits that the files sort in order
+	let digits = (frames.saturating_sub(1).checked_ilog10().unwrap_or(0) + 1).max(4) as usize;
+	for frame in 0..frames {
+		let image = render(frame);
+		if let Some(directory) = frame_directory {
+			image.save(&directory.join(format!("frame_{:0digits$}.png", frame, digits = digits)), format, dithering)?;
+		}
+		match &mut animated_file {
+			Some(AnimatedFile::Gif(gif)) => gif.write_frame(&image.to_bytes(PixelFormat::Rgb, Dithering::None))?,
+			Some(AnimatedFile::Apng(apng)) => apng.write_frame(&image.to_bytes(format, dithering))?,
+			None => {},
+		}
+	}
+
+	match animated_file {
+		Some(AnimatedFile::Gif(gif)) => gif.finish()?.flush(),
+		Some(AnimatedFile::Apng(apng)) => apng.finish()?.flush(),
+		None => Ok(()),
+	}
+}
+
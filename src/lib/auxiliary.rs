@@ -1,23 +1,15 @@
 use std::rc::Rc;
+use std::sync::{Arc, OnceLock};
 
 use super::colour_format::LinearCol;
 use super::dihedral_translation::{EvenD8Translation, IDENTITY};
 use super::dihedral;
-use super::interval_colouring::{SplitInterval, ConstantInterval, TreeInterval, FunctionInterval};
+use super::interval_colouring::{SplitInterval, ConstantInterval, TreeInterval, FunctionInterval, ParityInterval, StabilisationInterval, StripInterval, TextureInterval};
+use super::interval_rules;
 use super::square::SquareCut;
+use super::texture::Texture;
 use super::triangle::Triangle;
 
-pub enum WorkingMessage {
-	Begin,
-	End,
-}
-
-#[derive(PartialEq, Eq)]
-pub enum WorkingState {
-	Working,
-	Finished,
-}
-
 pub fn even_coordinates(transformation: EvenD8Translation) -> bool {
 	let (x, y) = transformation.get_translation();
 	(x.rem_euclid(2)) == 0 && (y.rem_euclid(2)) == 0
@@ -79,16 +71,46 @@ pub fn dragon_acceptable(transform: EvenD8Translation) -> bool {
 	}
 }
 
-pub fn simple_colouring (transform: EvenD8Translation) -> Box<dyn SplitInterval> {
-	let colour = if transform == IDENTITY {
+//what the colourings below need from the translations they're given, so the same colourings work for the dimers here
+//(EvenD8Translation, with intervals that split in two) and the trimers (EvenD12Translation, split in three, see trimer)
+pub trait CopyTranslation: Copy + PartialEq {
+	type Interval: ?Sized;
+
+	const IDENTITY: Self;
+	const ROTATIONS: i8; //how many turns make a full circle
+
+	fn get_translation(self) -> (isize, isize);
+	fn orientation(self) -> (bool, i8); //(flipped, rot)
+
+	fn constant(colour: LinearCol) -> Box<Self::Interval>;
+	fn function<F: Fn(f64) -> LinearCol + Clone + 'static>(func: F) -> Box<Self::Interval>;
+}
+
+impl CopyTranslation for EvenD8Translation {
+	type Interval = dyn SplitInterval;
+
+	const IDENTITY: EvenD8Translation = IDENTITY;
+	const ROTATIONS: i8 = 4;
+
+	fn get_translation(self) -> (isize, isize) {self.get_translation()}
+	fn orientation(self) -> (bool, i8) {(self.get_dihedral().flipped(), self.get_dihedral().rot())}
+
+	fn constant(colour: LinearCol) -> Box<dyn SplitInterval> {
+		Box::<ConstantInterval>::new(colour.into())
+	}
+	fn function<F: Fn(f64) -> LinearCol + Clone + 'static>(func: F) -> Box<dyn SplitInterval> {
+		Box::<FunctionInterval<F>>::new(func.into())
+	}
+}
+
+pub fn simple_colouring<T: CopyTranslation> (transform: T) -> Box<T::Interval> {
+	let colour = if transform == T::IDENTITY {
 		LinearCol::new(1.0, 1.0, 1.0, 0.0)
 	} else {
 		LinearCol::new(0.0, 0.0, 0.0, 0.0)
 	};
 
-	let interval: ConstantInterval = colour.into();
-
-	Box::new(interval)
+	T::constant(colour)
 }
 
 pub fn two_colouring (transform: EvenD8Translation) -> Box<dyn SplitInterval> {
@@ -106,19 +128,201 @@ pub fn two_colouring (transform: EvenD8Translation) -> Box<dyn SplitInterval> {
 	}
 }
 
-pub fn simple_continuum_colouring (transform: EvenD8Translation) -> Box<dyn SplitInterval> {
+pub fn simple_continuum_colouring<T: CopyTranslation> (transform: T) -> Box<T::Interval> {
 	let colour_start = LinearCol::new(1.0, 0.0, 0.0, 0.0);
 	let colour_end = LinearCol::new(0.0, 0.0, 1.0, 0.0);
 	let background = LinearCol::new(0.0, 0.0, 0.0, 0.0);
-	if transform != IDENTITY {
-		Box::<ConstantInterval>::new(background.into())
+	if transform != T::IDENTITY {
+		T::constant(background)
 	} else {
 		//Since this closure only captures two LinearCols, this closure is pretty cheap to clone.
 		//Otherwise, I would have wrapped this in an Rc or something.
 		let func = move |x : f64| (1.0 - x) * colour_start + x * colour_end;
-		let interval: FunctionInterval<_> = func.into();
-		Box::new(interval)
+		T::function(func)
+	}
+}
+
+//piecewise linear through evenly spaced stops, for x in [0,1]
+pub fn gradient(stops: &[LinearCol], x: f64) -> LinearCol {
+	let position = x.clamp(0.0, 1.0) * (stops.len() - 1) as f64;
+	let index = (position as usize).min(stops.len() - 2);
+	let along = position - index as f64;
+	(1.0 - along) * stops[index] + along * stops[index + 1]
+}
+
+pub const RAINBOW_STOPS: [LinearCol; 6] = [
+	LinearCol::new(1.0, 0.0, 0.0, 0.0),
+	LinearCol::new(1.0, 1.0, 0.0, 0.0),
+	LinearCol::new(0.0, 1.0, 0.0, 0.0),
+	LinearCol::new(0.0, 1.0, 1.0, 0.0),
+	LinearCol::new(0.0, 0.0, 1.0, 0.0),
+	LinearCol::new(1.0, 0.0, 1.0, 0.0),
+];
+
+pub const FIRE_STOPS: [LinearCol; 4] = [
+	LinearCol::new(0.05, 0.0, 0.0, 0.0),
+	LinearCol::new(0.8, 0.05, 0.0, 0.0),
+	LinearCol::new(1.0, 0.6, 0.0, 0.0),
+	LinearCol::new(1.0, 1.0, 0.8, 0.0),
+];
+
+//like simple_continuum_colouring, but through a whole gradient
+pub fn rainbow_colouring<T: CopyTranslation> (transform: T) -> Box<T::Interval> {
+	let background = LinearCol::new(0.0, 0.0, 0.0, 0.0);
+	if transform != T::IDENTITY {
+		T::constant(background)
+	} else {
+		T::function(|x : f64| gradient(&RAINBOW_STOPS, x))
+	}
+}
+
+pub fn fire_colouring<T: CopyTranslation> (transform: T) -> Box<T::Interval> {
+	let background = LinearCol::new(0.0, 0.0, 0.0, 0.0);
+	if transform != T::IDENTITY {
+		T::constant(background)
+	} else {
+		T::function(|x : f64| gradient(&FIRE_STOPS, x))
+	}
+}
+
+//every triangle (not just the identity) gets a flat colour from its orientation:
+//the hue goes round with the rotation, and flipped ones are darker
+pub fn orientation_colouring<T: CopyTranslation> (transform: T) -> Box<T::Interval> {
+	let (flipped, rot) = transform.orientation();
+	let hue = gradient(&RAINBOW_STOPS, rot.rem_euclid(T::ROTATIONS) as f64 / T::ROTATIONS as f64);
+	let colour = if flipped {0.3 * hue} else {hue};
+	T::constant(colour)
+}
+
+//every triangle again, coloured by the parity of the split path down to each pixel (see ParityInterval)
+pub fn split_parity_colouring (_transform: EvenD8Translation) -> Box<dyn SplitInterval> {
+	let even = LinearCol::new(1.0, 0.6, 0.0, 0.0);
+	let odd = LinearCol::new(0.0, 0.3, 1.0, 0.0);
+	Box::new(ParityInterval::new(even, odd))
+}
+
+//how deep each piece of colouring's interval settles (see StabilisationInterval), for any of the colourings here.
+//flat colourings settle straight away, and smooth ones like rainbow never do.
+//it's a one split check, so ones that average out the same everywhere (like thue_morse) look flat as well
+pub fn stabilisation_depth(colouring: TriangleColouring, transform: EvenD8Translation) -> Box<dyn SplitInterval> {
+	let unsettled = LinearCol::new(1.0, 1.0, 1.0, 0.0);
+	let palette = |depth: u32| gradient(&FIRE_STOPS, (depth + 1) as f64 / 4.0);
+	Box::new(StabilisationInterval::new(colouring(transform), palette, unsettled))
+}
+
+//the depth two_colouring settles at: the identity's copy a split later than everything else
+pub fn stabilisation_depth_colouring (transform: EvenD8Translation) -> Box<dyn SplitInterval> {
+	stabilisation_depth(two_colouring, transform)
+}
+
+//a repeatable hue for each copy of the base triangle, so neighbouring copies can be told apart.
+//a hand rolled mix rather than std's hasher, whose output isn't promised to stay the same between releases
+fn copy_hue<T: CopyTranslation>(transform: T) -> f64 {
+	let (flipped, rot) = transform.orientation();
+	mixed_hue(transform.get_translation(), (rot as u64) << 1 | flipped as u64)
+}
+
+//the mix itself, for anything with a position and an orientation
+fn mixed_hue((x, y): (isize, isize), orientation: u64) -> f64 {
+	let mut hash = (x as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15)
+		^ (y as u64).wrapping_mul(0xc2b2_ae3d_27d4_eb4f)
+		^ orientation.wrapping_mul(0x1656_67b1_9e37_79f9);
+	hash ^= hash >> 31;
+	hash = hash.wrapping_mul(0xbf58_476d_1ce4_e5b9);
+	hash ^= hash >> 29;
+	(hash >> 11) as f64 / (1u64 << 53) as f64
+}
+
+//every copy of the base triangle gets its own colour, darkening along its interval so the pieces inside each copy still show.
+//shows how the copies from neighbouring tiles fit together across the plane
+pub fn copies_colouring<T: CopyTranslation> (transform: T) -> Box<T::Interval> {
+	let hue = gradient(&RAINBOW_STOPS, copy_hue(transform));
+	T::function(move |x : f64| (1.0 - 0.6 * x) * hue)
+}
+
+//what the two colourings below paint with when nothing else is given (rainbow across, darker down), so there's still something to see
+pub fn default_texture() -> Arc<Texture> {
+	static DEFAULT: OnceLock<Arc<Texture>> = OnceLock::new();
+	DEFAULT.get_or_init(|| Arc::new(Texture::from_fn(64, 64, |u, v| (1.0 - 0.8 * v) * gradient(&RAINBOW_STOPS, u)))).clone()
+}
+
+//the identity's copy painted with the middle row of texture
+pub fn strip_colouring (texture: Arc<Texture>, transform: EvenD8Translation) -> Box<dyn SplitInterval> {
+	let background = LinearCol::new(0.0, 0.0, 0.0, 0.0);
+	if transform != IDENTITY {
+		Box::<ConstantInterval>::new(background.into())
+	} else {
+		Box::new(StripInterval::new(texture, 0.5))
+	}
+}
+
+//the identity's copy with the whole of texture mapped onto it (see TextureInterval)
+pub fn texture_colouring (texture: Arc<Texture>, transform: EvenD8Translation) -> Box<dyn SplitInterval> {
+	let background = LinearCol::new(0.0, 0.0, 0.0, 0.0);
+	if transform != IDENTITY {
+		Box::<ConstantInterval>::new(background.into())
+	} else {
+		Box::new(TextureInterval::new(texture))
+	}
+}
+
+//the identity's copy, with every point whose address starts with 01 (the second quarter) picked out
+pub fn prefix_colouring (transform: EvenD8Translation) -> Box<dyn SplitInterval> {
+	let background = LinearCol::new(0.0, 0.0, 0.0, 0.0);
+	if transform != IDENTITY {
+		return Box::<ConstantInterval>::new(background.into());
+	}
+	let rules = interval_rules::prefix_rules(&[("01", LinearCol::new(1.0, 0.8, 0.0, 0.0))], LinearCol::new(0.1, 0.1, 0.4, 0.0));
+	Box::new(Rc::new(rules.expect("the rules above are valid (40301)")).interval())
+}
+
+//the identity's copy coloured along the first 256 symbols of the thue-morse sequence.
+//any further and every piece would be the same half and half mix (see interval_rules::substitution)
+pub fn thue_morse_colouring (transform: EvenD8Translation) -> Box<dyn SplitInterval> {
+	let background = LinearCol::new(0.0, 0.0, 0.0, 0.0);
+	if transform != IDENTITY {
+		return Box::<ConstantInterval>::new(background.into());
 	}
+	let rules = interval_rules::substitution(&[
+		('a', "ab", LinearCol::new(0.02, 0.02, 0.15, 0.0)),
+		('b', "ba", LinearCol::new(1.0, 0.8, 0.1, 0.0)),
+	], 'a', Some(8));
+	Box::new(Rc::new(rules.expect("the rules above are valid (40302)")).interval())
+}
+
+//what every colouring above looks like
+pub type TriangleColouring = fn(EvenD8Translation) -> Box<dyn SplitInterval>;
+
+//the colourings above, by name
+pub static COLOURINGS: [(&str, TriangleColouring); 13] = [
+	("simple", simple_colouring),
+	("two", two_colouring),
+	("continuum", simple_continuum_colouring),
+	("rainbow", rainbow_colouring),
+	("fire", fire_colouring),
+	("orientation", orientation_colouring),
+	("parity", split_parity_colouring),
+	("depth", stabilisation_depth_colouring),
+	("copies", copies_colouring),
+	("strip", |transform| strip_colouring(default_texture(), transform)),
+	("texture", |transform| texture_colouring(default_texture(), transform)),
+	("prefix", prefix_colouring),
+	("thue-morse", thue_morse_colouring),
+];
+
+//the ones that paint with a texture, which can be given one instead of default_texture (see scene::Scene::triangle_colouring)
+pub type TexturedColouring = fn(Arc<Texture>, EvenD8Translation) -> Box<dyn SplitInterval>;
+
+pub static TEXTURED_COLOURINGS: [(&str, TexturedColouring); 2] = [
+	("strip", strip_colouring),
+	("texture", texture_colouring),
+];
+
+pub fn colouring(name: &str) -> Option<TriangleColouring> {
+	COLOURINGS
+		.iter()
+		.find(|(colouring_name, _)| *colouring_name == name)
+		.map(|(_, colouring)| *colouring)
 }
 
 pub fn dense_square_config (
@@ -1,63 +1,158 @@
-use super::auxiliary::{WorkingMessage, WorkingState};
-use super::fractal_worker;
+use super::controls::{self, AppState};
+use super::dithering::Dithering;
+use super::fractal_worker::{self, RenderSettings};
+use super::image_output;
+use super::pixel_format::PixelFormat;
+use super::pixel_sink::{DamageRect, DamageSink};
+use super::progress::{Progress, ProgressSink, ProgressTracker};
 
 use druid::*;
-use std::collections::HashMap;
-use std::sync::mpsc::Receiver;
-use std::sync::{Arc, Mutex, mpsc};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, Sender};
+use std::sync::{Arc, mpsc};
 use std::{thread,time};
 
 const REFRESH_TIME: u64  = 1_000_000 / 10; //microseconds
+//the picture on screen is a grid of images this size, so that new chunks only mean remaking the few images they land in
+const DISPLAY_TILE_SIZE: usize = 256;
+
+//asks the canvas to save a png. The payload is how many times bigger than the window to make it.
+//1 saves the window's own buffer once it's finished, anything else re-renders the view in the background as an undithered 16 bit png
+pub const SAVE_IMAGE: Selector<usize> = Selector::new("dimers.save-image");
+//where the save dialog says to put it
+const SAVE_IMAGE_TO: Selector<FileInfo> = Selector::new("dimers.save-image-to");
+//the canvas telling itself how big it's been laid out, so the size can go into the AppState
+const CANVAS_RESIZED: Selector<(usize, usize)> = Selector::new("dimers.canvas-resized");
 
 pub struct FractalBuilder {
-	shared_canvas: Arc<Mutex<Vec<u8>>>, // pixelbuffer seer
-	own_canvas: Vec<u8>,
+	own_canvas: Vec<u8>, // only ever touched by the ui, the worker sends it finished pieces
 	width: usize,
 	height: usize,
 	format: piet::ImageFormat,
-	receiver: Receiver<((usize, usize, usize, usize), WorkingMessage)>,
-	working_chunks: HashMap<(usize, usize, usize, usize), WorkingState>,
+	dithering: Dithering,
+	settings: RenderSettings,
+	receiver: Receiver<Vec<DamageRect>>,
+	dirty_tiles: Vec<bool>, // display tiles that own_canvas has changed under since they were last made
+	worker_done: bool, // the worker hung up its sender
+	progress: Arc<ProgressTracker>,
+	cancel: Arc<AtomicBool>, // tells the worker to give up
 	_worker: thread::JoinHandle<()>
 }
 
 impl FractalBuilder {
-	pub fn new(width: usize, height: usize, image_format: piet::ImageFormat) -> Self {
+	pub fn new(width: usize, height: usize, image_format: piet::ImageFormat, dithering: Dithering, settings: RenderSettings) -> Self {
+		FractalBuilder::with_regions(width, height, image_format, dithering, settings, vec![(0, 0, width, height)])
+	}
+
+	//regions as in fractal_worker::start
+	fn with_regions(
+		width: usize,
+		height: usize,
+		image_format: piet::ImageFormat,
+		dithering: Dithering,
+		settings: RenderSettings,
+		regions: Vec<(usize, usize, usize, usize)>
+	) -> Self {
 		let size: usize = image_format.bytes_per_pixel() * width * height;
 		let own_canvas = vec![0; size];
-		let working_chunks = HashMap::new();
-		let buffer: Vec<u8> = vec![0; size];
-		let shared_canvas = Arc::new(Mutex::new(buffer));
-		let canvas_clone = shared_canvas.clone();
+		let tile_count = width.div_ceil(DISPLAY_TILE_SIZE) * height.div_ceil(DISPLAY_TILE_SIZE);
+		let cancel = Arc::new(AtomicBool::new(false));
+		//the regions cover the canvas exactly once, so every pixel gets counted once
+		let progress = Arc::new(ProgressTracker::new(width * height));
 
-		let (sender, receiver) = mpsc::channel::<((usize, usize, usize, usize), WorkingMessage)>();
+		let (sender, receiver) = mpsc::channel::<Vec<DamageRect>>();
+
+		let sink = ProgressSink::new(
+			DamageSink::new(image_format.into(), dithering, sender, cancel.clone()),
+			progress.clone(),
+		);
 		
 		let worker = thread::spawn(move || {
-			fractal_worker::start(width, height, image_format, canvas_clone, sender); //this is where you call the actual builder
+			fractal_worker::start(width, height, settings, &regions, sink); //this is where you call the actual builder
 		});
 		
 		FractalBuilder {
-			shared_canvas,
 			own_canvas,
 			width,
 			height,
 			format: image_format,
+			dithering,
+			settings,
 			receiver,
-			working_chunks,
+			dirty_tiles: vec![true; tile_count],
+			worker_done: false,
+			progress,
+			cancel,
 			_worker: worker
 		}
 	}
-	
-	pub fn receive_chunks(&mut self) {
-		loop {
-			let recv_result = self.receiver.try_recv();
 
-			match recv_result {
-				Ok ((bounds, WorkingMessage::Begin)) => {
-					self.working_chunks.insert(bounds, WorkingState::Working);
-				},
+	//throws away the current render and starts a new one.
+	//if the size stays the same, the old picture stays up until the new one draws over it, so there's no flash of black.
+	pub fn restart(&mut self, width: usize, height: usize, settings: RenderSettings) {
+		let old_canvas = std::mem::take(&mut self.own_canvas);
+		let same_size = (width, height) == (self.width, self.height);
+		*self = FractalBuilder::new(width, height, self.format, self.dithering, settings);
+		if same_size {
+			self.own_canvas = old_canvas;
+		}
+	}
+
+	//the same picture on a different sized canvas, with the origin staying put relative to the middle.
+	//whatever was already drawn is kept (the render it came from was for the same picture),
+	//and the worker starts on the parts that weren't on screen before.
+	pub fn resize(&mut self, width: usize, height: usize) {
+		let old_width = self.width;
+		let old_height = self.height;
+		let old_canvas = std::mem::take(&mut self.own_canvas);
+
+		//where the old canvas lands on the new one
+		let shift_x = (width/2) as isize - (old_width/2) as isize;
+		let shift_y = (height/2) as isize - (old_height/2) as isize;
+		let kept = (
+			shift_x.clamp(0, width as isize) as usize,
+			shift_y.clamp(0, height as isize) as usize,
+			(old_width as isize + shift_x).clamp(0, width as isize) as usize,
+			(old_height as isize + shift_y).clamp(0, height as isize) as usize,
+		);
+
+		let regions = exposed_first(width, height, kept);
+		*self = FractalBuilder::with_regions(width, height, self.format, self.dithering, self.settings, regions);
+
+		let colour_depth = self.format.bytes_per_pixel();
+		for y in kept.1 .. kept.3 {
+			let old_y = (y as isize - shift_y) as usize;
+			let old_x = (kept.0 as isize - shift_x) as usize;
+			let old_start = (old_y * old_width + old_x) * colour_depth;
+			let new_start = (y * width + kept.0) * colour_depth;
+			let length = (kept.2 - kept.0) * colour_depth;
+			self.own_canvas[new_start .. new_start + length].copy_from_slice(&old_canvas[old_start .. old_start + length]);
+		}
+	}
+
+	pub fn get_progress(&self) -> Progress {
+		self.progress.snapshot()
+	}
 
-				Ok ((bounds, WorkingMessage::End)) => {
-					self.working_chunks.insert(bounds, WorkingState::Finished);
+	pub fn get_settings(&self) -> RenderSettings {
+		self.settings
+	}
+	
+	//copies whatever the worker has finished into own_canvas.
+	//returns the area that changed, if anything did
+	pub fn receive_damage(&mut self) -> Option<Rect> {
+		let mut damaged: Option<Rect> = None;
+		loop {
+			match self.receiver.try_recv() {
+				Ok (damage) => {
+					for rect in damage {
+						self.apply_damage(&rect);
+						let (left, top, right, bottom) = rect.bounds;
+						let area = Rect::new(left as f64, top as f64, right as f64, bottom as f64);
+						damaged = Some(damaged.map_or(area, |damaged| damaged.union(area)));
+					}
 				},
 
 				Err (mpsc::TryRecvError::Empty) => {
@@ -65,39 +160,69 @@ impl FractalBuilder {
 				},
 
 				Err (mpsc::TryRecvError::Disconnected) => {
+					self.worker_done = true;
 					break;
 				}, //this happens after the builder finishes its work
 			}
 		}
-
+		damaged
 	}
 
-	pub fn update_interior(&mut self) {
-
-		let mut finished_chunks: Vec<(usize, usize, usize, usize)> = vec![];
+	fn apply_damage(&mut self, rect: &DamageRect) {
+		let colour_depth = self.format.bytes_per_pixel();
+		let (left, top, right, bottom) = rect.bounds;
+		let row_length = (right - left) * colour_depth;
+		for y in top .. bottom {
+			let position = ((y * self.width) + left) * colour_depth;
+			let source = (y - top) * row_length;
+			self.own_canvas[position .. position + row_length].copy_from_slice(&rect.pixels[source .. source + row_length]);
+		}
 
-		for (bounds, state) in &self.working_chunks {
-			if *state == WorkingState::Finished {
-				finished_chunks.push(*bounds);
+		let tiles_across = self.tiles_across();
+		for tile_y in top / DISPLAY_TILE_SIZE ..= (bottom - 1) / DISPLAY_TILE_SIZE {
+			for tile_x in left / DISPLAY_TILE_SIZE ..= (right - 1) / DISPLAY_TILE_SIZE {
+				self.dirty_tiles[tile_y * tiles_across + tile_x] = true;
 			}
+		}
+	}
 
-			let colour_depth = self.format.bytes_per_pixel();
+	pub fn tiles_across(&self) -> usize {
+		self.width.div_ceil(DISPLAY_TILE_SIZE)
+	}
 
-			let guard = self.shared_canvas.lock().expect("lock poisoned");
-			for y in bounds.1 .. bounds.3 {
-				for x in bounds.0 .. bounds.2 {
-					let position = ((y * self.width) + x) * colour_depth;
-					for byte in 0 .. colour_depth {
-						self.own_canvas[position + byte] = guard[position + byte];
-					}
-				}
-			}
-			drop(guard);
-		}
+	pub fn tile_count(&self) -> usize {
+		self.dirty_tiles.len()
+	}
+
+	//the display tiles that need making again, which then count as clean
+	pub fn take_dirty_tiles(&mut self) -> Vec<usize> {
+		let dirty = self.dirty_tiles
+			.iter()
+			.enumerate()
+			.filter(|(_, &dirty)| dirty)
+			.map(|(index, _)| index)
+			.collect();
+		self.dirty_tiles.iter_mut().for_each(|dirty| *dirty = false);
+		dirty
+	}
+
+	//(left, top, right, bottom) of a display tile, cut short at the canvas edges
+	pub fn tile_bounds(&self, index: usize) -> (usize, usize, usize, usize) {
+		let left = (index % self.tiles_across()) * DISPLAY_TILE_SIZE;
+		let top = (index / self.tiles_across()) * DISPLAY_TILE_SIZE;
+		(left, top, (left + DISPLAY_TILE_SIZE).min(self.width), (top + DISPLAY_TILE_SIZE).min(self.height))
+	}
 
-		for chunk in &finished_chunks {
-			self.working_chunks.remove(chunk);
+	//own_canvas's bytes inside bounds, row by row
+	pub fn bytes_within(&self, bounds: (usize, usize, usize, usize)) -> Vec<u8> {
+		let colour_depth = self.format.bytes_per_pixel();
+		let (left, top, right, bottom) = bounds;
+		let mut bytes = Vec::with_capacity((right - left) * (bottom - top) * colour_depth);
+		for y in top .. bottom {
+			let position = ((y * self.width) + left) * colour_depth;
+			bytes.extend_from_slice(&self.own_canvas[position .. position + (right - left) * colour_depth]);
 		}
+		bytes
 	}
 	
 	pub fn get_width(&self) -> usize {
@@ -111,40 +236,146 @@ impl FractalBuilder {
 	pub fn get_format(&self) -> piet::ImageFormat {
 		self.format
 	}
+
+	//true once every chunk the worker drew has made it into own_canvas
+	pub fn is_finished(&self) -> bool {
+		self.worker_done
+	}
+
+	//writes own_canvas out as it is, finished or not
+	pub fn save_png(&self, path: &Path) -> io::Result<()> {
+		image_output::save_image(path, self.width, self.height, PixelFormat::from(self.format), &self.own_canvas)
+	}
+}
+
+//splits a width x height canvas into the strips around kept (top, bottom, left, right), and then kept itself last
+fn exposed_first(width: usize, height: usize, kept: (usize, usize, usize, usize)) -> Vec<(usize, usize, usize, usize)> {
+	let (left, top, right, bottom) = kept;
+	if left >= right || top >= bottom {
+		//nothing survived
+		return vec![(0, 0, width, height)];
+	}
+
+	let regions = vec![
+		(0, 0, width, top),
+		(0, bottom, width, height),
+		(0, top, left, bottom),
+		(right, top, width, bottom),
+		kept,
+	];
+	regions
+		.into_iter()
+		.filter(|region| region.0 < region.2 && region.1 < region.3)
+		.collect()
+}
+
+impl Drop for FractalBuilder {
+	fn drop(&mut self) {
+		//the worker notices at its next chunk, and the thread finishes on its own
+		self.cancel.store(true, Ordering::Relaxed);
+	}
 }
 
 
 pub struct FractalCanvas {
 	builder: FractalBuilder,
 	frame_timer: Option<TimerToken>,
+	save_factor: usize, // what the open save dialog is for
+	queued_save: Option<PathBuf>, // waiting for the render to finish
+	save_sender: Sender<String>, // handed to save threads, to say how it went
+	save_results: Receiver<String>, // read every tick, into the panel's status line
+	laid_out: (usize, usize), // the size layout last gave it
+	tiles: Vec<Option<piet::PietImage>>, // what's on screen, made from the builder's display tiles
 }
 
 impl FractalCanvas {
 	pub fn new(builder: FractalBuilder) -> FractalCanvas {
-		FractalCanvas{builder, frame_timer: None}
+		let laid_out = (builder.get_width(), builder.get_height());
+		let (save_sender, save_results) = mpsc::channel();
+		FractalCanvas{builder, frame_timer: None, save_factor: 1, queued_save: None, save_sender, save_results, laid_out, tiles: Vec::new()}
+	}
+
+	fn save(&mut self, path: PathBuf) {
+		if self.save_factor == 1 {
+			//the window's own picture, as soon as it's all there
+			self.queued_save = Some(path);
+			self.try_queued_save();
+			return;
+		}
+
+		//a bigger picture has to be drawn from scratch, at full precision. Don't hold up the window for it.
+		let (width, height) = (self.builder.get_width(), self.builder.get_height());
+		let settings = self.builder.get_settings();
+		let factor = self.save_factor;
+		let results = self.save_sender.clone();
+		thread::spawn(move || {
+			let image = fractal_worker::render_enlarged(width, height, settings, factor);
+			let message = match image.save(&path, PixelFormat::Rgba16, Dithering::None) {
+				Ok(()) => format!("saved {}", path.display()),
+				Err(error) => format!("couldn't save {}: {}", path.display(), error),
+			};
+			//the window might have closed by now, and then there's no one to tell
+			let _ = results.send(message);
+		});
+	}
+
+	fn try_queued_save(&mut self) {
+		if !self.builder.is_finished() {
+			return;
+		}
+		if let Some(path) = self.queued_save.take() {
+			let message = match self.builder.save_png(&path) {
+				Ok(()) => format!("saved {}", path.display()),
+				Err(error) => format!("couldn't save {}: {}", path.display(), error),
+			};
+			//read out into the status line along with the bigger saves' messages
+			let _ = self.save_sender.send(message);
+		}
 	}
 }
 
-impl Widget<()> for FractalCanvas {
+impl Widget<AppState> for FractalCanvas {
 	fn event(
 		&mut self,
 		ctx: &mut EventCtx,
 		event: &Event,
-		_data: &mut (),
+		data: &mut AppState,
 		_env: &Env
 	) {
 		match event {
 			Event::WindowConnected => {self.frame_timer = Some(ctx.request_timer(time::Duration::ZERO));},
-			Event::Timer(token) => {
-				match self.frame_timer {
-					Some(timer_token) => if &timer_token == token {
-						self.builder.update_interior();
-						self.builder.receive_chunks();
-						ctx.request_paint();
-						ctx.request_timer(time::Duration::from_micros(REFRESH_TIME));
-					}
-					None => {},
+			Event::Timer(token) if self.frame_timer.as_ref() == Some(token) => {
+				if let Some(damaged) = self.builder.receive_damage() {
+					ctx.request_paint_rect(damaged);
+				}
+				self.try_queued_save();
+				while let Ok(message) = self.save_results.try_recv() {
+					data.status = message;
 				}
+				let progress = self.builder.get_progress();
+				data.progress = progress.fraction();
+				data.progress_text = progress.eta();
+				//every request gets a new token, so keep hold of it or the next tick gets ignored
+				self.frame_timer = Some(ctx.request_timer(time::Duration::from_micros(REFRESH_TIME)));
+			},
+			Event::Command(command) if command.is(SAVE_IMAGE) => {
+				self.save_factor = *command.get_unchecked(SAVE_IMAGE);
+				let png = FileSpec::new("PNG image", &["png"]);
+				let options = FileDialogOptions::new()
+					.allowed_types(vec![png])
+					.default_type(png)
+					.default_name("dimers.png")
+					.accept_command(SAVE_IMAGE_TO);
+				ctx.submit_command(commands::SHOW_SAVE_PANEL.with(options));
+			},
+			Event::Command(command) if command.is(CANVAS_RESIZED) => {
+				let (width, height) = *command.get_unchecked(CANVAS_RESIZED);
+				data.width = width;
+				data.height = height;
+			},
+			Event::Command(command) if command.is(SAVE_IMAGE_TO) => {
+				let path = command.get_unchecked(SAVE_IMAGE_TO).path().to_path_buf();
+				self.save(path);
 			},
 			_ => ()
 		}
@@ -152,61 +383,89 @@ impl Widget<()> for FractalCanvas {
 	
 	fn lifecycle(
 		&mut self,
-		_ctx: &mut LifeCycleCtx,
-		_event: &LifeCycle,
-		_data: &(),
+		ctx: &mut LifeCycleCtx,
+		event: &LifeCycle,
+		_data: &AppState,
 		_env: &Env
 	) {
-		()
+		if let LifeCycle::Size(size) = event {
+			//an empty canvas can't be turned into an image, so never go all the way down to nothing
+			let width = (size.width.round() as usize).max(1);
+			let height = (size.height.round() as usize).max(1);
+			self.laid_out = (width, height);
+			ctx.submit_command(CANVAS_RESIZED.with((width, height)).to(ctx.widget_id()));
+		}
 	}
 	
 	fn update(
 		&mut self,
-		_ctx: &mut UpdateCtx,
-		_old_data: &(),
-		_data: &(),
+		ctx: &mut UpdateCtx,
+		old_data: &AppState,
+		data: &AppState,
 		_env: &Env
 	) {
-		()
+		if data.only_resizes(old_data) {
+			self.builder.resize(data.width, data.height);
+			ctx.request_paint();
+		} else if data.changes_render(old_data) {
+			self.builder.restart(data.width, data.height, data.render_settings());
+			ctx.request_paint();
+		}
+
+		//a scene asked for a different size than the window has room for, so make the room
+		if (data.width, data.height) != self.laid_out {
+			let size = (data.width as f64 + controls::PANEL_WIDTH, data.height as f64);
+			ctx.window().set_size(size);
+		}
 	}
 	
 	fn layout(
 		&mut self,
 		_ctx: &mut LayoutCtx,
 		bc: &BoxConstraints,
-		_data: &(),
+		_data: &AppState,
 		_env: &Env
 	) -> Size {
-		bc.constrain(Size::new(
-			self.builder.get_width() as f64,
-			self.builder.get_height() as f64
-		))
+		//all the room there is, as long as there's a limit to it
+		if bc.is_width_bounded() && bc.is_height_bounded() {
+			bc.max()
+		} else {
+			bc.constrain(Size::new(
+				self.builder.get_width() as f64,
+				self.builder.get_height() as f64
+			))
+		}
 	}
 	
 	fn paint(
 		&mut self,
 		ctx: &mut PaintCtx,
-		_data: &(),
+		_data: &AppState,
 		_env: &Env
 	) {
-		let image = {
-			let width = self.builder.get_width();
-			let height = self.builder.get_height();
-			let format = self.builder.get_format();
-			let buf: &[u8] = &self.builder.own_canvas;
-			//slow step? It must be, since it's the only thing I do while the canvas is still locked.
-			ctx.make_image(width, height, buf, format).expect("Failed to convert image: 16389")
-		};
-		
-		ctx.draw_image(
-			&image,
-			Rect::new(
-				0.,
-				0.,
-				self.builder.width as f64,
-				self.builder.height as f64
-			),
-			piet::InterpolationMode::Bilinear
-		);
+		//a new builder (after a restart or resize) can have a different grid, and starts out all dirty anyway
+		if self.tiles.len() != self.builder.tile_count() {
+			self.tiles = (0..self.builder.tile_count()).map(|_| None).collect();
+		}
+
+		//only the tiles something landed in get made again
+		let format = self.builder.get_format();
+		for index in self.builder.take_dirty_tiles() {
+			let bounds = self.builder.tile_bounds(index);
+			let bytes = self.builder.bytes_within(bounds);
+			let image = ctx.make_image(bounds.2 - bounds.0, bounds.3 - bounds.1, &bytes, format).expect("Failed to convert image: 16389");
+			self.tiles[index] = Some(image);
+		}
+
+		for (index, tile) in self.tiles.iter().enumerate() {
+			if let Some(image) = tile {
+				let (left, top, right, bottom) = self.builder.tile_bounds(index);
+				ctx.draw_image(
+					image,
+					Rect::new(left as f64, top as f64, right as f64, bottom as f64),
+					piet::InterpolationMode::NearestNeighbor
+				);
+			}
+		}
 	}
 }
\ No newline at end of file
@@ -29,22 +29,40 @@ pub struct LinearCol {
 }
 
 impl LinearCol {
-	pub fn new(r: f64, g: f64, b: f64, tau: f64) -> LinearCol { LinearCol{r, g, b, tau} }
+	pub const fn new(r: f64, g: f64, b: f64, tau: f64) -> LinearCol { LinearCol{r, g, b, tau} }
 
 	//WARNING: alpha premultiplied
 	pub fn r_lin(self) -> f64 {self.r}
 	pub fn g_lin(self) -> f64 {self.g}
 	pub fn b_lin(self) -> f64 {self.b}
 	pub fn y_lin(self) -> f64 {0.2162 * self.r + 0.7152 * self.g + 0.0722 * self.b}
+	pub fn alpha(self) -> f64 {1.0 - self.tau}
 	
 	pub fn grayscale(self) -> u8 {
+		self.grayscale_dithered(None)
+	}
+	
+	pub fn rgb(self) -> (u8, u8, u8) {
+		self.rgb_dithered(None)
+	}
+	
+	pub fn rgba_separate(self) -> (u8, u8, u8, u8) {
+		self.rgba_separate_dithered(None)
+	}
+	
+	pub fn rgba_premul(self) -> (u8, u8, u8, u8) {
+		self.rgba_premul_dithered(None)
+	}
+
+	//the _dithered versions take a threshold from dithering::Dithering::threshold (None for plain truncation)
+	pub fn grayscale_dithered(self, threshold: Option<f64>) -> u8 {
 		let alpha = 1.0 - self.tau;
 		let y_normalized = self.y_lin() / alpha; //fails if fully transparent, but what else is new?
 		let y_srgb = to_srgb(y_normalized);
-		(y_srgb * 256.0) as u8
+		to_byte(y_srgb, threshold)
 	}
 	
-	pub fn rgb(self) -> (u8, u8, u8) {
+	pub fn rgb_dithered(self, threshold: Option<f64>) -> (u8, u8, u8) {
 		let alpha = 1.0 - self.tau;
 		let r_normalized = self.r / alpha;
 		let g_normalized = self.g / alpha;
@@ -53,13 +71,13 @@ impl LinearCol {
 		let g_srgb = to_srgb(g_normalized);
 		let b_srgb = to_srgb(b_normalized);
 		(
-			(r_srgb * 256.0) as u8,
-			(g_srgb * 256.0) as u8,
-			(b_srgb * 256.0) as u8,
+			to_byte(r_srgb, threshold),
+			to_byte(g_srgb, threshold),
+			to_byte(b_srgb, threshold),
 		)
 	}
 	
-	pub fn rgba_separate(self) -> (u8, u8, u8, u8) {
+	pub fn rgba_separate_dithered(self, threshold: Option<f64>) -> (u8, u8, u8, u8) {
 		let alpha = 1.0 - self.tau;
 
 		let r_normalized;
@@ -80,24 +98,67 @@ impl LinearCol {
 		let g_srgb = to_srgb(g_normalized);
 		let b_srgb = to_srgb(b_normalized);
 		(
-			(r_srgb * 256.0) as u8,
-			(g_srgb * 256.0) as u8,
-			(b_srgb * 256.0) as u8,
-			(alpha  * 256.0) as u8,
+			to_byte(r_srgb, threshold),
+			to_byte(g_srgb, threshold),
+			to_byte(b_srgb, threshold),
+			to_byte(alpha , threshold),
+		)
+	}
+
+	//same as rgba_separate, but with 16 bits per channel. No dithering here, since there's nothing to band at this depth.
+	pub fn rgba_separate_16(self) -> (u16, u16, u16, u16) {
+		let alpha = 1.0 - self.tau;
+
+		let (r_normalized, g_normalized, b_normalized) = if alpha != 0.0 {
+			(self.r / alpha, self.g / alpha, self.b / alpha)
+		} else {
+			(0.0, 0.0, 0.0)
+		};
+
+		(
+			(to_srgb(r_normalized) * 65536.0) as u16,
+			(to_srgb(g_normalized) * 65536.0) as u16,
+			(to_srgb(b_normalized) * 65536.0) as u16,
+			(alpha                 * 65536.0) as u16,
 		)
 	}
 	
-	pub fn rgba_premul(self) -> (u8, u8, u8, u8) {
+	pub fn rgba_premul_dithered(self, threshold: Option<f64>) -> (u8, u8, u8, u8) {
 		//never mind, druid's rgba_premul is jank
 		//it somehow has the worst of both worlds: it is gamma compressed AND deals in alpha or something
 		let alpha = 1.0 - self.tau;
 		(
-			(self.r * 256.0) as u8,
-			(self.g * 256.0) as u8,
-			(self.b * 256.0) as u8,
-			(alpha  * 256.0) as u8,
+			to_byte(self.r, threshold),
+			to_byte(self.g, threshold),
+			to_byte(self.b, threshold),
+			to_byte(alpha , threshold),
 		)
 	}
+
+	//turned round the grey axis, a whole turn being 1.0. It's linear, so premultiplying doesn't get in the way,
+	//but the corners of the colour cube end up outside it (and get clipped back to no light at all)
+	pub fn hue_rotated(self, turns: f64) -> LinearCol {
+		let (sin, cos) = (turns * std::f64::consts::TAU).sin_cos();
+		let same = (1.0 + 2.0 * cos) / 3.0;
+		let ahead = (1.0 - cos) / 3.0 + sin / 3f64.sqrt();
+		let behind = (1.0 - cos) / 3.0 - sin / 3f64.sqrt();
+		LinearCol {
+			r: (same * self.r + behind * self.g + ahead * self.b).max(0.0),
+			g: (ahead * self.r + same * self.g + behind * self.b).max(0.0),
+			b: (behind * self.r + ahead * self.g + same * self.b).max(0.0),
+			tau: self.tau,
+		}
+	}
+}
+
+//without a threshold, this is the same truncation as always.
+//with one, 255 levels get spread so that averaging over the dither pattern gets you back to v.
+//(the "as u8" saturates, so out of range values are fine either way)
+fn to_byte(v: f64, threshold: Option<f64>) -> u8 {
+	match threshold {
+		None => (v * 256.0) as u8,
+		Some(threshold) => (v * 255.0 + threshold) as u8,
+	}
 }
 
 //I had the option to treat users of this module like babies and not give them any of the following methods, but I didn't.
@@ -108,10 +169,10 @@ impl Add<LinearCol> for LinearCol {
 	
 	fn add(self, rhs: LinearCol) -> Self::Output {
 		LinearCol{
-			r: (self.r + rhs.r) as f64,
-			g: (self.g + rhs.g) as f64,
-			b: (self.b + rhs.b) as f64,
-			tau: (self.tau + rhs.tau) as f64,
+			r: self.r + rhs.r,
+			g: self.g + rhs.g,
+			b: self.b + rhs.b,
+			tau: self.tau + rhs.tau,
 		}
 	}
 }
@@ -149,10 +210,10 @@ impl Sub<LinearCol> for LinearCol {
 	
 	fn sub(self, rhs: LinearCol) -> Self::Output {
 		LinearCol {
-			r: (self.r - rhs.r) as f64,
-			g: (self.g - rhs.g) as f64,
-			b: (self.b - rhs.b) as f64,
-			tau: (self.tau - rhs.tau) as f64,
+			r: self.r - rhs.r,
+			g: self.g - rhs.g,
+			b: self.b - rhs.b,
+			tau: self.tau - rhs.tau,
 		}
 	}
 }
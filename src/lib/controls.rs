@@ -0,0 +1,231 @@
+use druid::widget::{Flex, Label, LineBreaking, ProgressBar, RadioGroup, Slider, CrossAxisAlignment};
+use druid::{Data, Lens, Widget, WidgetExt, Env, LocalizedString, MenuDesc, MenuItem, SysMods};
+use druid::{AppDelegate, Command, DelegateCtx, FileDialogOptions, FileInfo, FileSpec, Handled, Selector, Target, commands};
+
+use super::auxiliary::COLOURINGS;
+use super::canvas::SAVE_IMAGE;
+use super::dithering::Dithering;
+use super::fractal_specification::PRESETS;
+use super::fractal_worker::RenderSettings;
+use super::pixel_format::PixelFormat;
+use super::scene::{CustomFractal, Scene, SceneFractal, SceneTexture};
+
+pub const PANEL_WIDTH: f64 = 160.0;
+pub const MAX_SCALE: f64 = 12.0;
+
+//how the canvas shows the picture (and so what a scene saved from the gui asks for)
+pub const DISPLAY_FORMAT: PixelFormat = PixelFormat::RgbaSeparate;
+//dithering is opt in (from the command line or a scene file), so the gui shows plain truncated bytes (see colour_format::to_byte)
+pub const DISPLAY_DITHERING: Dithering = Dithering::None;
+
+//sent by the file dialogs the menu opens
+pub const OPEN_SCENE: Selector<FileInfo> = Selector::new("dimers.open-scene");
+pub const SAVE_SCENE: Selector<FileInfo> = Selector::new("dimers.save-scene");
+const SCENE_FILES: FileSpec = FileSpec::new("Dimers scene", &["scene"]);
+
+//a scene::Scene, in a shape the widgets can work with.
+//fractal and colouring are indices into fractal_specification::PRESETS and auxiliary::COLOURINGS.
+//fractal is PRESETS.len() when it's custom_fractal instead (which no radio button matches).
+#[derive(Clone, Data, Lens)]
+pub struct AppState {
+	pub fractal: usize,
+	pub colouring: usize,
+	pub scale: f64, //the slider's raw value. Only whole numbers mean anything.
+	pub width: usize,
+	pub height: usize,
+	pub offset: (isize, isize), //as in RenderSettings
+	pub antialiasing: usize,
+	#[data(same_fn = "PartialEq::eq")]
+	pub custom_fractal: Option<CustomFractal>,
+	//the canvas paints with the default texture (see Scene::render_settings), but a loaded scene's is kept for saving it again
+	#[data(same_fn = "PartialEq::eq")]
+	pub texture: Option<SceneTexture>,
+	//not part of the scene, just how the current render is getting on (the canvas keeps these up to date)
+	pub progress: f64,
+	pub progress_text: String,
+	//what the last image or scene save (or scene load) said, since there's nowhere else in the window to say it
+	pub status: String,
+}
+
+impl AppState {
+	pub fn from_scene(scene: &Scene) -> AppState {
+		let (fractal, custom_fractal) = match scene.fractal {
+			SceneFractal::Preset(index) => (index, None),
+			SceneFractal::Custom(custom) => (PRESETS.len(), Some(custom)),
+		};
+		AppState {
+			fractal,
+			colouring: scene.colouring,
+			scale: scene.scale as f64,
+			width: scene.width,
+			height: scene.height,
+			offset: (
+				scene.origin.0 - (scene.width/2) as isize,
+				scene.origin.1 - (scene.height/2) as isize,
+			),
+			antialiasing: scene.antialiasing,
+			custom_fractal,
+			texture: scene.texture.clone(),
+			progress: 0.0,
+			progress_text: String::new(),
+			status: String::new(),
+		}
+	}
+
+	//exactly what the canvas shows
+	pub fn to_scene(&self) -> Scene {
+		let fractal = match self.custom_fractal {
+			Some(custom) if self.fractal == PRESETS.len() => SceneFractal::Custom(custom),
+			_ => SceneFractal::Preset(self.fractal),
+		};
+		Scene {
+			fractal,
+			colouring: self.colouring,
+			width: self.width,
+			height: self.height,
+			origin: (
+				(self.width/2) as isize + self.offset.0,
+				(self.height/2) as isize + self.offset.1,
+			),
+			scale: self.scale.round() as u32,
+			antialiasing: self.antialiasing,
+			format: DISPLAY_FORMAT,
+			dithering: DISPLAY_DITHERING,
+			texture: self.texture.clone(),
+		}
+	}
+
+	pub fn render_settings(&self) -> RenderSettings {
+		self.to_scene().render_settings()
+	}
+
+	//whether going from old to self is just the canvas changing size, with the origin staying put relative to the middle
+	pub fn only_resizes(&self, old: &AppState) -> bool {
+		let mut resized = old.to_scene();
+		resized.resize(self.width, self.height);
+		(self.width, self.height) != (old.width, old.height) && resized == self.to_scene()
+	}
+
+	//whether going from old to self means the picture has to be drawn again
+	pub fn changes_render(&self, old: &AppState) -> bool {
+		self.to_scene() != old.to_scene()
+	}
+}
+
+impl Default for AppState {
+	fn default() -> Self {
+		AppState::from_scene(&Scene::default())
+	}
+}
+
+//loads and saves scenes for the whole window
+pub struct Delegate;
+
+impl AppDelegate<AppState> for Delegate {
+	fn command(
+		&mut self,
+		_ctx: &mut DelegateCtx,
+		_target: Target,
+		command: &Command,
+		data: &mut AppState,
+		_env: &Env
+	) -> Handled {
+		if let Some(file) = command.get(OPEN_SCENE) {
+			match Scene::load(file.path()) {
+				Ok((scene, _)) => *data = AppState::from_scene(&scene),
+				Err(message) => data.status = format!("couldn't open {}: {}", file.path().display(), message),
+			}
+			Handled::Yes
+		} else if let Some(file) = command.get(SAVE_SCENE) {
+			data.status = match data.to_scene().save(file.path()) {
+				Ok(()) => format!("saved {}", file.path().display()),
+				Err(error) => format!("couldn't save {}: {}", file.path().display(), error),
+			};
+			Handled::Yes
+		} else {
+			Handled::No
+		}
+	}
+}
+
+//the picker that sits next to the canvas
+pub fn build_side_panel() -> impl Widget<AppState> {
+	let fractal_picker = RadioGroup::new(
+		PRESETS.iter().enumerate().map(|(index, (name, _))| (*name, index))
+	).lens(AppState::fractal);
+
+	let colouring_picker = RadioGroup::new(
+		COLOURINGS.iter().enumerate().map(|(index, (name, _))| (*name, index))
+	).lens(AppState::colouring);
+
+	let scale_label = Label::new(|data: &AppState, _env: &Env| format!("Scale: {}", data.scale.round()));
+	let scale_slider = Slider::new()
+		.with_range(0.0, MAX_SCALE)
+		.lens(AppState::scale);
+
+	Flex::column()
+		.cross_axis_alignment(CrossAxisAlignment::Start)
+		.with_child(Label::new("Fractal"))
+		.with_spacer(4.0)
+		.with_child(fractal_picker)
+		.with_spacer(12.0)
+		.with_child(Label::new("Colouring"))
+		.with_spacer(4.0)
+		.with_child(colouring_picker)
+		.with_spacer(12.0)
+		.with_child(scale_label)
+		.with_child(scale_slider)
+		.with_spacer(12.0)
+		.with_child(ProgressBar::new().lens(AppState::progress).expand_width())
+		.with_child(Label::new(|data: &AppState, _env: &Env| data.progress_text.clone()))
+		.with_spacer(12.0)
+		.with_child(Label::new(|data: &AppState, _env: &Env| data.status.clone()).with_line_break_mode(LineBreaking::WordWrap))
+		.padding(8.0)
+		.fix_width(PANEL_WIDTH)
+}
+
+//the window's menu bar. Ctrl+S saves what's on screen, the next few draw it again bigger, and then there's scenes.
+pub fn build_menu() -> MenuDesc<AppState> {
+	let save = MenuItem::new(
+		LocalizedString::new("dimers-save").with_placeholder("Save image..."),
+		SAVE_IMAGE.with(1)
+	).hotkey(SysMods::Cmd, "s");
+
+	let file_menu = [2, 4, 8].iter().fold(
+		MenuDesc::new(LocalizedString::new("dimers-file").with_placeholder("File")).append(save),
+		|menu, &factor| {
+			let title = format!("Save at {}\u{d7} resolution...", factor);
+			menu.append(MenuItem::new(
+				LocalizedString::new("dimers-save-enlarged").with_placeholder(title),
+				SAVE_IMAGE.with(factor)
+			))
+		}
+	);
+
+	let open_scene = MenuItem::new(
+		LocalizedString::new("dimers-open-scene").with_placeholder("Open scene..."),
+		commands::SHOW_OPEN_PANEL.with(
+			FileDialogOptions::new()
+				.allowed_types(vec![SCENE_FILES])
+				.accept_command(OPEN_SCENE)
+		)
+	).hotkey(SysMods::Cmd, "o");
+
+	let save_scene = MenuItem::new(
+		LocalizedString::new("dimers-save-scene").with_placeholder("Save scene..."),
+		commands::SHOW_SAVE_PANEL.with(
+			FileDialogOptions::new()
+				.allowed_types(vec![SCENE_FILES])
+				.default_type(SCENE_FILES)
+				.default_name("dimers.scene")
+				.accept_command(SAVE_SCENE)
+		)
+	);
+
+	let file_menu = file_menu
+		.append_separator()
+		.append(open_scene)
+		.append(save_scene);
+
+	MenuDesc::empty().append(file_menu)
+}
@@ -39,11 +39,9 @@ impl EvenD8 {
 			(-point.1, point.0)
 		} else {point};
 		//rotate 1/2
-		let point = if self.rot >= 2 {
+		if self.rot >= 2 {
 			(-point.0, -point.1)
-		} else {point};
-		
-		point
+		} else {point}
 	}
 }
 
@@ -103,11 +101,9 @@ impl OddD8 {
 			(-point.1, point.0)
 		} else {point};
 		//rotate 1/2
-		let point = if self.rot >= 2 {
+		if self.rot >= 2 {
 			(-point.0, -point.1)
-		} else {point};
-		
-		point
+		} else {point}
 	}
 	
 	//option because non-even positions will get sent to non-integers
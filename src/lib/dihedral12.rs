@@ -0,0 +1,189 @@
+use std::ops::Mul;
+
+//the trimer version of dihedral.rs. Same idea, but with sixth turns instead of quarter turns,
+//and the odd ones come with a sqrt(3) scale (and an extra twelfth of a turn) instead of a sqrt(2) one.
+//points are in eisenstein coordinates: (a, b) means a + b w, where w is a sixth of a turn anticlockwise from 1.
+//that way everything that matters lands on whole numbers, just like the square grid does for dimers.
+//they are represented as "flip? first, then do rot sixth turns"
+//intrinsic: rot is 0 to 5.
+#[derive(Clone, Copy, Hash, Eq, PartialEq, Debug)]
+pub struct EvenD12 {
+	flip: bool,
+	rot: i8,
+}
+
+//w (a + b w) = -b + (a + b) w, since w^2 = w - 1
+fn sixth_turn(point: (isize, isize)) -> (isize, isize) {
+	(-point.1, point.0 + point.1)
+}
+
+//the conjugate of w is 1 - w
+fn conjugate(point: (isize, isize)) -> (isize, isize) {
+	(point.0 + point.1, -point.1)
+}
+
+impl EvenD12 {
+	pub const fn new(flip: bool, rot: i8) -> EvenD12 {
+		EvenD12 {
+			flip,
+			rot: rot.rem_euclid(6),
+		}
+	}
+
+	pub fn flipped(self) -> bool {self.flip}
+	pub fn rot(self) -> i8 {self.rot}
+
+	pub fn inv(self) -> EvenD12 {
+		EvenD12 {
+			flip: self.flip,
+			rot: ( if self.flip {self.rot} else {-self.rot} ).rem_euclid(6),
+		}
+	}
+
+	pub fn apply(self, point: (isize, isize)) -> (isize, isize) {
+		//flip
+		let mut point = if self.flip {
+			conjugate(point)
+		} else {point};
+		//rotate 1/2
+		if self.rot >= 3 {
+			point = (-point.0, -point.1);
+		}
+		//rotate 1/6s
+		for _ in 0..self.rot % 3 {
+			point = sixth_turn(point);
+		}
+		point
+	}
+}
+
+impl Mul<EvenD12> for EvenD12 {
+	type Output = EvenD12;
+
+	fn mul(self, rhs: EvenD12) -> Self::Output {
+		if self.flip {
+			EvenD12 {
+				flip: !rhs.flip,
+				rot: (self.rot - rhs.rot).rem_euclid(6),
+			}
+		} else {
+			EvenD12 {
+				flip: rhs.flip,
+				rot: (self.rot + rhs.rot).rem_euclid(6),
+			}
+		}
+	}
+}
+
+
+//The <rot=0> rotation is supposed to be "anticlockwise a twelfth turn", and it's sixth turns from there.
+//the scale that comes with it is multiplying by 1 + w (which is sqrt(3) long, a twelfth of a turn round)
+#[derive(Clone, Copy, Hash, Eq, PartialEq, Debug)]
+pub struct OddD12 {
+	flip: bool,
+	rot: i8,
+}
+
+impl OddD12 {
+	pub const fn new(flip: bool, rot: i8) -> OddD12 {
+		OddD12 {
+			flip,
+			rot: rot.rem_euclid(6),
+		}
+	}
+
+	pub fn flipped(self) -> bool {self.flip}
+	pub fn rot(self) -> i8 {self.rot}
+
+	pub fn inv(self) -> OddD12 {
+		OddD12 {
+			flip: self.flip,
+			rot: if self.flip {self.rot} else {5 - self.rot}
+		}
+	}
+
+	pub fn apply_as_large(self, point: (isize, isize)) -> (isize, isize) {
+		//flip (commutes with scale so can happen first)
+		let point = if self.flip {
+			conjugate(point)
+		} else {point};
+		//rotate 1/12 and scale sqrt 3, (1 + w) (a + b w) = (a - b) + (a + 2b) w
+		let point = (point.0 - point.1, point.0 + 2 * point.1);
+		//then the sixth turns
+		EvenD12::new(false, self.rot).apply(point)
+	}
+
+	//option because positions that aren't multiples of 1 + w will get sent to non-integers
+	pub fn apply_as_small(self, point: (isize, isize)) -> Option<(isize, isize)> {
+		let point = self.apply_as_large(point);
+		if point.0.rem_euclid(3) != 0 || point.1.rem_euclid(3) != 0 {
+			None
+		} else {
+			//same as large, but the scale is different
+			Some((point.0.div_euclid(3), point.1.div_euclid(3)))
+		}
+	}
+}
+
+//Warning: this multiplication should be done in a way that incorporates some kind of tripling or thirding elsewhere
+impl Mul<OddD12> for OddD12 {
+	type Output = EvenD12;
+
+	fn mul(self, rhs: OddD12) -> Self::Output {
+		if self.flip {
+			EvenD12 {
+				flip: !rhs.flip,
+				rot: (self.rot - rhs.rot).rem_euclid(6),
+			}
+		} else {
+			//(1 + w)^2 = 3w, hence the extra sixth turn
+			EvenD12 {
+				flip: rhs.flip,
+				rot: (self.rot + rhs.rot + 1).rem_euclid(6),
+			}
+		}
+	}
+}
+
+impl Mul<OddD12> for EvenD12 {
+	type Output = OddD12;
+
+	fn mul(self, rhs: OddD12) -> Self::Output {
+		if self.flip {
+			//the conjugate of 1 + w is (1 + w) turned back a sixth
+			OddD12 {
+				flip: !rhs.flip,
+				rot: (self.rot - rhs.rot - 1).rem_euclid(6),
+			}
+		} else {
+			OddD12 {
+				flip: rhs.flip,
+				rot: (self.rot + rhs.rot).rem_euclid(6),
+			}
+		}
+	}
+}
+
+impl Mul<EvenD12> for OddD12 {
+	type Output = OddD12;
+
+	fn mul(self, rhs: EvenD12) -> Self::Output {
+		if self.flip {
+			OddD12 {
+				flip: !rhs.flip,
+				rot: (self.rot - rhs.rot).rem_euclid(6),
+			}
+		} else {
+			OddD12 {
+				flip: rhs.flip,
+				rot: (self.rot + rhs.rot).rem_euclid(6),
+			}
+		}
+	}
+}
+
+
+pub const IDENTITY: EvenD12 = EvenD12{
+	flip: false,
+	rot: 0,
+};
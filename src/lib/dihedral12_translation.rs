@@ -0,0 +1,204 @@
+use super::dihedral12;
+use std::ops::Mul;
+
+//the trimer version of dihedral_translation.rs, with translations in eisenstein coordinates (see dihedral12)
+
+//all the same rules as there:
+//when lhs is a "small" transformation, multiplication can fail (specifically when rhs has a translation that isn't a multiple of 1 + w)
+//inverting a "large" transformation can also fail in the same way
+//large cannot be multiplied by large and likewise with small.
+#[derive(Clone, Copy, Hash, Eq, PartialEq, Debug)]
+pub struct EvenD12Translation {
+	dihedral: dihedral12::EvenD12,
+	translation: (isize,isize),
+}
+
+impl EvenD12Translation {
+
+	pub const fn new(flip: bool, rot: i8, translation: (isize, isize)) -> EvenD12Translation {
+		EvenD12Translation {
+			dihedral: dihedral12::EvenD12::new(flip, rot),
+			translation,
+		}
+	}
+
+	pub fn inv(self) -> EvenD12Translation {
+		let inverse_dihedral = self.dihedral.inv();
+		EvenD12Translation {
+			dihedral: inverse_dihedral,
+			translation: inverse_dihedral.apply((-self.translation.0, -self.translation.1))
+		}
+	}
+
+	pub fn get_translation(self) -> (isize, isize) {
+		self.translation
+	}
+
+	pub fn get_dihedral(self) -> dihedral12::EvenD12 {
+		self.dihedral
+	}
+}
+
+//even * even
+impl Mul<EvenD12Translation> for EvenD12Translation {
+	type Output = EvenD12Translation;
+
+	fn mul(self, rhs: EvenD12Translation) -> Self::Output {
+		let conjugated_translation = self.dihedral.apply(rhs.translation);
+		EvenD12Translation {
+			dihedral: self.dihedral * rhs.dihedral,
+			translation: (self.translation.0 + conjugated_translation.0, self.translation.1 + conjugated_translation.1)
+		}
+	}
+}
+
+
+
+//These come with a scale down by sqrt(3) preapplied at the start
+#[derive(Clone, Copy, Hash, Eq, PartialEq, Debug)]
+pub struct OddD12TranslationSmall {
+	dihedral: dihedral12::OddD12,
+	translation: (isize, isize),
+}
+
+//These come with a scale up by sqrt(3) preapplied at the start
+#[derive(Clone, Copy, Hash, Eq, PartialEq, Debug)]
+pub struct OddD12TranslationLarge {
+	dihedral: dihedral12::OddD12,
+	translation: (isize, isize),
+}
+
+impl OddD12TranslationSmall {
+	pub const fn new(flip: bool, rot: i8, translation: (isize, isize)) -> OddD12TranslationSmall {
+		OddD12TranslationSmall {
+			dihedral: dihedral12::OddD12::new(flip, rot),
+			translation,
+		}
+	}
+
+	pub fn inv(self) -> OddD12TranslationLarge {
+		//this is a large version rather than a small version like self
+		let inverse_dihedral = self.dihedral.inv();
+		OddD12TranslationLarge{
+			dihedral: inverse_dihedral,
+			translation: inverse_dihedral.apply_as_large((-self.translation.0, -self.translation.1))
+		}
+	}
+
+	pub fn get_translation(self) -> (isize, isize) {
+		self.translation
+	}
+
+	pub fn get_dihedral(self) -> dihedral12::OddD12 {
+		self.dihedral
+	}
+}
+
+impl OddD12TranslationLarge {
+	pub const fn new(flip: bool, rot: i8, translation: (isize, isize)) -> OddD12TranslationLarge {
+		OddD12TranslationLarge {
+			dihedral: dihedral12::OddD12::new(flip, rot),
+			translation,
+		}
+	}
+
+	//option because it is possible for odd translations to have non-integer translation inverses
+	pub fn inv(self) -> Option<OddD12TranslationSmall> {
+		let inverse_dihedral = self.dihedral.inv();
+		Some(OddD12TranslationSmall{
+			dihedral: inverse_dihedral,
+			translation: inverse_dihedral.apply_as_small((-self.translation.0, -self.translation.1))?
+		})
+	}
+
+	pub fn get_translation(self) -> (isize, isize) {
+		self.translation
+	}
+
+	pub fn get_dihedral(self) -> dihedral12::OddD12 {
+		self.dihedral
+	}
+}
+
+//odd * odd (no large*large or small*small allowed)
+impl Mul<OddD12TranslationSmall> for OddD12TranslationLarge {
+	type Output = EvenD12Translation;
+
+	fn mul(self, rhs: OddD12TranslationSmall) -> Self::Output {
+		let conjugated_translation = self.dihedral.apply_as_large(rhs.translation);
+		EvenD12Translation {
+			dihedral: self.dihedral * rhs.dihedral,
+			translation: (self.translation.0 + conjugated_translation.0, self.translation.1 + conjugated_translation.1)
+		}
+	}
+}
+
+impl Mul<OddD12TranslationLarge> for OddD12TranslationSmall {
+	type Output = Option<EvenD12Translation>;
+
+	fn mul(self, rhs: OddD12TranslationLarge) -> Self::Output {
+		let conjugated_translation = self.dihedral.apply_as_small(rhs.translation)?;
+		Some(EvenD12Translation {
+			dihedral: self.dihedral * rhs.dihedral,
+			translation: (self.translation.0 + conjugated_translation.0, self.translation.1 + conjugated_translation.1)
+		})
+	}
+}
+
+
+//even * odd
+impl Mul<OddD12TranslationSmall> for EvenD12Translation {
+	type Output = OddD12TranslationSmall;
+
+	fn mul(self, rhs: OddD12TranslationSmall) -> Self::Output {
+		let conjugated_translation = self.dihedral.apply(rhs.translation);
+		OddD12TranslationSmall {
+			dihedral: self.dihedral * rhs.dihedral,
+			translation: (self.translation.0 + conjugated_translation.0, self.translation.1 + conjugated_translation.1)
+		}
+	}
+}
+
+impl Mul<OddD12TranslationLarge> for EvenD12Translation {
+	type Output = OddD12TranslationLarge;
+
+	fn mul(self, rhs: OddD12TranslationLarge) -> Self::Output {
+		let conjugated_translation = self.dihedral.apply(rhs.translation);
+		OddD12TranslationLarge {
+			dihedral: self.dihedral * rhs.dihedral,
+			translation: (self.translation.0 + conjugated_translation.0, self.translation.1 + conjugated_translation.1)
+		}
+	}
+}
+
+
+//odd * even
+impl Mul<EvenD12Translation> for OddD12TranslationLarge {
+	type Output = OddD12TranslationLarge;
+
+	fn mul(self, rhs: EvenD12Translation) -> Self::Output {
+		let conjugated_translation = self.dihedral.apply_as_large(rhs.translation);
+		OddD12TranslationLarge {
+			dihedral: self.dihedral * rhs.dihedral,
+			translation: (self.translation.0 + conjugated_translation.0, self.translation.1 + conjugated_translation.1)
+		}
+	}
+}
+
+impl Mul<EvenD12Translation> for OddD12TranslationSmall {
+	type Output = Option<OddD12TranslationSmall>;
+
+	fn mul(self, rhs: EvenD12Translation) -> Self::Output {
+		let conjugated_translation = self.dihedral.apply_as_small(rhs.translation)?;
+		Some(OddD12TranslationSmall {
+			dihedral: self.dihedral * rhs.dihedral,
+			translation: (self.translation.0 + conjugated_translation.0, self.translation.1 + conjugated_translation.1)
+		})
+	}
+}
+
+
+pub const IDENTITY: EvenD12Translation = EvenD12Translation{
+	dihedral: dihedral12::IDENTITY,
+	translation: (0,0)
+};
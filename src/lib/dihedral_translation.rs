@@ -5,7 +5,8 @@ use std::ops::Mul;
 
 //when lhs is a "small" transformation, multiplication can fail (specifically when rhs has a non-even translation)
 //inverting a "large" transformation can also fail (if it has a non-even translation)
-//large cannot be multiplied by large and likewise with small. There is no type that incorporates arbitrary scaling here.
+//large cannot be multiplied by large and likewise with small. There is no type that incorporates arbitrary scaling here
+//(similarity has one, for when that gets in the way).
 #[derive(Clone, Copy, Hash, Eq, PartialEq, Debug)]
 pub struct EvenD8Translation {
 	dihedral: dihedral::EvenD8,
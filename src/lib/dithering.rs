@@ -0,0 +1,174 @@
+use std::sync::OnceLock;
+
+//dithering happens right at the end, when a LinearCol gets squashed into 8 bits per channel.
+//every variant is just a way of picking a threshold in [0,1) for each pixel, so it works the same for every image format.
+//that's also why there's no error diffusion (floyd-steinberg and the like): it needs the pixels in order, one row after
+//the other, and the canvases here get them a tile at a time in whatever order the renderer finishes them.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Dithering {
+	//plain truncation, like it always was
+	None,
+	//ordered dithering with a (2^order by 2^order) Bayer matrix.
+	//this lines up nicely with the recursive tile order, since tiles are power of two sized too.
+	Bayer(u32),
+	//thresholds from a tiled blue noise texture (generated on first use)
+	BlueNoise,
+}
+
+pub const BLUE_NOISE_SIZE: usize = 64;
+
+impl Dithering {
+	//None means "don't dither at all", which isn't the same as a threshold of 0.5 (that would be rounding instead of truncation)
+	pub fn threshold(self, x: usize, y: usize) -> Option<f64> {
+		match self {
+			Dithering::None => None,
+			Dithering::Bayer(order) => {
+				let size = 1usize << order;
+				let rank = bayer_rank(order, x & (size - 1), y & (size - 1));
+				Some((rank as f64 + 0.5) / (size * size) as f64)
+			},
+			Dithering::BlueNoise => {
+				let texture = blue_noise_texture();
+				let rank = texture[(y % BLUE_NOISE_SIZE) * BLUE_NOISE_SIZE + (x % BLUE_NOISE_SIZE)];
+				Some((rank as f64 + 0.5) / (BLUE_NOISE_SIZE * BLUE_NOISE_SIZE) as f64)
+			},
+		}
+	}
+
+	//"none", "blue", "bayer" (order 3) or "bayerN" for some other order
+	pub fn from_name(name: &str) -> Option<Dithering> {
+		match name {
+			"none" => Some(Dithering::None),
+			"blue" => Some(Dithering::BlueNoise),
+			"bayer" => Some(Dithering::Bayer(3)),
+			other => {
+				let order: u32 = other.strip_prefix("bayer")?.parse().ok()?;
+				//anything past this is bigger than an image would ever be anyway
+				if order <= 16 {Some(Dithering::Bayer(order))} else {None}
+			},
+		}
+	}
+
+	pub fn name(self) -> String {
+		match self {
+			Dithering::None => "none".to_string(),
+			Dithering::BlueNoise => "blue".to_string(),
+			Dithering::Bayer(3) => "bayer".to_string(),
+			Dithering::Bayer(order) => format!("bayer{}", order),
+		}
+	}
+}
+
+//the usual bit trick: interleave the bits of (x xor y) and y, then reverse them.
+//each level of the recursion is one pair of bits, so it's really the same recursion as UncroppedSquare::split
+pub fn bayer_rank(order: u32, x: usize, y: usize) -> usize {
+	let xor = x ^ y;
+	let mut rank = 0;
+	for bit in 0..order {
+		rank = (rank << 1) | ((xor >> bit) & 1);
+		rank = (rank << 1) | ((y >> bit) & 1);
+	}
+	rank
+}
+
+//ranks 0..BLUE_NOISE_SIZE^2, laid out row by row
+pub fn blue_noise_texture() -> &'static [u16] {
+	static TEXTURE: OnceLock<Vec<u16>> = OnceLock::new();
+	TEXTURE.get_or_init(|| void_and_cluster(BLUE_NOISE_SIZE, 1.5))
+}
+
+//Ulichney's void-and-cluster method, on a torus.
+//energy[p] is the sum of a gaussian around every set pixel, so the "tightest cluster" is the set pixel with the most energy
+//and the "largest void" is the unset pixel with the least.
+//energies are updated incrementally, since recomputing them every step would be far too slow.
+fn void_and_cluster(size: usize, sigma: f64) -> Vec<u16> {
+	let area = size * size;
+
+	//the gaussian, indexed by toroidal offset
+	let mut kernel = vec![0.0; area];
+	for dy in 0..size {
+		for dx in 0..size {
+			let wrapped_x = dx.min(size - dx) as f64;
+			let wrapped_y = dy.min(size - dy) as f64;
+			kernel[dy * size + dx] = f64::exp(-(wrapped_x * wrapped_x + wrapped_y * wrapped_y) / (2.0 * sigma * sigma));
+		}
+	}
+
+	let mut pattern = vec![false; area];
+	let mut energy = vec![0.0; area];
+
+	let toggle = |pattern: &mut Vec<bool>, energy: &mut Vec<f64>, position: usize| {
+		pattern[position] = !pattern[position];
+		let sign = if pattern[position] {1.0} else {-1.0};
+		let (px, py) = (position % size, position / size);
+		for y in 0..size {
+			for x in 0..size {
+				let offset = ((y + size - py) % size) * size + ((x + size - px) % size);
+				energy[y * size + x] += sign * kernel[offset];
+			}
+		}
+	};
+
+	let tightest_cluster = |pattern: &Vec<bool>, energy: &Vec<f64>| {
+		(0..area)
+			.filter(|&p| pattern[p])
+			.max_by(|&a, &b| energy[a].total_cmp(&energy[b]))
+			.expect("no set pixels (51873)")
+	};
+
+	let largest_void = |pattern: &Vec<bool>, energy: &Vec<f64>| {
+		(0..area)
+			.filter(|&p| !pattern[p])
+			.min_by(|&a, &b| energy[a].total_cmp(&energy[b]))
+			.expect("no unset pixels (51874)")
+	};
+
+	//initial pattern: about a tenth of the pixels, scattered by a fixed xorshift so the texture is deterministic
+	let initial_count = area / 10;
+	let mut state: u64 = 0x9e37_79b9_7f4a_7c15;
+	let mut placed = 0;
+	while placed < initial_count {
+		state ^= state << 13;
+		state ^= state >> 7;
+		state ^= state << 17;
+		let position = (state % area as u64) as usize;
+		if !pattern[position] {
+			toggle(&mut pattern, &mut energy, position);
+			placed += 1;
+		}
+	}
+
+	//shuffle the initial pattern around until moving the tightest cluster would just put it back where it was
+	loop {
+		let cluster = tightest_cluster(&pattern, &energy);
+		toggle(&mut pattern, &mut energy, cluster);
+		let void = largest_void(&pattern, &energy);
+		if void == cluster {
+			toggle(&mut pattern, &mut energy, cluster);
+			break;
+		}
+		toggle(&mut pattern, &mut energy, void);
+	}
+
+	let mut ranks = vec![0u16; area];
+
+	//phase 1: rank the initial pattern by taking clusters away
+	let initial_pattern = pattern.clone();
+	let initial_energy = energy.clone();
+	for rank in (0..initial_count).rev() {
+		let cluster = tightest_cluster(&pattern, &energy);
+		toggle(&mut pattern, &mut energy, cluster);
+		ranks[cluster] = rank as u16;
+	}
+
+	//phase 2: from the initial pattern, fill voids until the whole thing is full
+	pattern = initial_pattern;
+	energy = initial_energy;
+	for rank in initial_count..area {
+		let void = largest_void(&pattern, &energy);
+		toggle(&mut pattern, &mut energy, void);
+		ranks[void] = rank as u16;
+	}
+
+	ranks
+}
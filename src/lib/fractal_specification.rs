@@ -7,28 +7,49 @@ use super::interval_colouring::SplitInterval;
 use super::square::SquareCut;
 
 
+//(tile x, tile y, requirement list, colouring) -> the two triangles in that tile
+pub type SquareConfigGetter = dyn Fn(
+	isize,
+	isize,
+	&[EvenD8Translation],
+	&mut dyn FnMut(EvenD8Translation) -> Box<dyn SplitInterval>
+) -> SquareCut + Sync;
+
 // how can I hope to get a &'static to a dyn Fn you ask? Well I don't know that well. I can at least leak a Box if it came down to it though.
 // I suppose that won't be much of a problem since I won't be making many of these...
 pub struct FractalSpecification {
 	splitting_policy_tuple: (OddD8TranslationSmall, OddD8TranslationSmall),
 	production_policy_tuple: (OddD8TranslationSmall, OddD8TranslationSmall),
-	internal_acceptable: &'static (dyn Fn(EvenD8Translation) -> bool),
-	get_square_config: &'static (dyn Fn(
-		isize,
-		isize,
-		&[EvenD8Translation],
-		&mut dyn FnMut(EvenD8Translation) -> Box<dyn SplitInterval>
-	) -> SquareCut),
+	//Sync so that presets can live in statics and be handed to worker threads
+	internal_acceptable: &'static (dyn Fn(EvenD8Translation) -> bool + Sync),
+	get_square_config: &'static SquareConfigGetter,
 	splitting_type: (bool, bool),
 }
 
 impl FractalSpecification {
+	//for fractals that aren't one of the consts below (the consts just fill in the fields directly)
+	pub const fn new(
+		production_policy: (OddD8TranslationSmall, OddD8TranslationSmall),
+		splitting_policy: (OddD8TranslationSmall, OddD8TranslationSmall),
+		acceptable: &'static (dyn Fn(EvenD8Translation) -> bool + Sync),
+		square_config: &'static SquareConfigGetter,
+		splitting_type: (bool, bool),
+	) -> FractalSpecification {
+		FractalSpecification {
+			splitting_policy_tuple: splitting_policy,
+			production_policy_tuple: production_policy,
+			internal_acceptable: acceptable,
+			get_square_config: square_config,
+			splitting_type,
+		}
+	}
+
 	pub fn splitting_policy(&self) -> (OddD8TranslationSmall, OddD8TranslationSmall) {
-		self.splitting_policy_tuple.clone()
+		self.splitting_policy_tuple
 	}
 	
 	pub fn production_policy(&self) -> (OddD8TranslationSmall, OddD8TranslationSmall) {
-		self.production_policy_tuple.clone()
+		self.production_policy_tuple
 	}
 	
 	pub fn acceptable(&self, transformation: EvenD8Translation) -> bool {
@@ -156,4 +177,45 @@ pub const HEIGHWAY: FractalSpecification = FractalSpecification{
 	get_square_config: &auxiliary::dragon_config,
 
 	splitting_type: (true, false),
-};
\ No newline at end of file
+};
+
+//every preset above, by name (for pickers, scene files and the like)
+pub static PRESETS: [(&str, &FractalSpecification); 6] = [
+	("coral", &CORAL),
+	("hydra", &HYDRA),
+	("dibolt", &DIBOLT),
+	("levy", &LEVY),
+	("scorpion", &SCORPION),
+	("heighway", &HEIGHWAY),
+];
+
+pub fn preset(name: &str) -> Option<&'static FractalSpecification> {
+	PRESETS
+		.iter()
+		.find(|(preset_name, _)| *preset_name == name)
+		.map(|(_, fractal)| *fractal)
+}
+
+//the acceptability rules and square configs the presets are made of, by name.
+//a fractal defined outside the source (see scene::CustomFractal) has to pick from these.
+pub type Acceptability = fn(EvenD8Translation) -> bool;
+pub type SquareConfig = fn(
+	isize,
+	isize,
+	&[EvenD8Translation],
+	&mut dyn FnMut(EvenD8Translation) -> Box<dyn SplitInterval>
+) -> SquareCut;
+
+pub static ACCEPTABILITY_RULES: [(&str, Acceptability); 4] = [
+	("even", auxiliary::even_coordinates),
+	("even_with_parity", auxiliary::even_coordinates_with_parity),
+	("outer_squares", auxiliary::outer_squares_around),
+	("dragon", auxiliary::dragon_acceptable),
+];
+
+pub static SQUARE_CONFIGS: [(&str, SquareConfig); 4] = [
+	("dense", auxiliary::dense_square_config),
+	("mid", auxiliary::mid_square_config),
+	("large", auxiliary::large_square_config),
+	("dragon", auxiliary::dragon_config),
+];
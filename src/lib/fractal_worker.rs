@@ -1,47 +1,72 @@
-use std::sync::mpsc::Sender;
-use std::sync::{Arc, Mutex};
-use druid::piet;
+use super::auxiliary::{self, TriangleColouring};
+use super::pixel_sink::PixelSink;
+use super::render::{self, Image, View};
 
-use super::relevance_getter::get_weights;
-use super::auxiliary::{self, WorkingMessage};
+use super::fractal_specification::{self, FractalSpecification};
 
-use super::relevance_getter;
-use super::fractal_specification;
+use std::{thread, time};
 
-use super::square::draw_into_canvas;
+//everything about what to draw, other than the canvas itself
+#[derive(Clone, Copy)]
+pub struct RenderSettings {
+	pub fractal: &'static FractalSpecification,
+	pub colouring: TriangleColouring,
+	pub scale: u32,
+	pub offset: (isize, isize), //where the fractal's origin is, relative to the middle of the canvas
+	pub antialiasing: usize, //see render::draw_view
+}
 
-use std::{thread, time};
+impl RenderSettings {
+	//what these settings look like on a width x height canvas
+	pub fn view(&self, width: usize, height: usize) -> View {
+		View {
+			width,
+			height,
+			origin: ( (width/2) as isize + self.offset.0, (height/2) as isize + self.offset.1 ),
+			scale: self.scale,
+		}
+	}
+}
+
+impl Default for RenderSettings {
+	fn default() -> Self {
+		RenderSettings {
+			fractal: &fractal_specification::HYDRA,
+			colouring: auxiliary::two_colouring,
+			scale: 7,
+			offset: (0, 0),
+			antialiasing: 1,
+		}
+	}
+}
+
+//the same picture start draws into a width x height window, but factor times bigger in each direction (see View::enlarged)
+pub fn render_enlarged(width: usize, height: usize, settings: RenderSettings, factor: usize) -> Image {
+	let view = settings.view(width, height).enlarged(factor);
+	render::render_antialiased(settings.fractal, view, settings.colouring, settings.antialiasing)
+}
 
-pub fn start(
+//regions are drawn one after the other, so the ones that matter most go first.
+//between them they should cover the whole width x height canvas.
+pub fn start<S: PixelSink>(
 	width: usize,
 	height: usize,
-	image_format: piet::ImageFormat,
-	canvas: Arc<Mutex<Vec<u8>>>,
-	sender: Sender<((usize, usize, usize, usize), WorkingMessage)>) {
+	settings: RenderSettings,
+	regions: &[(usize, usize, usize, usize)],
+	sink: S) {
 	
 	thread::sleep(time::Duration::from_secs(0));
 
-	let fractal = &fractal_specification::HYDRA;
-	
-	let relevance_list = relevance_getter::get_relevance_list(fractal);
-	let requirement_list = relevance_getter::to_requirement_list(&relevance_list);
-	let splitter_list = relevance_getter::get_splitter_list(fractal, &requirement_list);
-	let triangle_weights = get_weights(fractal, &relevance_list);
-
-	draw_into_canvas(
-		image_format,
-		canvas,
-		&triangle_weights,
-		&requirement_list,
-		width,
-		(0, 0, width as isize, height as isize),
-		( (width/2) as isize, (height/2) as isize ),
-		7,
-		fractal,
-		&auxiliary::two_colouring,
-		&splitter_list,
-		sender,
-	);
+	for &region in regions {
+		render::draw_region(
+			settings.fractal,
+			settings.view(width, height),
+			region,
+			settings.colouring,
+			settings.antialiasing,
+			&sink,
+		);
+	}
 	
 	//split space into level=0 blocks (where a block is made of two triangles, and possibly truncated)
 		//figure out what cutting each triangle contains
@@ -71,5 +96,4 @@ pub fn start(
 		}
 		
 	} */
-	println!("done");
 }
\ No newline at end of file
@@ -0,0 +1,385 @@
+use std::path::Path;
+use std::sync::Arc;
+use std::{thread, time};
+
+use super::animation::{self, Motion};
+use super::auxiliary::COLOURINGS;
+use super::dihedral_translation::EvenD8Translation;
+use super::dithering::Dithering;
+use super::fractal_specification::{FractalSpecification, PRESETS};
+use super::interval_colouring::SplitInterval;
+use super::pixel_format::PixelFormat;
+use super::pixel_sink::{ByteCanvas, PixelSink};
+use super::progress::ProgressTracker;
+use super::relevance_getter;
+use super::render::View;
+use super::scene::{self, Scene, SceneFractal, SceneTexture};
+use super::square::draw_into_canvas;
+use super::streaming;
+use super::tile_pyramid::{self, TileLayout};
+use super::trimer;
+use super::trimer_specification::{self, TrimerSpecification};
+use super::zoom::{self, Easing, Zoom};
+
+//the same thing fractal_worker does, but without a window to watch it happen.
+//draws the (0, 0, width, height) rectangle into sink
+pub fn render_into<F, S>(
+	fractal: &FractalSpecification,
+	triangle_colouring: F,
+	(width, height): (usize, usize),
+	origin: (isize, isize),
+	scale: u32,
+	sink: &S,
+) where F: FnMut(EvenD8Translation) -> Box<dyn SplitInterval>, S: PixelSink + ?Sized {
+	render_region(fractal, triangle_colouring, (0, 0, width, height), origin, scale, sink);
+}
+
+//just the pixels inside bounds (left, top, right, bottom), in the same coordinates as render_into
+pub fn render_region<F, S>(
+	fractal: &FractalSpecification,
+	triangle_colouring: F,
+	bounds: (usize, usize, usize, usize),
+	origin: (isize, isize),
+	scale: u32,
+	sink: &S,
+) where F: FnMut(EvenD8Translation) -> Box<dyn SplitInterval>, S: PixelSink + ?Sized {
+	//the presets are fine, and anything else should have been through relevance_getter::check before it got this far
+	let relevance_list = relevance_getter::get_relevance_list(fractal).unwrap_or_else(|error| panic!("{} (32201)", error));
+	let requirement_list = relevance_getter::to_requirement_list(&relevance_list);
+	let splitter_list = relevance_getter::get_splitter_list(fractal, &requirement_list).unwrap_or_else(|error| panic!("{} (32201)", error));
+	let triangle_weights = relevance_getter::get_weights(fractal, &relevance_list).unwrap_or_else(|error| panic!("{} (32201)", error));
+
+	draw_into_canvas(
+		sink,
+		&triangle_weights,
+		&requirement_list,
+		(bounds.0 as isize, bounds.1 as isize, bounds.2 as isize, bounds.3 as isize),
+		origin,
+		scale,
+		fractal,
+		triangle_colouring,
+		&splitter_list,
+	);
+}
+
+//returns the finished canvas, laid out as image_format says.
+pub fn render_to_buffer<F>(
+	fractal: &FractalSpecification,
+	triangle_colouring: F,
+	(width, height): (usize, usize),
+	origin: (isize, isize),
+	scale: u32,
+	image_format: PixelFormat,
+	dithering: Dithering,
+) -> Vec<u8> where F: FnMut(EvenD8Translation) -> Box<dyn SplitInterval> {
+	let canvas = ByteCanvas::new(width, height, image_format, dithering);
+	render_into(fractal, triangle_colouring, (width, height), origin, scale, &canvas);
+	canvas.into_bytes()
+}
+
+const USAGE: &str = "usage: dimers [--scene <file>] [--output <file.png|file.pfm|file.exr>] [--save-scene <file>]
+	[--fractal NAME] [--colouring NAME] [--width N] [--height N] [--origin X,Y] [--scale N] [--antialias N]
+	[--format gray8|rgb8|rgba8|rgba16|rgbaf32] [--dither none|bayer|blue] [--texture <file.ppm|file.pfm>] [--quiet]
+	[--pyramid <directory> [--levels N] [--layout dzi|xyz]] [--stream [--band-height N]]
+	[--animate cycle|hue|pan|zoom [--path X,Y;X,Y...] [--frames N] [--delay MS] [--frame-directory <directory>]]
+	[--zoom-address 0110... | --zoom-to X,Y] [--zoom-levels N] [--easing smooth|linear]
+flags after --scene change what it says. Without a scene, it's the picture the gui starts with.
+--texture is what the strip and texture colourings paint with, and goes into a saved scene as its path.
+--fractal also takes the trimers (flake, curl, crest), which have their own colourings and can't be saved as scenes.
+--pyramid writes deep zoom tiles of the picture, with each of the levels twice the size of the one before (see tile_pyramid).
+--stream draws the png a band of rows at a time, for pictures too big to fit in memory (see streaming).
+--animate makes --output a looping gif or apng (and --frame-directory gets every frame as a png). cycle is for the
+continuum, rainbow and fire colourings, and pan goes round --path (offsets from --origin) and back (see animation).
+zoom goes --zoom-levels doublings of --scale (6 unless asked) in on the piece of the fractal at a split address,
+or on a pixel of the picture, bringing it to the middle on the way (see zoom).";
+
+//command line rendering, for when there's no window (or no need for one)
+pub fn run(args: &[String]) -> Result<(), String> {
+	let mut scene = Scene::default();
+	let mut output: Option<String> = None;
+	let mut scene_output: Option<String> = None;
+	let mut format_given = false;
+	let mut quiet = false;
+	//trimers don't fit in a scene, so they get kept to the side (with the colouring's name, since their colourings are different)
+	let mut trimer_fractal: Option<&TrimerSpecification> = None;
+	let mut colouring_name: Option<String> = None;
+	let mut pyramid: Option<String> = None;
+	let mut levels: u32 = 4;
+	let mut layout = TileLayout::Dzi;
+	let mut stream = false;
+	let mut band_height: Option<usize> = None;
+	let mut motion_name: Option<String> = None;
+	let mut path: Vec<(isize, isize)> = Vec::new();
+	let mut frames: u32 = 48;
+	let mut delay: u16 = 40;
+	let mut frame_directory: Option<String> = None;
+	let mut zoom_address: Option<String> = None;
+	let mut zoom_pixel: Option<(isize, isize)> = None;
+	let mut zoom_levels: f64 = 6.0;
+	let mut easing = Easing::Smooth;
+
+	let mut args = args.iter();
+	while let Some(flag) = args.next() {
+		let mut value = || args.next().ok_or_else(|| format!("missing value for {}\n{}", flag, USAGE));
+		match flag.as_str() {
+			"--scene" => {
+				(scene, format_given) = Scene::load(Path::new(value()?))?;
+			},
+			"--output" => output = Some(value()?.clone()),
+			"--save-scene" => scene_output = Some(value()?.clone()),
+			"--fractal" => {
+				let name = value()?;
+				if let Some(index) = scene::index_of(&PRESETS, name) {
+					scene.fractal = SceneFractal::Preset(index);
+					trimer_fractal = None;
+				} else {
+					trimer_fractal = Some(trimer_specification::preset(name).ok_or_else(|| format!("unknown fractal {}\n{}", name, USAGE))?);
+				}
+			},
+			"--colouring" => colouring_name = Some(value()?.clone()),
+			"--format" => {
+				let name = value()?;
+				scene.format = PixelFormat::from_name(name).ok_or_else(|| format!("unknown format {}\n{}", name, USAGE))?;
+				format_given = true;
+			},
+			"--width" => scene.resize(parse_size(value()?)?, scene.height),
+			"--height" => scene.resize(scene.width, parse_size(value()?)?),
+			"--origin" => scene.origin = parse_point(value()?)?,
+			"--scale" => {
+				let scale: u32 = parse_number(value()?)?;
+				if scale > zoom::MAX_SCALE {
+					return Err(format!("scale can only go up to {}\n{}", zoom::MAX_SCALE, USAGE));
+				}
+				scene.scale = scale;
+			},
+			"--antialias" => {
+				let antialiasing: usize = parse_number(value()?)?;
+				if !antialiasing.is_power_of_two() {
+					return Err(format!("antialiasing has to be a power of two\n{}", USAGE));
+				}
+				scene.antialiasing = antialiasing;
+			},
+			"--dither" => {
+				let name = value()?;
+				scene.dithering = Dithering::from_name(name).ok_or_else(|| format!("unknown dithering {}\n{}", name, USAGE))?;
+			},
+			"--texture" => {
+				scene.texture = Some(SceneTexture::load(Path::new(value()?))?);
+			},
+			"--pyramid" => pyramid = Some(value()?.clone()),
+			"--levels" => {
+				levels = parse_number(value()?)?;
+				if levels == 0 {
+					return Err(format!("a pyramid needs at least one level\n{}", USAGE));
+				}
+			},
+			"--layout" => {
+				let name = value()?;
+				layout = TileLayout::from_name(name).ok_or_else(|| format!("unknown layout {}\n{}", name, USAGE))?;
+			},
+			"--stream" => stream = true,
+			"--band-height" => {
+				let rows = parse_number(value()?)?;
+				if rows == 0 {
+					return Err(format!("bands need at least one row\n{}", USAGE));
+				}
+				band_height = Some(rows);
+				stream = true;
+			},
+			"--animate" => motion_name = Some(value()?.clone()),
+			"--path" => path = value()?.split(';').map(parse_point).collect::<Result<_, _>>()?,
+			"--frames" => {
+				frames = parse_number(value()?)?;
+				if frames == 0 {
+					return Err(format!("an animation needs at least one frame\n{}", USAGE));
+				}
+			},
+			"--delay" => delay = parse_number(value()?)?,
+			"--frame-directory" => frame_directory = Some(value()?.clone()),
+			"--zoom-address" => {
+				zoom_address = Some(value()?.clone());
+				zoom_pixel = None;
+			},
+			"--zoom-to" => {
+				zoom_pixel = Some(parse_point(value()?)?);
+				zoom_address = None;
+			},
+			"--zoom-levels" => {
+				zoom_levels = parse_number(value()?)?;
+				if zoom_levels.is_nan() || zoom_levels <= 0.0 {
+					return Err(format!("a zoom has to go in some way\n{}", USAGE));
+				}
+			},
+			"--easing" => {
+				let name = value()?;
+				easing = Easing::from_name(name).ok_or_else(|| format!("unknown easing {}\n{}", name, USAGE))?;
+			},
+			"--quiet" => quiet = true,
+			other => return Err(format!("unknown argument {}\n{}", other, USAGE)),
+		}
+	}
+
+	if output.is_none() && scene_output.is_none() && pyramid.is_none() && frame_directory.is_none() {
+		return Err(USAGE.to_string());
+	}
+
+	let trimer_colouring = match (trimer_fractal, &colouring_name) {
+		(None, Some(name)) => {
+			scene.colouring = scene::index_of(&COLOURINGS, name).ok_or_else(|| format!("unknown colouring {}\n{}", name, USAGE))?;
+			None
+		},
+		(None, None) => None,
+		(Some(_), name) => {
+			let name = name.as_deref().unwrap_or("three");
+			Some(trimer::colouring(name).ok_or_else(|| format!("unknown trimer colouring {}\n{}", name, USAGE))?)
+		},
+	};
+	if trimer_fractal.is_some() && scene_output.is_some() {
+		return Err(format!("trimers can't be saved as scenes\n{}", USAGE));
+	}
+	//the last level is drawn levels - 1 doublings in from the scene's scale
+	if pyramid.is_some() && scene.scale as u64 + levels as u64 - 1 > zoom::MAX_SCALE as u64 {
+		return Err(format!("a pyramid's last level is at --scale plus --levels minus 1, which can only go up to {}\n{}", zoom::MAX_SCALE, USAGE));
+	}
+
+	let motion = match motion_name.as_deref() {
+		None => None,
+		Some(_) if trimer_fractal.is_some() => return Err(format!("trimers can't be animated\n{}", USAGE)),
+		Some("cycle") => {
+			if animation::cycling_stops(COLOURINGS[scene.colouring].0).is_none() {
+				return Err(format!("{} isn't a colouring that can cycle\n{}", COLOURINGS[scene.colouring].0, USAGE));
+			}
+			Some(Motion::Cycle)
+		},
+		Some("hue") => Some(Motion::Hue),
+		Some("pan") if path.is_empty() => return Err(format!("pan needs a --path\n{}", USAGE)),
+		Some("pan") => Some(Motion::Pan(path)),
+		Some("zoom") => {
+			if scene.scale as f64 + zoom_levels > zoom::MAX_SCALE as f64 {
+				return Err(format!("zooms can only go up to scale {}\n{}", zoom::MAX_SCALE, USAGE));
+			}
+			let target = match (&zoom_address, zoom_pixel) {
+				(Some(address), _) => zoom::address_point(scene.fractal.specification(), address)?,
+				(None, Some(pixel)) => zoom::canvas_point(scene.view(), pixel),
+				(None, None) => return Err(format!("zoom needs a --zoom-address or --zoom-to\n{}", USAGE)),
+			};
+			Some(Motion::Zoom(Zoom::new(target, zoom_levels, easing)))
+		},
+		Some(other) => return Err(format!("unknown animation {}\n{}", other, USAGE)),
+	};
+	if motion.is_none() && frame_directory.is_some() {
+		return Err(format!("--frame-directory is only for animations\n{}", USAGE));
+	}
+
+	if let Some(scene_output) = scene_output {
+		scene.save(Path::new(&scene_output)).map_err(|error| error.to_string())?;
+	}
+
+	//whichever renderer the fractal needs, drawing any view of the scene (counting into tracker, if there is one).
+	//it gets its own copy of the scene, since the output format can still change below (which doesn't change the drawing)
+	let drawn = scene.clone();
+	let render_view = move |view: View, tracker: Option<Arc<ProgressTracker>>| match (trimer_fractal, trimer_colouring, tracker) {
+		(Some(fractal), Some(colouring), Some(tracker)) => trimer::render_tracked(fractal, view, colouring, drawn.antialiasing, tracker),
+		(Some(fractal), Some(colouring), None) => trimer::render_antialiased(fractal, view, colouring, drawn.antialiasing),
+		(_, _, Some(tracker)) => drawn.showing(view).render_tracked(tracker),
+		(_, _, None) => drawn.showing(view).render(),
+	};
+
+	if let Some(motion) = &motion {
+		//8 bit unless asked otherwise, like the pyramid tiles
+		let format = if format_given {scene.format} else {PixelFormat::RgbaSeparate};
+		let export = |tracker: Option<Arc<ProgressTracker>>| animation::export(
+			frames, delay, (scene.width, scene.height), format, scene.dithering,
+			frame_directory.as_deref().map(Path::new), output.as_deref().map(Path::new),
+			|frame| animation::render_frame(&scene, motion, motion.t(frame, frames), tracker.clone()),
+		);
+		let result = if quiet {
+			export(None)
+		} else {
+			render_showing_progress(animation::pixel_total(&scene, motion, frames), |tracker| export(Some(tracker)))
+		};
+		result.map_err(|error| error.to_string())?;
+	} else if let Some(output) = output {
+		//floats for the float formats, 16 bit png otherwise
+		if !format_given {
+			scene.format = if output.ends_with(".pfm") || output.ends_with(".exr") {
+				PixelFormat::RgbaLinearF32
+			} else {
+				PixelFormat::Rgba16
+			};
+		}
+		if stream {
+			let band_height = band_height.unwrap_or_else(|| streaming::default_band_height(scene.width));
+			let save = |tracker: Option<Arc<ProgressTracker>>| streaming::save_png(
+				Path::new(&output), scene.view(), band_height, scene.format, scene.dithering,
+				|view| render_view(view, tracker.clone()),
+			);
+			let result = if quiet {
+				save(None)
+			} else {
+				render_showing_progress(scene.width * scene.height, |tracker| save(Some(tracker)))
+			};
+			result.map_err(|error| error.to_string())?;
+		} else {
+			let image = if quiet {
+				render_view(scene.view(), None)
+			} else {
+				render_showing_progress(scene.width * scene.height, |tracker| render_view(scene.view(), Some(tracker)))
+			};
+			image.save(Path::new(&output), scene.format, scene.dithering).map_err(|error| error.to_string())?;
+		}
+	}
+
+	if let Some(directory) = pyramid {
+		let directory = Path::new(&directory);
+		let name = directory.file_name().and_then(|name| name.to_str()).unwrap_or("pyramid");
+		//8 bit tiles unless asked otherwise, since they're for the web
+		let format = if format_given {scene.format} else {PixelFormat::RgbaSeparate};
+		let export = |tracker: Option<Arc<ProgressTracker>>| tile_pyramid::export(
+			directory, name, scene.view(), levels, layout, format, scene.dithering,
+			|view| render_view(view, tracker.clone()),
+		);
+		let result = if quiet {
+			export(None)
+		} else {
+			render_showing_progress(tile_pyramid::pixel_total(scene.view(), levels, layout), |tracker| export(Some(tracker)))
+		};
+		result.map_err(|error| error.to_string())?;
+	}
+
+	Ok(())
+}
+
+//renders on another thread, redrawing a progress bar on stderr until it's done
+//(render gets a tracker whose total is total)
+fn render_showing_progress<T, F>(total: usize, render: F) -> T where F: FnOnce(Arc<ProgressTracker>) -> T + Send, T: Send {
+	let tracker = Arc::new(ProgressTracker::new(total));
+	let result = thread::scope(|scope| {
+		let worker = scope.spawn(|| render(tracker.clone()));
+		while !worker.is_finished() {
+			eprint!("\r{}", tracker.snapshot());
+			thread::sleep(time::Duration::from_millis(250));
+		}
+		worker.join().expect("render thread panicked (40417)")
+	});
+	eprintln!("\r{}", tracker.snapshot());
+	result
+}
+
+fn parse_number<T: std::str::FromStr>(text: &str) -> Result<T, String> {
+	text.parse().map_err(|_| format!("expected a number, got {}", text))
+}
+
+//a width or height, which can't be 0
+fn parse_size(text: &str) -> Result<usize, String> {
+	match parse_number(text)? {
+		0 => Err(format!("the canvas can't be empty\n{}", USAGE)),
+		size => Ok(size),
+	}
+}
+
+//X,Y
+fn parse_point(text: &str) -> Result<(isize, isize), String> {
+	let (x, y) = text.split_once(',').ok_or_else(|| format!("expected X,Y, got {}\n{}", text, USAGE))?;
+	Ok((parse_number(x)?, parse_number(y)?))
+}
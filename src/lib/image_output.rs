@@ -0,0 +1,527 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use super::dithering::Dithering;
+use super::pixel_format::PixelFormat;
+
+//everything here is written by hand, to avoid pulling in a whole image library for three simple formats.
+//png is written uncompressed (stored deflate blocks), so the files are big, but they're valid and lossless.
+
+//picks the writer from the file extension
+pub fn save_image(path: &Path, width: usize, height: usize, format: PixelFormat, data: &[u8]) -> io::Result<()> {
+	let extension = path
+		.extension()
+		.and_then(|extension| extension.to_str())
+		.map(|extension| extension.to_ascii_lowercase());
+
+	let mut file = BufWriter::new(File::create(path)?);
+	match extension.as_deref() {
+		Some("png") => write_png(&mut file, width, height, format, data)?,
+		Some("pfm") => write_pfm(&mut file, width, height, format, data)?,
+		Some("exr") => write_exr(&mut file, width, height, format, data)?,
+		_ => return Err(invalid_input("unknown image extension (expected png, pfm or exr)")),
+	}
+	file.flush()
+}
+
+fn invalid_input(message: &str) -> io::Error {
+	io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+//----------------------------------------------------------------- png
+
+const CRC_TABLE: [u32; 256] = make_crc_table();
+
+const fn make_crc_table() -> [u32; 256] {
+	let mut table = [0u32; 256];
+	let mut n = 0;
+	while n < 256 {
+		let mut c = n as u32;
+		let mut k = 0;
+		while k < 8 {
+			c = if c & 1 == 1 {0xedb8_8320 ^ (c >> 1)} else {c >> 1};
+			k += 1;
+		}
+		table[n] = c;
+		n += 1;
+	}
+	table
+}
+
+fn crc32(parts: &[&[u8]]) -> u32 {
+	let mut crc = 0xffff_ffffu32;
+	for part in parts {
+		for &byte in *part {
+			crc = CRC_TABLE[((crc ^ byte as u32) & 0xff) as usize] ^ (crc >> 8);
+		}
+	}
+	crc ^ 0xffff_ffff
+}
+
+fn write_chunk<W: Write>(writer: &mut W, chunk_type: &[u8; 4], data: &[u8]) -> io::Result<()> {
+	writer.write_all(&(data.len() as u32).to_be_bytes())?;
+	writer.write_all(chunk_type)?;
+	writer.write_all(data)?;
+	writer.write_all(&crc32(&[chunk_type, data]).to_be_bytes())
+}
+
+//(colour type, bit depth) as png understands them
+pub fn png_layout(format: PixelFormat) -> Option<(u8, u8)> {
+	match format {
+		PixelFormat::Grayscale => Some((0, 8)),
+		PixelFormat::Rgb => Some((2, 8)),
+		PixelFormat::RgbaSeparate => Some((6, 8)),
+		PixelFormat::Rgba16 => Some((6, 16)),
+		//png has no premultiplied alpha, and no floats
+		PixelFormat::RgbaPremul | PixelFormat::RgbaLinearF32 => None,
+	}
+}
+
+//the signature and IHDR
+fn write_png_header<W: Write>(writer: &mut W, width: usize, height: usize, format: PixelFormat) -> io::Result<()> {
+	let (colour_type, bit_depth) = png_layout(format).ok_or_else(|| invalid_input("pixel format can't be written as png"))?;
+	if width == 0 || height == 0 {
+		return Err(invalid_input("pngs can't be empty"));
+	}
+
+	writer.write_all(&[0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'])?;
+
+	let mut header = Vec::with_capacity(13);
+	header.extend_from_slice(&(width as u32).to_be_bytes());
+	header.extend_from_slice(&(height as u32).to_be_bytes());
+	header.extend_from_slice(&[bit_depth, colour_type, 0, 0, 0]);
+	write_chunk(writer, b"IHDR", &header)
+}
+
+//zlib header: deflate, 32K window, no preset dictionary, "fastest" (which is fair enough)
+const ZLIB_HEADER: [u8; 2] = [0x78, 0x01];
+
+const MAX_STORED_BLOCK: usize = 65535;
+
+//the zlib stream png pixels go in, without any compressing: every row behind a filter type 0 (none), as stored deflate
+//blocks (one per row, or a few for very wide rows), then an empty final block and the adler32 of everything in them.
+//the stream can be cut up however the caller likes, so PngWriter sends each row out as it comes and ApngWriter a frame at a time
+struct StoredRows {
+	adler_a: u32,
+	adler_b: u32,
+}
+
+impl StoredRows {
+	fn new() -> StoredRows {
+		StoredRows {adler_a: 1, adler_b: 0}
+	}
+
+	//adds row's blocks to stream
+	fn push_row(&mut self, row: &[u8], stream: &mut Vec<u8>) {
+		let mut filtered = Vec::with_capacity(row.len() + 1);
+		filtered.push(0);
+		filtered.extend_from_slice(row);
+
+		for &byte in &filtered {
+			self.adler_a = (self.adler_a + byte as u32) % 65521;
+			self.adler_b = (self.adler_b + self.adler_a) % 65521;
+		}
+
+		stream.reserve(filtered.len() + 5 * (filtered.len() / MAX_STORED_BLOCK + 1));
+		for block in filtered.chunks(MAX_STORED_BLOCK) {
+			let length = block.len() as u16;
+			stream.push(0); //not final, stored
+			stream.extend_from_slice(&length.to_le_bytes());
+			stream.extend_from_slice(&(!length).to_le_bytes());
+			stream.extend_from_slice(block);
+		}
+	}
+
+	//adds the empty final block, then the checksum
+	fn finish(self, stream: &mut Vec<u8>) {
+		stream.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+		stream.extend_from_slice(&((self.adler_b << 16) | self.adler_a).to_be_bytes());
+	}
+}
+
+//writes a png one row at a time, so the whole image never has to be in memory at once.
+//each row goes out as its own IDAT chunk (see StoredRows).
+pub struct PngWriter<W: Write> {
+	writer: W,
+	row_bytes: usize,
+	rows_left: usize,
+	rows: StoredRows,
+}
+
+impl<W: Write> PngWriter<W> {
+	pub fn new(mut writer: W, width: usize, height: usize, format: PixelFormat) -> io::Result<PngWriter<W>> {
+		write_png_header(&mut writer, width, height, format)?;
+		write_chunk(&mut writer, b"IDAT", &ZLIB_HEADER)?;
+
+		Ok(PngWriter {
+			writer,
+			row_bytes: width * format.bytes_per_pixel(),
+			rows_left: height,
+			rows: StoredRows::new(),
+		})
+	}
+
+	pub fn write_row(&mut self, row: &[u8]) -> io::Result<()> {
+		if row.len() != self.row_bytes {
+			return Err(invalid_input("png row has the wrong length"));
+		}
+		if self.rows_left == 0 {
+			return Err(invalid_input("too many png rows"));
+		}
+		self.rows_left -= 1;
+
+		let mut data = Vec::new();
+		self.rows.push_row(row, &mut data);
+		write_chunk(&mut self.writer, b"IDAT", &data)
+	}
+
+	pub fn finish(mut self) -> io::Result<W> {
+		if self.rows_left != 0 {
+			return Err(invalid_input("not enough png rows"));
+		}
+		let mut data = Vec::new();
+		self.rows.finish(&mut data);
+		write_chunk(&mut self.writer, b"IDAT", &data)?;
+		write_chunk(&mut self.writer, b"IEND", &[])?;
+		Ok(self.writer)
+	}
+}
+
+pub fn write_png<W: Write>(writer: &mut W, width: usize, height: usize, format: PixelFormat, data: &[u8]) -> io::Result<()> {
+	let mut png = PngWriter::new(writer, width, height, format)?;
+	let row_bytes = width * format.bytes_per_pixel();
+	for row in data.chunks(row_bytes).take(height) {
+		png.write_row(row)?;
+	}
+	png.finish()?;
+	Ok(())
+}
+
+//a whole zlib stream of data's rows (the same as PngWriter makes, just all at once)
+fn zlib_rows(data: &[u8], row_bytes: usize) -> Vec<u8> {
+	let mut stream = ZLIB_HEADER.to_vec();
+	let mut rows = StoredRows::new();
+	for row in data.chunks(row_bytes) {
+		rows.push_row(row, &mut stream);
+	}
+	rows.finish(&mut stream);
+	stream
+}
+
+//an animated png that loops forever, with every frame a whole picture shown for delay milliseconds.
+//the number of frames goes at the start of the file, so it has to be known before any of them are.
+//viewers without apng support just show the first frame.
+pub struct ApngWriter<W: Write> {
+	writer: W,
+	width: usize,
+	height: usize,
+	row_bytes: usize,
+	frames_left: u32,
+	delay: u16,
+	sequence: u32, //fcTL and fdAT chunks are numbered together
+}
+
+impl<W: Write> ApngWriter<W> {
+	pub fn new(mut writer: W, width: usize, height: usize, format: PixelFormat, frames: u32, delay: u16) -> io::Result<ApngWriter<W>> {
+		if frames == 0 {
+			return Err(invalid_input("an animation needs at least one frame"));
+		}
+		write_png_header(&mut writer, width, height, format)?;
+
+		let mut control = Vec::with_capacity(8);
+		control.extend_from_slice(&frames.to_be_bytes());
+		control.extend_from_slice(&0u32.to_be_bytes()); //plays, 0 for forever
+		write_chunk(&mut writer, b"acTL", &control)?;
+
+		Ok(ApngWriter {
+			writer,
+			width,
+			height,
+			row_bytes: width * format.bytes_per_pixel(),
+			frames_left: frames,
+			delay,
+			sequence: 0,
+		})
+	}
+
+	//data is laid out as the format from new says
+	pub fn write_frame(&mut self, data: &[u8]) -> io::Result<()> {
+		if data.len() != self.row_bytes * self.height {
+			return Err(invalid_input("apng frame has the wrong size"));
+		}
+		if self.frames_left == 0 {
+			return Err(invalid_input("too many apng frames"));
+		}
+		let first = self.sequence == 0;
+		self.frames_left -= 1;
+
+		let mut control = Vec::with_capacity(26);
+		control.extend_from_slice(&self.sequence.to_be_bytes());
+		control.extend_from_slice(&(self.width as u32).to_be_bytes());
+		control.extend_from_slice(&(self.height as u32).to_be_bytes());
+		control.extend_from_slice(&[0; 8]); //x and y offsets
+		control.extend_from_slice(&self.delay.to_be_bytes());
+		control.extend_from_slice(&1000u16.to_be_bytes()); //so the delay is in milliseconds
+		control.extend_from_slice(&[0, 0]); //leave the frame there afterwards, and replace what was there (rather than blending)
+		write_chunk(&mut self.writer, b"fcTL", &control)?;
+		self.sequence += 1;
+
+		let stream = zlib_rows(data, self.row_bytes);
+		if first {
+			write_chunk(&mut self.writer, b"IDAT", &stream)
+		} else {
+			let mut frame_data = Vec::with_capacity(stream.len() + 4);
+			frame_data.extend_from_slice(&self.sequence.to_be_bytes());
+			frame_data.extend_from_slice(&stream);
+			self.sequence += 1;
+			write_chunk(&mut self.writer, b"fdAT", &frame_data)
+		}
+	}
+
+	pub fn finish(mut self) -> io::Result<W> {
+		if self.frames_left != 0 {
+			return Err(invalid_input("not enough apng frames"));
+		}
+		write_chunk(&mut self.writer, b"IEND", &[])?;
+		Ok(self.writer)
+	}
+}
+
+//----------------------------------------------------------------- floats
+
+//(r, g, b, alpha) of pixel `index` in an RgbaLinearF32 buffer
+fn read_float_pixel(data: &[u8], index: usize) -> [f32; 4] {
+	let mut pixel = [0.0; 4];
+	for (channel, value) in pixel.iter_mut().enumerate() {
+		let start = index * 16 + channel * 4;
+		*value = f32::from_le_bytes(data[start..start + 4].try_into().expect("slice has length 4"));
+	}
+	pixel
+}
+
+//portable float map: rgb only, bottom row first. The colour is left premultiplied (i.e. composited over black)
+pub fn write_pfm<W: Write>(writer: &mut W, width: usize, height: usize, format: PixelFormat, data: &[u8]) -> io::Result<()> {
+	if format != PixelFormat::RgbaLinearF32 {
+		return Err(invalid_input("pfm output needs the rgbaf32 pixel format"));
+	}
+
+	//negative scale means little endian
+	write!(writer, "PF\n{} {}\n-1.0\n", width, height)?;
+	for y in (0..height).rev() {
+		for x in 0..width {
+			let [r, g, b, _] = read_float_pixel(data, y * width + x);
+			writer.write_all(&r.to_le_bytes())?;
+			writer.write_all(&g.to_le_bytes())?;
+			writer.write_all(&b.to_le_bytes())?;
+		}
+	}
+	Ok(())
+}
+
+fn exr_attribute<W: Write>(writer: &mut W, name: &str, attribute_type: &str, value: &[u8]) -> io::Result<()> {
+	writer.write_all(name.as_bytes())?;
+	writer.write_all(&[0])?;
+	writer.write_all(attribute_type.as_bytes())?;
+	writer.write_all(&[0])?;
+	writer.write_all(&(value.len() as i32).to_le_bytes())?;
+	writer.write_all(value)
+}
+
+//openexr, uncompressed scanlines, 32 bit float channels.
+//exr expects linear premultiplied colour, which is exactly what LinearCol is, so nothing is lost at all.
+pub fn write_exr<W: Write>(writer: &mut W, width: usize, height: usize, format: PixelFormat, data: &[u8]) -> io::Result<()> {
+	if format != PixelFormat::RgbaLinearF32 {
+		return Err(invalid_input("exr output needs the rgbaf32 pixel format"));
+	}
+
+	let mut header: Vec<u8> = Vec::new();
+	header.extend_from_slice(&[0x76, 0x2f, 0x31, 0x01]); //magic
+	header.extend_from_slice(&2i32.to_le_bytes()); //version 2, single part scanline
+
+	//channels have to be in alphabetical order
+	let mut channels: Vec<u8> = Vec::new();
+	for name in ["A", "B", "G", "R"] {
+		channels.extend_from_slice(name.as_bytes());
+		channels.push(0);
+		channels.extend_from_slice(&2i32.to_le_bytes()); //FLOAT
+		channels.extend_from_slice(&[0, 0, 0, 0]); //pLinear and reserved
+		channels.extend_from_slice(&1i32.to_le_bytes()); //x sampling
+		channels.extend_from_slice(&1i32.to_le_bytes()); //y sampling
+	}
+	channels.push(0);
+
+	let mut window: Vec<u8> = Vec::new();
+	for value in [0, 0, width as i32 - 1, height as i32 - 1] {
+		window.extend_from_slice(&value.to_le_bytes());
+	}
+
+	exr_attribute(&mut header, "channels", "chlist", &channels)?;
+	exr_attribute(&mut header, "compression", "compression", &[0])?;
+	exr_attribute(&mut header, "dataWindow", "box2i", &window)?;
+	exr_attribute(&mut header, "displayWindow", "box2i", &window)?;
+	exr_attribute(&mut header, "lineOrder", "lineOrder", &[0])?;
+	exr_attribute(&mut header, "pixelAspectRatio", "float", &1.0f32.to_le_bytes())?;
+	exr_attribute(&mut header, "screenWindowCenter", "v2f", &[0; 8])?;
+	exr_attribute(&mut header, "screenWindowWidth", "float", &1.0f32.to_le_bytes())?;
+	header.push(0);
+
+	//offset table, then one block per scanline: (y, byte count, then each channel's whole row)
+	let row_bytes = width * 4 * 4;
+	let block_bytes = 8 + row_bytes;
+	let table_end = header.len() + 8 * height;
+	writer.write_all(&header)?;
+	for y in 0..height {
+		writer.write_all(&((table_end + y * block_bytes) as u64).to_le_bytes())?;
+	}
+
+	let mut row: Vec<u8> = Vec::with_capacity(row_bytes);
+	for y in 0..height {
+		row.clear();
+		//channel index into (r, g, b, alpha), in A B G R order
+		for channel in [3, 2, 1, 0] {
+			for x in 0..width {
+				row.extend_from_slice(&read_float_pixel(data, y * width + x)[channel].to_le_bytes());
+			}
+		}
+		writer.write_all(&(y as i32).to_le_bytes())?;
+		writer.write_all(&(row_bytes as i32).to_le_bytes())?;
+		writer.write_all(&row)?;
+	}
+	Ok(())
+}
+
+//----------------------------------------------------------------- gif
+
+//levels of each of r, g and b in the palette (the old web safe one, 6 x 6 x 6 colours)
+const GIF_LEVELS: usize = 6;
+
+//an animated gif that loops forever. Gif only has 256 colours, so every frame gets squashed into the web safe palette,
+//with the dithering picking between the levels either side of each colour, same as it does between bytes.
+//delays are in hundredths of a second (and browsers slow anything under 2 down to 10).
+pub struct GifWriter<W: Write> {
+	writer: W,
+	width: usize,
+	height: usize,
+	delay: u16,
+	dithering: Dithering,
+}
+
+impl<W: Write> GifWriter<W> {
+	pub fn new(mut writer: W, width: usize, height: usize, delay: u16, dithering: Dithering) -> io::Result<GifWriter<W>> {
+		let (Ok(gif_width), Ok(gif_height)) = (u16::try_from(width), u16::try_from(height)) else {
+			return Err(invalid_input("gifs can't be more than 65535 pixels across"));
+		};
+		writer.write_all(b"GIF89a")?;
+		writer.write_all(&gif_width.to_le_bytes())?;
+		writer.write_all(&gif_height.to_le_bytes())?;
+		//a global palette of 256 colours, background colour 0, square pixels
+		writer.write_all(&[0xf7, 0, 0])?;
+
+		let mut palette = [0u8; 3 * 256];
+		for index in 0..GIF_LEVELS.pow(3) {
+			let level = |place: usize| ((index / place) % GIF_LEVELS * 255 / (GIF_LEVELS - 1)) as u8;
+			palette[3 * index..3 * index + 3].copy_from_slice(&[level(GIF_LEVELS * GIF_LEVELS), level(GIF_LEVELS), level(1)]);
+		}
+		writer.write_all(&palette)?;
+
+		//the netscape extension, to loop forever
+		writer.write_all(&[0x21, 0xff, 11])?;
+		writer.write_all(b"NETSCAPE2.0")?;
+		writer.write_all(&[3, 1, 0, 0, 0])?;
+
+		Ok(GifWriter {writer, width, height, delay, dithering})
+	}
+
+	//rgb is PixelFormat::Rgb bytes
+	pub fn write_frame(&mut self, rgb: &[u8]) -> io::Result<()> {
+		if rgb.len() != 3 * self.width * self.height {
+			return Err(invalid_input("gif frame has the wrong size"));
+		}
+		let indices: Vec<u8> = rgb.chunks(3).enumerate().map(|(position, pixel)| {
+			//the byte's already been rounded or dithered once, so without dithering this one rounds to the nearest level
+			let threshold = self.dithering.threshold(position % self.width, position / self.width).unwrap_or(0.5);
+			let level = |byte: u8| ((byte as f64 * (GIF_LEVELS - 1) as f64 / 255.0 + threshold) as usize).min(GIF_LEVELS - 1);
+			(level(pixel[0]) * GIF_LEVELS * GIF_LEVELS + level(pixel[1]) * GIF_LEVELS + level(pixel[2])) as u8
+		}).collect();
+
+		//graphic control: leave the frame there afterwards, delay, no transparency
+		self.writer.write_all(&[0x21, 0xf9, 4, 0x04])?;
+		self.writer.write_all(&self.delay.to_le_bytes())?;
+		self.writer.write_all(&[0, 0])?;
+
+		//the whole screen, with no palette of its own and not interlaced
+		self.writer.write_all(&[0x2c, 0, 0, 0, 0])?;
+		self.writer.write_all(&(self.width as u16).to_le_bytes())?;
+		self.writer.write_all(&(self.height as u16).to_le_bytes())?;
+		self.writer.write_all(&[0])?;
+
+		self.writer.write_all(&[8])?; //bits per pixel, for the lzw
+		for block in lzw_encode(&indices).chunks(255) {
+			self.writer.write_all(&[block.len() as u8])?;
+			self.writer.write_all(block)?;
+		}
+		self.writer.write_all(&[0])
+	}
+
+	pub fn finish(mut self) -> io::Result<W> {
+		self.writer.write_all(&[0x3b])?;
+		Ok(self.writer)
+	}
+}
+
+//gif's lzw, for 8 bit pixels: codes start at 9 bits and grow to 12, and the table starts over once it's full.
+//how wide each code is has to match what decoders expect, which lags a code behind the table (hence the - 1s)
+fn lzw_encode(indices: &[u8]) -> Vec<u8> {
+	const CLEAR: u16 = 256;
+	const END: u16 = 257;
+	const FIRST_FREE: u16 = 258;
+	const MAX_CODES: u16 = 4096;
+	let width = |codes: u16| (u16::BITS - codes.leading_zeros()).clamp(9, 12);
+
+	let mut output = Vec::new();
+	let (mut buffer, mut buffered) = (0u32, 0);
+	let mut emit = |code: u16, bits: u32| {
+		buffer |= (code as u32) << buffered;
+		buffered += bits;
+		while buffered >= 8 {
+			output.push(buffer as u8);
+			buffer >>= 8;
+			buffered -= 8;
+		}
+	};
+
+	let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+	let mut next_code = FIRST_FREE;
+	emit(CLEAR, 9);
+	let mut current: Option<u16> = None;
+	for &index in indices {
+		let Some(prefix) = current else {
+			current = Some(index as u16);
+			continue;
+		};
+		if let Some(&code) = table.get(&(prefix, index)) {
+			current = Some(code);
+			continue;
+		}
+		emit(prefix, width(next_code - 1));
+		table.insert((prefix, index), next_code);
+		next_code += 1;
+		if next_code == MAX_CODES {
+			emit(CLEAR, width(next_code - 1));
+			table.clear();
+			next_code = FIRST_FREE;
+		}
+		current = Some(index as u16);
+	}
+	if let Some(prefix) = current {
+		emit(prefix, width(next_code - 1));
+		//the decoder adds a code for that last one, so the end code is read a step later
+		emit(END, width(next_code));
+	} else {
+		emit(END, 9);
+	}
+	emit(0, 7); //whatever's left over
+	output
+}
@@ -1,6 +1,7 @@
-use std::{rc::Rc, borrow::Borrow};
+use std::{rc::Rc, borrow::Borrow, sync::Arc, cell::RefCell, collections::HashMap};
 
 use super::colour_format::LinearCol;
+use super::texture::Texture;
 
 //CRITICAL (no slow impls allowed). Idea: big Rc<TreeNode> things should also be fast.
 pub trait SplitInterval {
@@ -47,6 +48,13 @@ impl<F: Fn(f64) -> LinearCol + Clone + 'static> From<F> for FunctionInterval<F>
 	}
 }
 
+impl<F: Fn(f64) -> LinearCol + Clone + 'static> FunctionInterval<F> {
+	//just part of func, or somewhere past [0,1] for funcs that go round in circles (see animation's palette cycling)
+	pub fn new(func: F, start: f64, end: f64) -> FunctionInterval<F> {
+		FunctionInterval {func, start, end}
+	}
+}
+
 impl<F: Fn(f64) -> LinearCol + Clone + 'static> SplitInterval for FunctionInterval<F> {
 	fn get(&self) -> LinearCol {
 		let middle = (self.start + self.end) / 2.0;
@@ -70,6 +78,131 @@ impl<F: Fn(f64) -> LinearCol + Clone + 'static> SplitInterval for FunctionInterv
 	}
 }
 
+//a FunctionInterval for closures too slow to call for every pixel. Every piece split off the same one shares a cache,
+//keyed by the (start, end) of the piece, so each piece's colour only gets worked out once.
+//once the cache has capacity entries it stops growing (and the rest just get worked out every time, like FunctionInterval)
+pub struct CachedFunctionInterval<F> where F: Fn(f64) -> LinearCol + Clone + 'static {
+	func: F,
+	start: f64,
+	end: f64,
+	cache: Rc<RefCell<HashMap<(u64, u64), LinearCol>>>,
+	capacity: usize,
+}
+
+impl<F: Fn(f64) -> LinearCol + Clone + 'static> CachedFunctionInterval<F> {
+	pub fn new(func: F, capacity: usize) -> CachedFunctionInterval<F> {
+		CachedFunctionInterval {
+			func,
+			start: 0.0,
+			end: 1.0,
+			cache: Rc::new(RefCell::new(HashMap::new())),
+			capacity,
+		}
+	}
+
+	fn piece(&self, start: f64, end: f64) -> Box<dyn SplitInterval> {
+		Box::new(CachedFunctionInterval {
+			func: self.func.clone(),
+			start,
+			end,
+			cache: self.cache.clone(),
+			capacity: self.capacity,
+		})
+	}
+}
+
+impl<F: Fn(f64) -> LinearCol + Clone + 'static> SplitInterval for CachedFunctionInterval<F> {
+	fn get(&self) -> LinearCol {
+		//the bits, since f64 isn't Hash. Pieces only ever come from halving, so equal pieces have equal bits
+		let key = (self.start.to_bits(), self.end.to_bits());
+		//(RefCell::borrow spelled out, since Borrow::borrow is in scope too)
+		if let Some(colour) = RefCell::borrow(&self.cache).get(&key) {
+			return *colour;
+		}
+		let colour = (self.func)((self.start + self.end) / 2.0);
+		let mut cache = self.cache.borrow_mut();
+		if cache.len() < self.capacity {
+			cache.insert(key, colour);
+		}
+		colour
+	}
+
+	fn split(&self) -> (Box<dyn SplitInterval>, Box<dyn SplitInterval>) {
+		let middle = (self.start + self.end) / 2.0;
+		(self.piece(self.start, middle), self.piece(middle, self.end))
+	}
+}
+
+//inner, until it's been split max_depth times. After that every piece is a ConstantInterval of the colour it had there,
+//so deep trees and slow functions stop costing anything below the depth where it can't be seen anyway
+pub struct DepthLimitedInterval {
+	inner: Box<dyn SplitInterval>,
+	depth_left: u32,
+}
+
+impl DepthLimitedInterval {
+	pub fn new(inner: Box<dyn SplitInterval>, max_depth: u32) -> DepthLimitedInterval {
+		DepthLimitedInterval {
+			inner,
+			depth_left: max_depth,
+		}
+	}
+}
+
+impl SplitInterval for DepthLimitedInterval {
+	fn get(&self) -> LinearCol {
+		self.inner.get()
+	}
+
+	fn split(&self) -> (Box<dyn SplitInterval>, Box<dyn SplitInterval>) {
+		if self.depth_left == 0 {
+			let constant = ConstantInterval(self.inner.get());
+			return (Box::new(constant), Box::new(constant));
+		}
+		let (left, right) = self.inner.split();
+		(
+			Box::new(DepthLimitedInterval {inner: left, depth_left: self.depth_left - 1}),
+			Box::new(DepthLimitedInterval {inner: right, depth_left: self.depth_left - 1}),
+		)
+	}
+}
+
+//the same thing for trimers (see trimer), where every split makes three pieces rather than two.
+//only the simpler intervals here come in a three way version
+pub trait TernaryInterval {
+	fn get(&self) -> LinearCol;
+	fn split(&self) -> (Box<dyn TernaryInterval>, Box<dyn TernaryInterval>, Box<dyn TernaryInterval>);
+}
+
+impl TernaryInterval for ConstantInterval {
+	fn get(&self) -> LinearCol {
+		self.0
+	}
+
+	fn split(&self) -> (Box<dyn TernaryInterval>, Box<dyn TernaryInterval>, Box<dyn TernaryInterval>) {
+		(Box::new(*self), Box::new(*self), Box::new(*self))
+	}
+}
+
+impl<F: Fn(f64) -> LinearCol + Clone + 'static> TernaryInterval for FunctionInterval<F> {
+	fn get(&self) -> LinearCol {
+		let middle = (self.start + self.end) / 2.0;
+		(self.func)(middle)
+	}
+
+	fn split(&self) -> (Box<dyn TernaryInterval>, Box<dyn TernaryInterval>, Box<dyn TernaryInterval>) {
+		let third = (self.end - self.start) / 3.0;
+		let piece = |start: f64, end: f64| -> Box<dyn TernaryInterval> {
+			Box::new(FunctionInterval {func: self.func.clone(), start, end})
+		};
+		(
+			piece(self.start, self.start + third),
+			piece(self.start + third, self.end - third),
+			piece(self.end - third, self.end),
+		)
+	}
+}
+
 //first time ?Sized has come up in my experience
 impl<T: SplitInterval + ?Sized> SplitInterval for Rc<T> {
 	fn get(&self) -> LinearCol {
@@ -83,6 +216,18 @@ impl<T: SplitInterval + ?Sized> SplitInterval for Rc<T> {
 	}
 }
 
+impl<T: TernaryInterval + ?Sized> TernaryInterval for Rc<T> {
+	fn get(&self) -> LinearCol {
+		let reference: &T = self.borrow();
+		reference.get()
+	}
+
+	fn split(&self) -> (Box<dyn TernaryInterval>, Box<dyn TernaryInterval>, Box<dyn TernaryInterval>) {
+		let reference: &T = self.borrow();
+		reference.split()
+	}
+}
+
 pub struct TreeInterval {
 	colour: LinearCol,
 	left_child: Rc<dyn SplitInterval>,
@@ -109,4 +254,235 @@ impl TreeInterval {
 	}
 }
 
-//import path from file into a closure?
+//TreeInterval with three children, for trimers
+pub struct TernaryTreeInterval {
+	colour: LinearCol,
+	children: [Rc<dyn TernaryInterval>; 3],
+}
+
+impl TernaryInterval for TernaryTreeInterval {
+	fn get(&self) -> LinearCol {
+		self.colour
+	}
+
+	fn split(&self) -> (Box<dyn TernaryInterval>, Box<dyn TernaryInterval>, Box<dyn TernaryInterval>) {
+		let [first, second, third] = &self.children;
+		(Box::new(first.clone()), Box::new(second.clone()), Box::new(third.clone()))
+	}
+}
+
+impl TernaryTreeInterval {
+	pub fn new(colour: LinearCol, children: [Rc<dyn TernaryInterval>; 3]) -> TernaryTreeInterval {
+		TernaryTreeInterval{
+			colour,
+			children,
+		}
+	}
+}
+
+//an image as an interval: [0,1] runs left to right along one row of the texture (v, from the top)
+#[derive(Clone)]
+pub struct StripInterval {
+	texture: Arc<Texture>,
+	row: f64,
+	start: f64,
+	end: f64,
+}
+
+impl StripInterval {
+	pub fn new(texture: Arc<Texture>, row: f64) -> StripInterval {
+		StripInterval {
+			texture,
+			row,
+			start: 0.0,
+			end: 1.0,
+		}
+	}
+}
+
+impl SplitInterval for StripInterval {
+	fn get(&self) -> LinearCol {
+		self.texture.sample((self.start + self.end) / 2.0, self.row)
+	}
+
+	fn split(&self) -> (Box<dyn SplitInterval>, Box<dyn SplitInterval>) {
+		let middle = (self.start + self.end) / 2.0;
+		(
+			Box::new(StripInterval {end: middle, ..self.clone()}),
+			Box::new(StripInterval {start: middle, ..self.clone()}),
+		)
+	}
+}
+
+//the whole texture, following the fractal's own subdivision: splits take turns halving it across and down,
+//so every piece of the fractal gets the matching piece of the picture (which square is which comes from the split path)
+#[derive(Clone)]
+pub struct TextureInterval {
+	texture: Arc<Texture>,
+	across: (f64, f64),
+	down: (f64, f64),
+	split_across: bool, //which way the next split goes
+}
+
+impl TextureInterval {
+	pub fn new(texture: Arc<Texture>) -> TextureInterval {
+		TextureInterval {
+			texture,
+			across: (0.0, 1.0),
+			down: (0.0, 1.0),
+			split_across: true,
+		}
+	}
+}
+
+impl SplitInterval for TextureInterval {
+	fn get(&self) -> LinearCol {
+		self.texture.sample((self.across.0 + self.across.1) / 2.0, (self.down.0 + self.down.1) / 2.0)
+	}
+
+	fn split(&self) -> (Box<dyn SplitInterval>, Box<dyn SplitInterval>) {
+		let (mut first, mut second) = (self.clone(), self.clone());
+		first.split_across = !self.split_across;
+		second.split_across = !self.split_across;
+		if self.split_across {
+			let middle = (self.across.0 + self.across.1) / 2.0;
+			first.across.1 = middle;
+			second.across.0 = middle;
+		} else {
+			let middle = (self.down.0 + self.down.1) / 2.0;
+			first.down.1 = middle;
+			second.down.0 = middle;
+		}
+		(Box::new(first), Box::new(second))
+	}
+}
+
+
+//two colours, picked by how many times the path down to here went right (odd or even).
+//since that changes with every level, the picture depends on how deep the pixels are, which is rather the point
+#[derive(Clone, Copy)]
+pub struct ParityInterval {
+	odd: bool,
+	colours: (LinearCol, LinearCol), //(even, odd)
+}
+
+impl ParityInterval {
+	pub fn new(even: LinearCol, odd: LinearCol) -> ParityInterval {
+		ParityInterval {
+			odd: false,
+			colours: (even, odd),
+		}
+	}
+}
+
+impl SplitInterval for ParityInterval {
+	fn get(&self) -> LinearCol {
+		if self.odd {self.colours.1} else {self.colours.0}
+	}
+
+	fn split(&self) -> (Box<dyn SplitInterval>, Box<dyn SplitInterval>) {
+		(
+			Box::new(*self),
+			Box::new(ParityInterval {odd: !self.odd, colours: self.colours}),
+		)
+	}
+}
+
+//instead of inner's colour, shows how many splits it took before inner's colour stopped changing
+//(that is, before both halves looked the same as the whole), as palette(depth).
+//that's only ever checked one split down: a piece whose halves have the same colour as it counts as settled, even if
+//something further in is different (thue_morse's pieces all average to the same grey, so it's palette(0) everywhere).
+//past the point it settles, every piece is palette of that depth, whatever inner does there.
+//anything that hasn't settled by the time it gets drawn is unsettled.
+//inner is split once, when each piece is made, and its halves are kept for the piece's own split until it settles
+pub struct StabilisationInterval<P> where P: Fn(u32) -> LinearCol + Clone + 'static {
+	halves: Option<(Rc<dyn SplitInterval>, Rc<dyn SplitInterval>)>, //None once settled, since then inner doesn't matter
+	depth: u32,
+	settled_at: Option<u32>,
+	palette: P,
+	unsettled: LinearCol,
+}
+
+impl<P: Fn(u32) -> LinearCol + Clone + 'static> StabilisationInterval<P> {
+	pub fn new(inner: Box<dyn SplitInterval>, palette: P, unsettled: LinearCol) -> StabilisationInterval<P> {
+		StabilisationInterval::at_depth(inner.as_ref(), 0, palette, unsettled)
+	}
+
+	fn at_depth(inner: &dyn SplitInterval, depth: u32, palette: P, unsettled: LinearCol) -> StabilisationInterval<P> {
+		let (left, right) = inner.split();
+		let (halves, settled_at) = if settled(inner.get(), left.get(), right.get()) {
+			(None, Some(depth))
+		} else {
+			(Some((Rc::from(left), Rc::from(right))), None)
+		};
+		StabilisationInterval {halves, depth, settled_at, palette, unsettled}
+	}
+}
+
+impl<P: Fn(u32) -> LinearCol + Clone + 'static> SplitInterval for StabilisationInterval<P> {
+	fn get(&self) -> LinearCol {
+		match self.settled_at {
+			Some(depth) => (self.palette)(depth),
+			None => self.unsettled,
+		}
+	}
+
+	fn split(&self) -> (Box<dyn SplitInterval>, Box<dyn SplitInterval>) {
+		let piece = |half: &Rc<dyn SplitInterval>| -> Box<dyn SplitInterval> {
+			Box::new(StabilisationInterval::at_depth(half.as_ref(), self.depth + 1, self.palette.clone(), self.unsettled))
+		};
+		match &self.halves {
+			Some((left, right)) => (piece(left), piece(right)),
+			None => {
+				let settled_piece = || -> Box<dyn SplitInterval> {
+					Box::new(StabilisationInterval {
+						halves: None,
+						depth: self.depth + 1,
+						settled_at: self.settled_at,
+						palette: self.palette.clone(),
+						unsettled: self.unsettled,
+					})
+				};
+				(settled_piece(), settled_piece())
+			}
+		}
+	}
+}
+
+//whether both halves are (near enough) the same colour as the whole
+fn settled(whole: LinearCol, left: LinearCol, right: LinearCol) -> bool {
+	const TOLERANCE: f64 = 1e-9;
+	let same = |a: LinearCol, b: LinearCol| {
+		(a.r_lin() - b.r_lin()).abs() < TOLERANCE
+			&& (a.g_lin() - b.g_lin()).abs() < TOLERANCE
+			&& (a.b_lin() - b.b_lin()).abs() < TOLERANCE
+			&& (a.alpha() - b.alpha()).abs() < TOLERANCE
+	};
+	same(whole, left) && same(whole, right)
+}
+
+//whatever inner would be, with every colour's hue turned by turns (see LinearCol::hue_rotated)
+pub struct HueRotatedInterval {
+	inner: Box<dyn SplitInterval>,
+	turns: f64,
+}
+
+impl HueRotatedInterval {
+	pub fn new(inner: Box<dyn SplitInterval>, turns: f64) -> HueRotatedInterval {
+		HueRotatedInterval {inner, turns}
+	}
+}
+
+impl SplitInterval for HueRotatedInterval {
+	fn get(&self) -> LinearCol {
+		self.inner.get().hue_rotated(self.turns)
+	}
+
+	fn split(&self) -> (Box<dyn SplitInterval>, Box<dyn SplitInterval>) {
+		let (left, right) = self.inner.split();
+		(
+			Box::new(HueRotatedInterval::new(left, self.turns)),
+			Box::new(HueRotatedInterval::new(right, self.turns)),
+		)
+	}
+}
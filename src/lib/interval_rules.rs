@@ -0,0 +1,257 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::colour_format::LinearCol;
+use super::interval_colouring::SplitInterval;
+
+//colourings described by rules about the split path (the "address") down to each piece, rather than built by hand like two_colouring.
+//addresses are strings of 0s and 1s, 0 for the first (left) half of a split and 1 for the second.
+//all of them end up as an Automaton: a finite set of states, each with a colour and a state for each half.
+//that makes them infinitely deep for free, since nothing gets built as it's split, the pieces just move between states.
+
+pub struct Automaton {
+	colours: Vec<LinearCol>,
+	transitions: Vec<(usize, usize)>, //(first half, second half)
+	start: usize,
+}
+
+impl Automaton {
+	//the colour of a state is what a piece in that state is drawn as, so it should look like what's inside it
+	//(the rule builders below take care of that)
+	pub fn new(colours: Vec<LinearCol>, transitions: Vec<(usize, usize)>, start: usize) -> Result<Automaton, String> {
+		if colours.len() != transitions.len() {
+			return Err("every state needs a colour and a pair of transitions".to_string());
+		}
+		let states = colours.len();
+		if start >= states || transitions.iter().any(|&(first, second)| first >= states || second >= states) {
+			return Err("transition to a state that doesn't exist".to_string());
+		}
+		Ok(Automaton {colours, transitions, start})
+	}
+
+	pub fn interval(self: &Rc<Self>) -> AutomatonInterval {
+		AutomatonInterval {
+			automaton: self.clone(),
+			state: self.start,
+		}
+	}
+}
+
+#[derive(Clone)]
+pub struct AutomatonInterval {
+	automaton: Rc<Automaton>,
+	state: usize,
+}
+
+impl SplitInterval for AutomatonInterval {
+	fn get(&self) -> LinearCol {
+		self.automaton.colours[self.state]
+	}
+
+	fn split(&self) -> (Box<dyn SplitInterval>, Box<dyn SplitInterval>) {
+		let (first, second) = self.automaton.transitions[self.state];
+		(
+			Box::new(AutomatonInterval {automaton: self.automaton.clone(), state: first}),
+			Box::new(AutomatonInterval {automaton: self.automaton.clone(), state: second}),
+		)
+	}
+}
+
+fn parse_address(address: &str) -> Result<Vec<usize>, String> {
+	address.chars().map(|digit| match digit {
+		'0' => Ok(0),
+		'1' => Ok(1),
+		_ => Err(format!("addresses are made of 0s and 1s, not {:?}", digit)),
+	}).collect()
+}
+
+//"everything whose address starts with prefix is this colour", with otherwise for the rest.
+//where prefixes overlap, the shorter one wins. A piece that's only partly covered gets the average of its halves,
+//the same way two_colouring's middle colour works
+pub fn prefix_rules(rules: &[(&str, LinearCol)], otherwise: LinearCol) -> Result<Automaton, String> {
+	//state 0 is otherwise, then one constant state per rule, then the prefixes still being matched (a trie, from the root)
+	let mut colours = vec![otherwise];
+	let mut transitions = vec![(0, 0)];
+	for (index, (_, colour)) in rules.iter().enumerate() {
+		colours.push(*colour);
+		transitions.push((index + 1, index + 1));
+	}
+
+	let root = colours.len();
+	let mut children: Vec<[Option<usize>; 2]> = vec![[None, None]]; //indexed by trie node, root first
+	let mut settled: Vec<Option<usize>> = vec![None]; //the state a trie node has become, once a rule matches there
+	for (index, (prefix, _)) in rules.iter().enumerate() {
+		let mut node = 0;
+		for digit in parse_address(prefix)? {
+			if settled[node].is_some() {
+				break; //a shorter rule already covers this one
+			}
+			node = match children[node][digit] {
+				Some(child) => child,
+				None => {
+					children.push([None, None]);
+					settled.push(None);
+					children[node][digit] = Some(children.len() - 1);
+					children.len() - 1
+				}
+			};
+		}
+		if settled[node].is_none() {
+			settled[node] = Some(index + 1);
+			children[node] = [None, None]; //anything longer under here is covered now
+		}
+	}
+
+	//trie node n becomes state root + n, unless it's settled (then it's just that rule's state)
+	let state_of = |node: usize| settled[node].unwrap_or(root + node);
+	for halves in &children {
+		let half = |digit: usize| halves[digit].map_or(0, state_of);
+		colours.push(otherwise); //filled in below
+		transitions.push((half(0), half(1)));
+	}
+
+	//children always come after their parent in the trie, so going backwards every half's colour is ready in time
+	for node in (0..children.len()).rev() {
+		let state = root + node;
+		let (first, second) = transitions[state];
+		colours[state] = (colours[first] + colours[second]) / 2.0;
+	}
+
+	Automaton::new(colours, transitions, state_of(0))
+}
+
+//a substitution (like thue-morse's 0 -> 01, 1 -> 10): each symbol's piece is made of the pieces of the symbols it's replaced by.
+//replacements have to be a power of two long (2 is a single split, 4 is two, and so on).
+//with rounds, the start symbol is replaced that many times and the symbols it ends up as are drawn flat in their colour.
+//without, it goes on forever, and a symbol's colour is only what it looks like from a long way down. Every piece has to look
+//like what's inside it (see Automaton::new), so that's the average the symbol settles on over all its replacements, which for
+//most substitutions (thue-morse included) is the same mix, and so the same colour, for every symbol
+pub fn substitution(rules: &[(char, &str, LinearCol)], start: char, rounds: Option<u32>) -> Result<Automaton, String> {
+	let mut symbols: HashMap<char, usize> = HashMap::new();
+	for (index, (symbol, _, _)) in rules.iter().enumerate() {
+		if symbols.insert(*symbol, index).is_some() {
+			return Err(format!("more than one rule for {:?}", symbol));
+		}
+	}
+	for (symbol, replacement, _) in rules {
+		let length = replacement.chars().count();
+		if length < 2 || !length.is_power_of_two() {
+			return Err(format!("{:?} has to be replaced by a power of two (at least 2) symbols", symbol));
+		}
+		if let Some(missing) = replacement.chars().find(|symbol| !symbols.contains_key(symbol)) {
+			return Err(format!("no rule for {:?}", missing));
+		}
+	}
+	if !symbols.contains_key(&start) {
+		return Err(format!("no rule for {:?}", start));
+	}
+
+	let mut builder = SubstitutionBuilder {
+		rules,
+		rounds,
+		states: HashMap::new(),
+		runs: Vec::new(),
+		transitions: Vec::new(),
+	};
+	let start = builder.state(&start.to_string(), 0);
+
+	//what each symbol looks like, round by round (just the one round without rounds, since then they're all the same)
+	let average = |run: &str, colours: &[LinearCol]| {
+		let length = run.chars().count() as f64;
+		run.chars().fold(LinearCol::new(0.0, 0.0, 0.0, 0.0), |total, symbol| total + colours[symbols[&symbol]]) / length
+	};
+	let mut by_round = vec![rules.iter().map(|(_, _, colour)| *colour).collect::<Vec<_>>()];
+	match rounds {
+		//from the last round, where the symbols are flat, back up to the first
+		Some(rounds) => for _ in 0..rounds {
+			let next = &by_round[0];
+			let colours = rules.iter().map(|(_, replacement, _)| average(replacement, next)).collect();
+			by_round.insert(0, colours);
+		},
+		//the colours that stay the same when every symbol becomes the average of its replacement. That's solved by going
+		//half way there over and over, since going all the way goes round in circles forever for substitutions like
+		//a -> bb, b -> aa, where a and b swap every round
+		None => {
+			const TOLERANCE: f64 = 1e-12;
+			const MAX_ROUNDS: usize = 10_000;
+			let colours = &mut by_round[0];
+			let mut converged = false;
+			for _ in 0..MAX_ROUNDS {
+				let next: Vec<LinearCol> = rules.iter().enumerate()
+					.map(|(index, (_, replacement, _))| (colours[index] + average(replacement, colours)) / 2.0)
+					.collect();
+				let change = next.iter().zip(colours.iter()).fold(0.0, |change: f64, (&new, &old)| change.max(distance(new, old)));
+				*colours = next;
+				if change < TOLERANCE {
+					converged = true;
+					break;
+				}
+			}
+			if !converged {
+				return Err(format!("the colours hadn't settled after {} rounds (try giving it a number of rounds)", MAX_ROUNDS));
+			}
+		},
+	}
+
+	let colours = builder.runs.iter().map(|(run, round)| average(run, &by_round[*round as usize])).collect();
+	Automaton::new(colours, builder.transitions, start)
+}
+
+//how far apart two colours are in the channel that's furthest apart
+fn distance(a: LinearCol, b: LinearCol) -> f64 {
+	[
+		a.r_lin() - b.r_lin(),
+		a.g_lin() - b.g_lin(),
+		a.b_lin() - b.b_lin(),
+		a.alpha() - b.alpha(),
+	].iter().fold(0.0, |furthest: f64, difference| furthest.max(difference.abs()))
+}
+
+//the states substitution makes, one for each run of symbols (a symbol or part of a replacement) in each round it turns up in
+struct SubstitutionBuilder<'a> {
+	rules: &'a [(char, &'a str, LinearCol)],
+	rounds: Option<u32>,
+	states: HashMap<(String, u32), usize>,
+	runs: Vec<(String, u32)>, //(run, round) for each state
+	transitions: Vec<(usize, usize)>,
+}
+
+impl SubstitutionBuilder<'_> {
+	//run has already been checked to only have symbols with rules
+	fn state(&mut self, run: &str, round: u32) -> usize {
+		//without rounds, every round is the same
+		let round = if self.rounds.is_some() {round} else {0};
+		let key = (run.to_string(), round);
+		if let Some(&state) = self.states.get(&key) {
+			return state;
+		}
+
+		//made before its halves, since they might lead back to it
+		let state = self.runs.len();
+		self.states.insert(key.clone(), state);
+		self.runs.push(key);
+		self.transitions.push((state, state));
+
+		//a single symbol splits into the halves of its replacement, a round further on (unless it's past the last round,
+		//where it's flat). Longer runs split in half where they are
+		let length = run.chars().count();
+		let (halves, round) = if length == 1 {
+			if self.rounds == Some(round) {
+				return state;
+			}
+			let symbol = run.chars().next().expect("run has one char (40211)");
+			let rules = self.rules;
+			let (_, replacement, _) = rules.iter().find(|(rule_symbol, _, _)| *rule_symbol == symbol).expect("run only has symbols with rules (40212)");
+			(*replacement, round + 1)
+		} else {
+			(run, round)
+		};
+		let middle = halves.char_indices().nth(halves.chars().count() / 2).map(|(position, _)| position).expect("run is long enough (40213)");
+		let (first_half, second_half) = halves.split_at(middle);
+		let (first_half, second_half) = (first_half.to_string(), second_half.to_string());
+		let first = self.state(&first_half, round);
+		let second = self.state(&second_half, round);
+		self.transitions[state] = (first, second);
+		state
+	}
+}
@@ -1,22 +1,49 @@
-pub mod fractal_worker;
+//the renderer, usable without the gui.
+//for most uses, render() below (with a preset from fractal_specification and a colouring from auxiliary) is all you need.
+
+#[cfg(feature = "gui")]
 pub mod canvas;
+#[cfg(feature = "gui")]
+pub mod controls;
+pub mod fractal_worker;
 
-//as library-esque modules, there's no reason to lint for this here.
-#[allow(dead_code)]
 pub mod dihedral;
-#[allow(dead_code)]
 pub mod dihedral_translation;
+pub mod similarity;
+pub mod dihedral12;
+pub mod dihedral12_translation;
 
 pub mod relevance_getter;
-#[allow(dead_code)]
 pub mod fractal_specification;
+pub mod trimer_specification;
 
-#[allow(dead_code)]
 pub mod auxiliary;
 
 pub mod triangle;
 pub mod square;
+pub mod trimer;
 
-#[allow(dead_code)]
 pub mod colour_format;
-pub mod interval_colouring;
\ No newline at end of file
+pub mod interval_colouring;
+pub mod interval_rules;
+pub mod texture;
+pub mod dithering;
+
+pub mod pixel_format;
+pub mod pixel_sink;
+pub mod image_output;
+pub mod headless;
+pub mod render;
+pub mod streaming;
+pub mod animation;
+pub mod zoom;
+pub mod tile_pyramid;
+pub mod tile_server;
+pub mod progress;
+pub mod scene;
+
+pub use fractal_specification::{FractalSpecification, PRESETS};
+pub use auxiliary::COLOURINGS;
+pub use colour_format::LinearCol;
+pub use render::{render, View, Image};
+pub use scene::Scene;
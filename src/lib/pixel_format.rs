@@ -0,0 +1,140 @@
+#[cfg(feature = "gui")]
+use druid::piet;
+
+use super::colour_format::LinearCol;
+use super::dithering::Dithering;
+
+//byte layouts for a canvas. The first four are the same as piet's, so the gui can hand them straight to make_image.
+//the rest are for headless output only.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PixelFormat {
+	Grayscale,
+	Rgb,
+	RgbaSeparate,
+	RgbaPremul,
+	//srgb, separate alpha, 16 bits per channel (big endian, since that's what png wants)
+	Rgba16,
+	//linear, premultiplied alpha, one little endian f32 per channel. This is LinearCol with as little lost as possible.
+	RgbaLinearF32,
+}
+
+impl PixelFormat {
+	pub fn bytes_per_pixel(self) -> usize {
+		match self {
+			PixelFormat::Grayscale => 1,
+			PixelFormat::Rgb => 3,
+			PixelFormat::RgbaSeparate => 4,
+			PixelFormat::RgbaPremul => 4,
+			PixelFormat::Rgba16 => 8,
+			PixelFormat::RgbaLinearF32 => 16,
+		}
+	}
+
+	//None if piet can't display it
+	#[cfg(feature = "gui")]
+	pub fn to_piet(self) -> Option<piet::ImageFormat> {
+		match self {
+			PixelFormat::Grayscale => Some(piet::ImageFormat::Grayscale),
+			PixelFormat::Rgb => Some(piet::ImageFormat::Rgb),
+			PixelFormat::RgbaSeparate => Some(piet::ImageFormat::RgbaSeparate),
+			PixelFormat::RgbaPremul => Some(piet::ImageFormat::RgbaPremul),
+			PixelFormat::Rgba16 | PixelFormat::RgbaLinearF32 => None,
+		}
+	}
+
+	pub fn from_name(name: &str) -> Option<PixelFormat> {
+		match name {
+			"gray8" => Some(PixelFormat::Grayscale),
+			"rgb8" => Some(PixelFormat::Rgb),
+			"rgba8" => Some(PixelFormat::RgbaSeparate),
+			"rgba8premul" => Some(PixelFormat::RgbaPremul),
+			"rgba16" => Some(PixelFormat::Rgba16),
+			"rgbaf32" => Some(PixelFormat::RgbaLinearF32),
+			_ => None,
+		}
+	}
+
+	//the other way round from from_name
+	pub fn name(self) -> &'static str {
+		match self {
+			PixelFormat::Grayscale => "gray8",
+			PixelFormat::Rgb => "rgb8",
+			PixelFormat::RgbaSeparate => "rgba8",
+			PixelFormat::RgbaPremul => "rgba8premul",
+			PixelFormat::Rgba16 => "rgba16",
+			PixelFormat::RgbaLinearF32 => "rgbaf32",
+		}
+	}
+
+	//writes one pixel into pixel (which should be exactly bytes_per_pixel long)
+	//threshold only matters for the 8 bit formats (see dithering::Dithering::threshold)
+	pub fn write_pixel(self, pixel: &mut [u8], colour: LinearCol, threshold: Option<f64>) {
+		match self {
+			PixelFormat::Grayscale => {
+				pixel[0] = colour.grayscale_dithered(threshold);
+			},
+			PixelFormat::Rgb => {
+				let srgb_col: (u8, u8, u8) = colour.rgb_dithered(threshold);
+				pixel[0] = srgb_col.0;
+				pixel[1] = srgb_col.1;
+				pixel[2] = srgb_col.2;
+			},
+			PixelFormat::RgbaSeparate => {
+				let srgba_col: (u8, u8, u8, u8) = colour.rgba_separate_dithered(threshold);
+				pixel[0] = srgba_col.0;
+				pixel[1] = srgba_col.1;
+				pixel[2] = srgba_col.2;
+				pixel[3] = srgba_col.3;
+			},
+			PixelFormat::RgbaPremul => {
+				let srgba_col: (u8, u8, u8, u8) = colour.rgba_premul_dithered(threshold);
+				pixel[0] = srgba_col.0;
+				pixel[1] = srgba_col.1;
+				pixel[2] = srgba_col.2;
+				pixel[3] = srgba_col.3;
+			},
+			PixelFormat::Rgba16 => {
+				let srgba_col: (u16, u16, u16, u16) = colour.rgba_separate_16();
+				pixel[0..2].copy_from_slice(&srgba_col.0.to_be_bytes());
+				pixel[2..4].copy_from_slice(&srgba_col.1.to_be_bytes());
+				pixel[4..6].copy_from_slice(&srgba_col.2.to_be_bytes());
+				pixel[6..8].copy_from_slice(&srgba_col.3.to_be_bytes());
+			},
+			PixelFormat::RgbaLinearF32 => {
+				pixel[ 0.. 4].copy_from_slice(&(colour.r_lin() as f32).to_le_bytes());
+				pixel[ 4.. 8].copy_from_slice(&(colour.g_lin() as f32).to_le_bytes());
+				pixel[ 8..12].copy_from_slice(&(colour.b_lin() as f32).to_le_bytes());
+				pixel[12..16].copy_from_slice(&(colour.alpha() as f32).to_le_bytes());
+			},
+		}
+	}
+}
+
+//a whole row-by-row image of LinearCols at once
+pub fn encode_pixels(colours: &[LinearCol], width: usize, format: PixelFormat, dithering: Dithering) -> Vec<u8> {
+	encode_rows(colours, width, 0, format, dithering)
+}
+
+//the same, for rows that start first_row down a bigger image (so the dithering lines up with the rest of it)
+pub fn encode_rows(colours: &[LinearCol], width: usize, first_row: usize, format: PixelFormat, dithering: Dithering) -> Vec<u8> {
+	let colour_depth = format.bytes_per_pixel();
+	let mut bytes = vec![0; colour_depth * colours.len()];
+	for (index, colour) in colours.iter().enumerate() {
+		let threshold = dithering.threshold(index % width, first_row + index / width);
+		format.write_pixel(&mut bytes[index * colour_depth .. (index + 1) * colour_depth], *colour, threshold);
+	}
+	bytes
+}
+
+#[cfg(feature = "gui")]
+impl From<piet::ImageFormat> for PixelFormat {
+	fn from(format: piet::ImageFormat) -> Self {
+		match format {
+			piet::ImageFormat::Grayscale => PixelFormat::Grayscale,
+			piet::ImageFormat::Rgb => PixelFormat::Rgb,
+			piet::ImageFormat::RgbaSeparate => PixelFormat::RgbaSeparate,
+			piet::ImageFormat::RgbaPremul => PixelFormat::RgbaPremul,
+			_ => panic!("Unsupported colour format"),
+		}
+	}
+}
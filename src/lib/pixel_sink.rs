@@ -0,0 +1,343 @@
+use std::sync::mpsc::Sender;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use super::colour_format::LinearCol;
+use super::dithering::Dithering;
+use super::pixel_format::{self, PixelFormat};
+
+//anything the renderer can draw into.
+//put is called once for every pixel inside the bounds, in the recursive tile order (so not row by row!)
+//the chunk hooks bracket each SENDING_SIZE block, for sinks that want to do something once a block is done.
+//everything takes &self, since sinks are usually shared with whoever is watching the render.
+pub trait PixelSink {
+	fn put(&self, x: usize, y: usize, colour: LinearCol);
+
+	fn begin_chunk(&self, _bounds: (usize, usize, usize, usize)) {}
+	fn commit_chunk(&self, _bounds: (usize, usize, usize, usize)) {}
+
+	//checked before each chunk. Once this is true, the rest of the render is skipped.
+	fn cancelled(&self) -> bool {false}
+}
+
+//so a sink can be handed over by reference, or boxed up
+impl<S: PixelSink + ?Sized> PixelSink for &S {
+	fn put(&self, x: usize, y: usize, colour: LinearCol) {(**self).put(x, y, colour)}
+	fn begin_chunk(&self, bounds: (usize, usize, usize, usize)) {(**self).begin_chunk(bounds)}
+	fn commit_chunk(&self, bounds: (usize, usize, usize, usize)) {(**self).commit_chunk(bounds)}
+	fn cancelled(&self) -> bool {(**self).cancelled()}
+}
+
+impl<S: PixelSink + ?Sized> PixelSink for Box<S> {
+	fn put(&self, x: usize, y: usize, colour: LinearCol) {(**self).put(x, y, colour)}
+	fn begin_chunk(&self, bounds: (usize, usize, usize, usize)) {(**self).begin_chunk(bounds)}
+	fn commit_chunk(&self, bounds: (usize, usize, usize, usize)) {(**self).commit_chunk(bounds)}
+	fn cancelled(&self) -> bool {(**self).cancelled()}
+}
+
+
+//the original canvas: bytes laid out as a PixelFormat says, behind a mutex so the gui can read it while it's being drawn
+pub struct ByteCanvas {
+	data: Arc<Mutex<Vec<u8>>>,
+	width: usize,
+	format: PixelFormat,
+	dithering: Dithering,
+}
+
+impl ByteCanvas {
+	pub fn new(width: usize, height: usize, format: PixelFormat, dithering: Dithering) -> ByteCanvas {
+		let data = Arc::new(Mutex::new(vec![0; format.bytes_per_pixel() * width * height]));
+		ByteCanvas::from_shared(data, width, format, dithering)
+	}
+
+	//for when someone else already holds the buffer (it has to be big enough)
+	pub fn from_shared(data: Arc<Mutex<Vec<u8>>>, width: usize, format: PixelFormat, dithering: Dithering) -> ByteCanvas {
+		ByteCanvas {data, width, format, dithering}
+	}
+
+	pub fn shared(&self) -> Arc<Mutex<Vec<u8>>> {
+		self.data.clone()
+	}
+
+	pub fn get_format(&self) -> PixelFormat {
+		self.format
+	}
+
+	//if anything else still holds on to the buffer, this copies it instead
+	pub fn into_bytes(self) -> Vec<u8> {
+		match Arc::try_unwrap(self.data) {
+			Ok(mutex) => mutex.into_inner().expect("Canvas lock poisoned"),
+			Err(shared) => shared.lock().expect("Canvas lock poisoned").clone(),
+		}
+	}
+}
+
+impl PixelSink for ByteCanvas {
+	fn put(&self, x: usize, y: usize, colour: LinearCol) {
+		let threshold = self.dithering.threshold(x, y);
+		let colour_depth = self.format.bytes_per_pixel();
+		let buffer_pos = colour_depth * (y * self.width + x);
+		let mut guard = self.data.lock().expect("Canvas lock poisoned");
+		self.format.write_pixel(&mut guard[buffer_pos .. buffer_pos + colour_depth], colour, threshold);
+		drop(guard);
+	}
+}
+
+
+//keeps every LinearCol exactly as it came out, so it can be encoded (maybe several times) afterwards
+pub struct FloatCanvas {
+	data: Mutex<Vec<LinearCol>>,
+	width: usize,
+	height: usize,
+}
+
+impl FloatCanvas {
+	pub fn new(width: usize, height: usize) -> FloatCanvas {
+		FloatCanvas {
+			data: Mutex::new(vec![LinearCol::new(0.0, 0.0, 0.0, 1.0); width * height]),
+			width,
+			height,
+		}
+	}
+
+	pub fn get_width(&self) -> usize {
+		self.width
+	}
+
+	pub fn get_height(&self) -> usize {
+		self.height
+	}
+
+	pub fn into_colours(self) -> Vec<LinearCol> {
+		self.data.into_inner().expect("Canvas lock poisoned")
+	}
+
+	pub fn to_bytes(&self, format: PixelFormat, dithering: Dithering) -> Vec<u8> {
+		let guard = self.data.lock().expect("Canvas lock poisoned");
+		pixel_format::encode_pixels(&guard, self.width, format, dithering)
+	}
+}
+
+impl PixelSink for FloatCanvas {
+	fn put(&self, x: usize, y: usize, colour: LinearCol) {
+		let mut guard = self.data.lock().expect("Canvas lock poisoned");
+		guard[y * self.width + x] = colour;
+	}
+}
+
+
+//anti-aliasing: the renderer draws factor times bigger than inner, and every factor x factor block
+//gets averaged down into one pixel of inner as soon as its last sample arrives.
+//every sample has to be put exactly once, which the renderer does.
+pub struct Supersampled<S> {
+	inner: S,
+	factor: usize,
+	width: usize, //of inner
+	samples: Mutex<Vec<(LinearCol, usize)>>, //running sum and count for each pixel of inner
+}
+
+impl<S: PixelSink> Supersampled<S> {
+	pub fn new(inner: S, width: usize, height: usize, factor: usize) -> Supersampled<S> {
+		Supersampled {
+			inner,
+			factor,
+			width,
+			samples: Mutex::new(vec![(LinearCol::new(0.0, 0.0, 0.0, 0.0), 0); width * height]),
+		}
+	}
+
+	//the bounds of inner that cover the given (large) bounds
+	fn shrink(&self, bounds: (usize, usize, usize, usize)) -> (usize, usize, usize, usize) {
+		(
+			bounds.0 / self.factor,
+			bounds.1 / self.factor,
+			bounds.2.div_ceil(self.factor),
+			bounds.3.div_ceil(self.factor),
+		)
+	}
+}
+
+impl<S: PixelSink> PixelSink for Supersampled<S> {
+	fn put(&self, x: usize, y: usize, colour: LinearCol) {
+		let (small_x, small_y) = (x / self.factor, y / self.factor);
+		let block_size = self.factor * self.factor;
+		let mut guard = self.samples.lock().expect("Canvas lock poisoned");
+		let entry = &mut guard[small_y * self.width + small_x];
+		entry.0 = entry.0 + colour;
+		entry.1 += 1;
+		let finished = if entry.1 == block_size {Some(entry.0 / block_size as f64)} else {None};
+		drop(guard);
+
+		if let Some(average) = finished {
+			self.inner.put(small_x, small_y, average);
+		}
+	}
+
+	fn begin_chunk(&self, bounds: (usize, usize, usize, usize)) {self.inner.begin_chunk(self.shrink(bounds))}
+	fn commit_chunk(&self, bounds: (usize, usize, usize, usize)) {self.inner.commit_chunk(self.shrink(bounds))}
+	fn cancelled(&self) -> bool {self.inner.cancelled()}
+}
+
+
+//doesn't keep the image at all, just what it looked like overall
+#[derive(Clone, Copy)]
+pub struct Statistics {
+	pub pixels: usize,
+	pub chunks: usize,
+	//plain average of every pixel (still premultiplied, as always)
+	pub mean: LinearCol,
+	pub min_luminance: f64,
+	pub max_luminance: f64,
+	//pixels with no alpha at all
+	pub transparent: usize,
+}
+
+pub struct StatisticsSink {
+	totals: Mutex<(Statistics, LinearCol)>,
+}
+
+impl StatisticsSink {
+	pub fn new() -> StatisticsSink {
+		let empty = Statistics {
+			pixels: 0,
+			chunks: 0,
+			mean: LinearCol::new(0.0, 0.0, 0.0, 0.0),
+			min_luminance: f64::INFINITY,
+			max_luminance: f64::NEG_INFINITY,
+			transparent: 0,
+		};
+		StatisticsSink {totals: Mutex::new((empty, LinearCol::new(0.0, 0.0, 0.0, 0.0)))}
+	}
+
+	pub fn get(&self) -> Statistics {
+		let guard = self.totals.lock().expect("Statistics lock poisoned");
+		let (mut statistics, sum) = *guard;
+		if statistics.pixels != 0 {
+			statistics.mean = sum / statistics.pixels as f64;
+		}
+		statistics
+	}
+}
+
+impl Default for StatisticsSink {
+	fn default() -> Self {
+		StatisticsSink::new()
+	}
+}
+
+impl PixelSink for StatisticsSink {
+	fn put(&self, _x: usize, _y: usize, colour: LinearCol) {
+		let mut guard = self.totals.lock().expect("Statistics lock poisoned");
+		let (statistics, sum) = &mut *guard;
+		statistics.pixels += 1;
+		statistics.min_luminance = statistics.min_luminance.min(colour.y_lin());
+		statistics.max_luminance = statistics.max_luminance.max(colour.y_lin());
+		if colour.alpha() == 0.0 {
+			statistics.transparent += 1;
+		}
+		*sum = *sum + colour;
+	}
+
+	fn commit_chunk(&self, _bounds: (usize, usize, usize, usize)) {
+		self.totals.lock().expect("Statistics lock poisoned").0.chunks += 1;
+	}
+}
+
+
+//a finished piece of the canvas, already turned into bytes. pixels is row by row within bounds.
+pub struct DamageRect {
+	pub bounds: (usize, usize, usize, usize),
+	pub pixels: Vec<u8>,
+}
+
+//how many pixels from outside any chunk to collect before sending them off.
+//(below a scale of 4 there are no chunks at all, so this is everything)
+const LOOSE_BATCH: usize = 1024;
+
+struct PendingDamage {
+	chunk: Option<DamageRect>, //the chunk being drawn right now
+	loose: Vec<DamageRect>, //single pixels
+}
+
+//encodes pixels straight into per-chunk buffers, and sends each buffer off as soon as its chunk is done (this is what the gui watches).
+//nothing is shared with the receiving end, so whoever's watching never has to wait on a lock the renderer holds.
+//setting the cancel flag stops the render. It also gets set if the receiver hangs up, since nobody wants the result anymore.
+pub struct DamageSink {
+	format: PixelFormat,
+	dithering: Dithering,
+	sender: Sender<Vec<DamageRect>>,
+	cancel: Arc<AtomicBool>,
+	pending: Mutex<PendingDamage>, //only ever locked by the rendering thread
+}
+
+impl DamageSink {
+	pub fn new(format: PixelFormat, dithering: Dithering, sender: Sender<Vec<DamageRect>>, cancel: Arc<AtomicBool>) -> DamageSink {
+		DamageSink {
+			format,
+			dithering,
+			sender,
+			cancel,
+			pending: Mutex::new(PendingDamage {chunk: None, loose: Vec::new()}),
+		}
+	}
+
+	fn send(&self, damage: Vec<DamageRect>) {
+		if self.sender.send(damage).is_err() {
+			self.cancel.store(true, Ordering::Relaxed);
+		}
+	}
+}
+
+impl PixelSink for DamageSink {
+	fn put(&self, x: usize, y: usize, colour: LinearCol) {
+		let threshold = self.dithering.threshold(x, y);
+		let colour_depth = self.format.bytes_per_pixel();
+		let mut pending = self.pending.lock().expect("Damage lock poisoned");
+
+		if let Some(chunk) = &mut pending.chunk {
+			let (left, top, right, bottom) = chunk.bounds;
+			if (left..right).contains(&x) && (top..bottom).contains(&y) {
+				let buffer_pos = colour_depth * ((y - top) * (right - left) + (x - left));
+				self.format.write_pixel(&mut chunk.pixels[buffer_pos .. buffer_pos + colour_depth], colour, threshold);
+				return;
+			}
+		}
+
+		let mut pixel = vec![0; colour_depth];
+		self.format.write_pixel(&mut pixel, colour, threshold);
+		pending.loose.push(DamageRect {bounds: (x, y, x + 1, y + 1), pixels: pixel});
+		if pending.loose.len() >= LOOSE_BATCH {
+			let batch = std::mem::take(&mut pending.loose);
+			drop(pending);
+			self.send(batch);
+		}
+	}
+
+	fn begin_chunk(&self, bounds: (usize, usize, usize, usize)) {
+		let size = (bounds.2 - bounds.0) * (bounds.3 - bounds.1) * self.format.bytes_per_pixel();
+		self.pending.lock().expect("Damage lock poisoned").chunk = Some(DamageRect {bounds, pixels: vec![0; size]});
+	}
+
+	fn commit_chunk(&self, _bounds: (usize, usize, usize, usize)) {
+		let chunk = self.pending.lock().expect("Damage lock poisoned").chunk.take();
+		if let Some(chunk) = chunk {
+			self.send(vec![chunk]);
+		}
+	}
+
+	fn cancelled(&self) -> bool {
+		self.cancel.load(Ordering::Relaxed)
+	}
+}
+
+//whatever single pixels are left over go out when the render's done with the sink
+impl Drop for DamageSink {
+	fn drop(&mut self) {
+		if let Ok(pending) = self.pending.get_mut() {
+			let loose = std::mem::take(&mut pending.loose);
+			if !loose.is_empty() {
+				self.send(loose);
+			}
+		}
+	}
+}
@@ -0,0 +1,137 @@
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+use super::colour_format::LinearCol;
+use super::pixel_sink::PixelSink;
+
+//how far along a render is, counted in finished pixels of the final image
+//(chunks don't work as a unit, since below a scale of 4 there aren't any)
+#[derive(Clone, Copy, Debug)]
+pub struct Progress {
+	pub total: usize,
+	pub completed: usize,
+	pub elapsed: Duration,
+}
+
+impl Progress {
+	pub fn fraction(&self) -> f64 {
+		if self.total == 0 {
+			1.0
+		} else {
+			self.completed as f64 / self.total as f64
+		}
+	}
+
+	pub fn is_finished(&self) -> bool {
+		self.completed >= self.total
+	}
+
+	//assumes the rest goes as fast as it has so far, which is about right since every pixel costs roughly the same.
+	//None until there's anything to go on
+	pub fn remaining(&self) -> Option<Duration> {
+		if self.completed == 0 {
+			return None;
+		}
+		let left = self.total.saturating_sub(self.completed) as f64 / self.completed as f64;
+		Some(self.elapsed.mul_f64(left))
+	}
+
+	//short enough for the gui's side panel
+	pub fn eta(&self) -> String {
+		if self.is_finished() {
+			format!("done in {}", show_duration(self.elapsed))
+		} else {
+			match self.remaining() {
+				Some(remaining) => format!("about {} left", show_duration(remaining)),
+				None => "starting".to_string(),
+			}
+		}
+	}
+}
+
+//a text progress bar, for the command line
+impl fmt::Display for Progress {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		const BAR_LENGTH: usize = 30;
+		let filled = (self.fraction() * BAR_LENGTH as f64) as usize;
+		write!(
+			f,
+			"[{}{}] {:5.1}%  {} elapsed, {}",
+			"#".repeat(filled),
+			".".repeat(BAR_LENGTH - filled),
+			self.fraction() * 100.0,
+			show_duration(self.elapsed),
+			self.eta(),
+		)
+	}
+}
+
+fn show_duration(duration: Duration) -> String {
+	let seconds = duration.as_secs_f64();
+	if seconds < 60.0 {
+		format!("{:.1}s", seconds)
+	} else {
+		let whole = seconds as u64;
+		format!("{}m{:02}s", whole / 60, whole % 60)
+	}
+}
+
+//the counters behind a Progress. Shared between the render (through ProgressSink) and whoever wants to watch it.
+pub struct ProgressTracker {
+	total: usize,
+	completed: AtomicUsize,
+	start: Instant,
+	finished_after: OnceLock<Duration>, //so the clock stops once it's done
+}
+
+impl ProgressTracker {
+	pub fn new(total: usize) -> ProgressTracker {
+		ProgressTracker {
+			total,
+			completed: AtomicUsize::new(0),
+			start: Instant::now(),
+			finished_after: OnceLock::new(),
+		}
+	}
+
+	pub fn snapshot(&self) -> Progress {
+		Progress {
+			total: self.total,
+			completed: self.completed.load(Ordering::Relaxed),
+			elapsed: self.finished_after.get().copied().unwrap_or_else(|| self.start.elapsed()),
+		}
+	}
+
+	fn count_pixel(&self) {
+		let completed = self.completed.fetch_add(1, Ordering::Relaxed) + 1;
+		if completed == self.total {
+			//only the one call that finishes it gets here
+			let _ = self.finished_after.set(self.start.elapsed());
+		}
+	}
+}
+
+//counts every pixel on its way through to inner
+pub struct ProgressSink<S> {
+	inner: S,
+	tracker: Arc<ProgressTracker>,
+}
+
+impl<S: PixelSink> ProgressSink<S> {
+	pub fn new(inner: S, tracker: Arc<ProgressTracker>) -> ProgressSink<S> {
+		ProgressSink {inner, tracker}
+	}
+}
+
+impl<S: PixelSink> PixelSink for ProgressSink<S> {
+	fn put(&self, x: usize, y: usize, colour: LinearCol) {
+		self.inner.put(x, y, colour);
+		self.tracker.count_pixel();
+	}
+
+	fn begin_chunk(&self, bounds: (usize, usize, usize, usize)) {self.inner.begin_chunk(bounds)}
+	fn commit_chunk(&self, bounds: (usize, usize, usize, usize)) {self.inner.commit_chunk(bounds)}
+	fn cancelled(&self) -> bool {self.inner.cancelled()}
+}
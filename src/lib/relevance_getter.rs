@@ -1,18 +1,79 @@
 use super::fractal_specification::FractalSpecification;
-use super::dihedral_translation::{EvenD8Translation, IDENTITY};
+use super::dihedral_translation::{EvenD8Translation, OddD8TranslationSmall, OddD8TranslationLarge};
 
 use std::collections::HashSet;
 use std::collections::HashMap;
+use std::hash::Hash;
+use std::ops::Mul;
 
 use nalgebra::{DMatrix, DVector};
 
-pub fn get_relevance_list(fractal: &FractalSpecification) -> Vec<EvenD8Translation> {
+//everything below only needs this much of a fractal, so the same search works for dimers (two pieces, D8 and sqrt(2))
+//and trimers (three pieces, D12 and sqrt(3)), or anything else with the same shape of multiplication table.
+//Even is same sized, Small is a piece's worth smaller and Large a piece's worth bigger (see dihedral_translation)
+pub trait SelfSimilar {
+	type Even: Copy + Eq + Hash + Mul<Self::Large, Output = Self::Large>;
+	type Small: Copy + Mul<Self::Large, Output = Option<Self::Even>>;
+	type Large: Copy + Mul<Self::Even, Output = Self::Large> + Mul<Self::Small, Output = Self::Even>;
+
+	const IDENTITY: Self::Even;
+	fn inverse(transformation: Self::Even) -> Self::Even;
+	fn unsplit(piece: Self::Small) -> Self::Large;
+
+	//all the same length (the number of pieces)
+	fn production_pieces(&self) -> Vec<Self::Small>;
+	fn splitting_pieces(&self) -> Vec<Self::Small>;
+	fn is_acceptable(&self, transformation: Self::Even) -> bool;
+}
+
+impl SelfSimilar for FractalSpecification {
+	type Even = EvenD8Translation;
+	type Small = OddD8TranslationSmall;
+	type Large = OddD8TranslationLarge;
+
+	const IDENTITY: EvenD8Translation = super::dihedral_translation::IDENTITY;
+	fn inverse(transformation: EvenD8Translation) -> EvenD8Translation {transformation.inv()}
+	fn unsplit(piece: OddD8TranslationSmall) -> OddD8TranslationLarge {piece.inv()}
+
+	fn production_pieces(&self) -> Vec<OddD8TranslationSmall> {
+		let (a, b) = self.production_policy();
+		vec![a, b]
+	}
+
+	fn splitting_pieces(&self) -> Vec<OddD8TranslationSmall> {
+		let (a, b) = self.splitting_policy();
+		vec![a, b]
+	}
+
+	fn is_acceptable(&self, transformation: EvenD8Translation) -> bool {
+		self.acceptable(transformation)
+	}
+}
+
+//past this many relevant triangles, the search is taken to be running off forever (the presets need a few dozen)
+pub const MAX_RELEVANT: usize = 1000;
+
+//the searches below fail (rather than panicking) for policies that don't fit together, since those can come from scene files.
+//this runs all of them, for checking a fractal before anything tries to draw it
+pub fn check<F: SelfSimilar + ?Sized>(fractal: &F) -> Result<(), String> {
+	let relevance_list = find_relevance_list(fractal)?;
+	let requirement_list = find_requirement_list::<F>(&relevance_list);
+	find_splitter_list(fractal, &requirement_list)?;
+	find_weights(fractal, &relevance_list)?;
+	Ok(())
+}
+
+//the dimer versions of the searches below, in the shapes the square renderer uses
+pub fn get_relevance_list(fractal: &FractalSpecification) -> Result<Vec<EvenD8Translation>, String> {
+	find_relevance_list(fractal)
+}
+
+pub fn find_relevance_list<F: SelfSimilar + ?Sized>(fractal: &F) -> Result<Vec<F::Even>, String> {
 	//small:
-	let (split_a, split_b) = fractal.splitting_policy();
-	let (prod_a, prod_b) = fractal.production_policy();
+	let splits = fractal.splitting_pieces();
+	let productions = fractal.production_pieces();
 	//large:
-	let unsplit_a = split_a.inv();
-	let unsplit_b = split_b.inv();
+	let unsplits: Vec<F::Large> = splits.iter().map(|&split| F::unsplit(split)).collect();
 	
 	
 	//below is my rambling about how to know what unsplit to use in which case
@@ -43,15 +104,14 @@ pub fn get_relevance_list(fractal: &FractalSpecification) -> Vec<EvenD8Translati
 	//-----------------------------------------------------------------------------------------------------------
 	
 	//"find" (or rather assert) that the identity is required
-	let mut found_set: HashSet<EvenD8Translation> = HashSet::new();
-	let mut relevance_list: Vec<EvenD8Translation> = Vec::new();
-	let mut searching: Vec<EvenD8Translation> = Vec::new();
-	found_set.insert(IDENTITY);
-	relevance_list.push(IDENTITY);
-	searching.push(IDENTITY);
+	let mut found_set: HashSet<F::Even> = HashSet::new();
+	let mut relevance_list: Vec<F::Even> = Vec::new();
+	let mut searching: Vec<F::Even> = Vec::new();
+	found_set.insert(F::IDENTITY);
+	relevance_list.push(F::IDENTITY);
+	searching.push(F::IDENTITY);
 	
-	while !searching.is_empty() {
-		let current_transform = searching.pop().unwrap(); //obviously nonempty because above
+	while let Some(current_transform) = searching.pop() {
 		
 		//thread::sleep(time::Duration::from_secs(1));
 		//println!("\nSearching {:?}", current_transform);
@@ -60,88 +120,101 @@ pub fn get_relevance_list(fractal: &FractalSpecification) -> Vec<EvenD8Translati
 		//println!("product_b {:?}", current_transform * unsplit_b);
 		//println!("finalprod {:?}", prod_a * (current_transform * unsplit_b));
 		
-		//find the two triangles that this triangle produces into
-		
-
-		let a_unsplit_a = ( prod_a * (current_transform * unsplit_a) ).expect("Incompatible production");
-		let b_unsplit_b = ( prod_b * (current_transform * unsplit_b) ).expect("Incompatible production");
-		
-		let relevant_a = if fractal.acceptable(a_unsplit_a) {
-			a_unsplit_a
-		} else {
-			( prod_a * (current_transform * unsplit_b) ).expect("Incompatible production")
-		};
-		
-		let relevant_b = if fractal.acceptable(b_unsplit_b) {
-			b_unsplit_b
-		} else {
-			( prod_b * (current_transform * unsplit_a) ).expect("Incompatible production")
-		};
-		
-		//if they're new, make a discovery
-		if !found_set.contains(&relevant_a) {
-			found_set.insert(relevant_a);
-			relevance_list.push(relevant_a);
-			searching.push(relevant_a);
-		}
-		if !found_set.contains(&relevant_b) {
-			found_set.insert(relevant_b);
-			relevance_list.push(relevant_b);
-			searching.push(relevant_b);
+		//find the triangles that this triangle produces into.
+		//piece k tries unsplitting the same way first, then the others in order, and takes the first acceptable one
+		//(or the last one tried, if none of them are)
+		for (index, &production) in productions.iter().enumerate() {
+			let mut relevant = None;
+			for &unsplit in unsplits[index..].iter().chain(&unsplits[..index]) {
+				let Some(candidate) = production * (current_transform * unsplit) else {continue};
+				relevant = Some(candidate);
+				if fractal.is_acceptable(candidate) {
+					break;
+				}
+			}
+			let relevant = relevant.ok_or("the production policy can't be undone by any split (incompatible production)")?;
+			
+			//if it's new, make a discovery
+			if !found_set.contains(&relevant) {
+				found_set.insert(relevant);
+				relevance_list.push(relevant);
+				searching.push(relevant);
+				if relevance_list.len() > MAX_RELEVANT {
+					return Err(format!("more than {} triangles are relevant, so the policies probably never settle", MAX_RELEVANT));
+				}
+			}
 		}
 	}
 	
-	relevance_list
+	Ok(relevance_list)
 }
 
 //this is the one that defines "the kth position" relative to a position.
 //current_position * requirement_list[k] is the kth position relative to current_position
 pub fn to_requirement_list(relevance_list: &[EvenD8Translation]) -> Vec<EvenD8Translation> {
+	find_requirement_list::<FractalSpecification>(relevance_list)
+}
+
+pub fn find_requirement_list<F: SelfSimilar + ?Sized>(relevance_list: &[F::Even]) -> Vec<F::Even> {
 	relevance_list
-		.into_iter()
-		.map(|x| x.inv())
+		.iter()
+		.map(|&x| F::inverse(x))
 		.collect()
 }
 
+//one entry of a splitter list (see below)
+pub type Splitter = ((usize, bool), (usize, bool));
+
 //entries are: splitter_list[k] = (
 //	(which triangle produces into far  child's position k?, how did it split? (false for first way, true for second way)),
 //	(which triangle produces into near child's position k?, how did it split? (false for first way, true for second way))
 //)
-pub fn get_splitter_list(fractal: &FractalSpecification, requirement_list: &[EvenD8Translation]) -> Vec<((usize, bool), (usize, bool))> {
-	//make maps for each, then get the final list from that
-	let mut  far_hashmap: HashMap<EvenD8Translation, (usize, bool)> = HashMap::new();
-	let mut near_hashmap: HashMap<EvenD8Translation, (usize, bool)> = HashMap::new();
+pub fn get_splitter_list(fractal: &FractalSpecification, requirement_list: &[EvenD8Translation]) -> Result<Vec<Splitter>, String> {
+	Ok(find_splitter_list(fractal, requirement_list)?
+		.into_iter()
+		.map(|entry| ((entry[0].0, entry[0].1 == 1), (entry[1].0, entry[1].1 == 1)))
+		.collect())
+}
+
+//the same for any number of pieces: splitter_list[k][child] = (which triangle produces into child's position k?, which of its pieces is it?)
+//(children in the order of the splitting policy, so for dimers, far then near)
+pub fn find_splitter_list<F: SelfSimilar + ?Sized>(fractal: &F, requirement_list: &[F::Even]) -> Result<Vec<Vec<(usize, usize)>>, String> {
+	let splits = fractal.splitting_pieces();
+	let productions = fractal.production_pieces();
+
+	//make maps for each child, then get the final list from that
+	let mut child_hashmaps: Vec<HashMap<F::Even, (usize, usize)>> = vec![HashMap::new(); splits.len()];
 	
-	for index in 0..requirement_list.len() {
-		//add to the far
-		let current_far_transform_a = fractal.splitting_policy().0.inv() * requirement_list[index] * fractal.production_policy().0;
-		let current_far_transform_b = fractal.splitting_policy().0.inv() * requirement_list[index] * fractal.production_policy().1;
-		far_hashmap.insert(current_far_transform_a, (index, false));
-		far_hashmap.insert(current_far_transform_b, (index, true));
-		//add to the near
-		let current_near_transform_a = fractal.splitting_policy().1.inv() * requirement_list[index] * fractal.production_policy().0;
-		let current_near_transform_b = fractal.splitting_policy().1.inv() * requirement_list[index] * fractal.production_policy().1;
-		near_hashmap.insert(current_near_transform_a, (index, false));
-		near_hashmap.insert(current_near_transform_b, (index, true));
+	for (index, &requirement) in requirement_list.iter().enumerate() {
+		for (&split, hashmap) in splits.iter().zip(&mut child_hashmaps) {
+			for (piece, &production) in productions.iter().enumerate() {
+				let current_transform = F::unsplit(split) * requirement * production;
+				hashmap.insert(current_transform, (index, piece));
+			}
+		}
 	}
 	
 	//collect to the return vector
-	let mut splitter_list: Vec<((usize, bool), (usize, bool))> = Vec::new();
+	let mut splitter_list: Vec<Vec<(usize, usize)>> = Vec::new();
 	for transformation in requirement_list {
 		//all transformations here should have been seen. For that not to be the case, requirement_list must have been lacking
-		let current_entry = (
-			far_hashmap .get(transformation).expect("failed to construst splitter list (74218)").to_owned(),
-			near_hashmap.get(transformation).expect("failed to construst splitter list (74218)").to_owned()
-		);
+		let current_entry = child_hashmaps
+			.iter()
+			.map(|hashmap| hashmap.get(transformation).copied().ok_or("a relevant triangle isn't produced by any split (no splitter list)"))
+			.collect::<Result<_, _>>()?;
 		splitter_list.push(current_entry);
 	}
 	
-	splitter_list
+	Ok(splitter_list)
 }
 
 
 //these "weights" tell you how much of the k_th position fractal you'll find in the current triangle
-pub fn get_weights(fractal: &FractalSpecification, relevance_list: &[EvenD8Translation]) -> Vec<f64> {
+pub fn get_weights(fractal: &FractalSpecification, relevance_list: &[EvenD8Translation]) -> Result<Vec<f64>, String> {
+	find_weights(fractal, relevance_list)
+}
+
+pub fn find_weights<F: SelfSimilar + ?Sized>(fractal: &F, relevance_list: &[F::Even]) -> Result<Vec<f64>, String> {
 	//one equation for each triangle (with one dimension of redundancy)
 	//a final equation that says the triangle weights sum to 1 (assuming the total fractal area _is_ 1)
 	//solve this system of linear equations
@@ -153,67 +226,44 @@ pub fn get_weights(fractal: &FractalSpecification, relevance_list: &[EvenD8Trans
 	
 	//for now, something simple.
 	let length = relevance_list.len();
-	let (production_a, production_b) = fractal.production_policy();
-	let (split_a, split_b) = fractal.splitting_policy();
+	let productions = fractal.production_pieces();
+	let splits = fractal.splitting_pieces();
+	let piece_size = 1.0 / productions.len() as f64;
 
 	//an equation for each triangle, plus an extra one for weights
 	let mut coefficients: DMatrix<f64> = DMatrix::zeros(length + 1, length);
 
 	//tells you where in relevancelist to find a transform
-	let mut transform_finder: HashMap<EvenD8Translation, usize> = HashMap::new();
-	for index in 0..length {
-		transform_finder.insert(relevance_list[index], index);
+	let mut transform_finder: HashMap<F::Even, usize> = HashMap::new();
+	for (index, &transformation) in relevance_list.iter().enumerate() {
+		transform_finder.insert(transformation, index);
 	}
 
-	for index in 0..length {
-		let current_transformation = relevance_list[index];
+	for (index, &current_transformation) in relevance_list.iter().enumerate() {
 		
-		let unsplit_by_a = current_transformation * split_a.inv();
-		let unsplit_by_b = current_transformation * split_b.inv();
+		for &production in &productions {
+			//exactly one way of unsplitting should give something acceptable
+			let mut produces_into = None;
+			for &split in &splits {
+				//for dimers these always unwrap, because we will only ever have even coordinated current_transformations.
+				//trimers can have ways of unsplitting that don't land on the grid at all, which just don't count
+				let unsplit = current_transformation * F::unsplit(split);
+				let Some(candidate) = production * unsplit else {continue};
 
-		let produces_into_a = {
-			//these must always unwrap, because we will only ever have even coordinated current_transformations
-			let by_a = (production_a * unsplit_by_a).unwrap();
-			let by_b = (production_a * unsplit_by_b).unwrap();
-
-			if fractal.acceptable(by_a) && fractal.acceptable(by_b) {
-				panic!("help");
+				if fractal.is_acceptable(candidate) {
+					if produces_into.is_some() {
+						return Err("a triangle produces into two acceptable places at once".to_string());
+					}
+					produces_into = Some(candidate);
+				}
 			}
+			let produces_into = produces_into.ok_or("a triangle produces into nowhere acceptable")?;
 
-			if fractal.acceptable(by_a) {
-				by_a
-			} else if fractal.acceptable(by_b){
-				by_b
-			} else {
-				panic!("help")
-			}
-		};
-
-		let produces_into_b = {
-			let by_a = (production_b * unsplit_by_a).unwrap();
-			let by_b = (production_b * unsplit_by_b).unwrap();
-
-			if fractal.acceptable(by_a) && fractal.acceptable(by_b) {
-				panic!("help");
-			}
-
-			if fractal.acceptable(by_a) {
-				by_a
-			} else if fractal.acceptable(by_b){
-				by_b
-			} else {
-				panic!("help")
-			}
-		};
-
-		//we've found that this triangle produces into image_a with size 1/2,
-		//and image_b with size 1/2, so add those weights to the equations
-		//(if these images even do anything, that is)
-		let &image_a = transform_finder.get(&produces_into_a).expect("what");
-		coefficients[(image_a, index)] += 1.0 / 2.0;
-
-		let &image_b = transform_finder.get(&produces_into_b).expect("what");
-		coefficients[(image_b, index)] += 1.0 / 2.0;
+			//we've found that this triangle produces into that image with size 1/(number of pieces),
+			//so add those weights to the equations (if these images even do anything, that is)
+			let &image = transform_finder.get(&produces_into).ok_or("a triangle produces into one the relevance search didn't find")?;
+			coefficients[(image, index)] += piece_size;
+		}
 	}
 
 	for index in 0..length {
@@ -229,10 +279,10 @@ pub fn get_weights(fractal: &FractalSpecification, relevance_list: &[EvenD8Trans
 	constants[length] = 1.0;
 
 	//dump it all into the library function now. It's up to it to deal with the extra equation + dimension of redundancy
-	let weights: DVector<f64> = coefficients.svd(true, true).solve(&constants, 0.000001).expect("solve failed?");
+	let weights: DVector<f64> = coefficients.svd(true, true).solve(&constants, 0.000001).map_err(|error| format!("couldn't solve for the weights: {}", error))?;
 
-	weights
+	Ok(weights
 		.into_iter()
 		.map(|x| x.to_owned())
-		.collect()
+		.collect())
 }
\ No newline at end of file
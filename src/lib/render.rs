@@ -0,0 +1,148 @@
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+use super::colour_format::LinearCol;
+use super::dihedral_translation::EvenD8Translation;
+use super::dithering::Dithering;
+use super::fractal_specification::FractalSpecification;
+use super::headless;
+use super::image_output;
+use super::interval_colouring::SplitInterval;
+use super::pixel_format::{self, PixelFormat};
+use super::pixel_sink::{FloatCanvas, PixelSink, Supersampled};
+use super::progress::{ProgressSink, ProgressTracker};
+
+//what part of the plane to draw, and how big.
+//origin is where the fractal's origin lands in the image (y = down), and scale is as in square::draw_into_canvas
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct View {
+	pub width: usize,
+	pub height: usize,
+	pub origin: (isize, isize),
+	pub scale: u32,
+}
+
+impl View {
+	//origin in the middle of the image, like the gui does it
+	pub fn centred(width: usize, height: usize, scale: u32) -> View {
+		View {
+			width,
+			height,
+			origin: ( (width/2) as isize, (height/2) as isize ),
+			scale,
+		}
+	}
+
+	//the same picture, factor times bigger in each direction.
+	//factor has to be a power of two, since scale can only go up in whole steps
+	pub fn enlarged(self, factor: usize) -> View {
+		assert!(factor.is_power_of_two(), "enlargement must be a power of two (51120)");
+		View {
+			width: self.width * factor,
+			height: self.height * factor,
+			origin: (self.origin.0 * factor as isize, self.origin.1 * factor as isize),
+			scale: self.scale + factor.trailing_zeros(),
+		}
+	}
+}
+
+//a finished render, at full precision. Pick a PixelFormat only when you need bytes.
+pub struct Image {
+	width: usize,
+	height: usize,
+	pixels: Vec<LinearCol>,
+}
+
+impl Image {
+	//for renderers other than the ones below (see trimer). pixels are row by row, y = down
+	pub fn from_pixels(width: usize, height: usize, pixels: Vec<LinearCol>) -> Image {
+		assert_eq!(pixels.len(), width * height, "image is the wrong size for its pixels (41101)");
+		Image {width, height, pixels}
+	}
+
+	pub fn get_width(&self) -> usize {
+		self.width
+	}
+
+	pub fn get_height(&self) -> usize {
+		self.height
+	}
+
+	//row by row, y = down
+	pub fn pixels(&self) -> &[LinearCol] {
+		&self.pixels
+	}
+
+	pub fn get(&self, x: usize, y: usize) -> LinearCol {
+		self.pixels[y * self.width + x]
+	}
+
+	pub fn to_bytes(&self, format: PixelFormat, dithering: Dithering) -> Vec<u8> {
+		pixel_format::encode_pixels(&self.pixels, self.width, format, dithering)
+	}
+
+	//png, pfm or exr, going by the extension (see image_output::save_image)
+	pub fn save(&self, path: &Path, format: PixelFormat, dithering: Dithering) -> io::Result<()> {
+		image_output::save_image(path, self.width, self.height, format, &self.to_bytes(format, dithering))
+	}
+}
+
+//draws view into sink with antialiasing x antialiasing samples per pixel (1 for none, otherwise a power of two)
+pub fn draw_view<F, S>(fractal: &FractalSpecification, view: View, triangle_colouring: F, antialiasing: usize, sink: &S)
+	where F: FnMut(EvenD8Translation) -> Box<dyn SplitInterval>, S: PixelSink + ?Sized {
+	draw_region(fractal, view, (0, 0, view.width, view.height), triangle_colouring, antialiasing, sink);
+}
+
+//the same, but only the part of the view inside bounds (left, top, right, bottom)
+pub fn draw_region<F, S>(
+	fractal: &FractalSpecification,
+	view: View,
+	bounds: (usize, usize, usize, usize),
+	triangle_colouring: F,
+	antialiasing: usize,
+	sink: &S,
+) where F: FnMut(EvenD8Translation) -> Box<dyn SplitInterval>, S: PixelSink + ?Sized {
+	if antialiasing > 1 {
+		let large = view.enlarged(antialiasing);
+		let large_bounds = (bounds.0 * antialiasing, bounds.1 * antialiasing, bounds.2 * antialiasing, bounds.3 * antialiasing);
+		let supersampled = Supersampled::new(sink, view.width, view.height, antialiasing);
+		headless::render_region(fractal, triangle_colouring, large_bounds, large.origin, large.scale, &supersampled);
+	} else {
+		headless::render_region(fractal, triangle_colouring, bounds, view.origin, view.scale, sink);
+	}
+}
+
+//the whole renderer in one call
+pub fn render<F>(fractal: &FractalSpecification, view: View, triangle_colouring: F) -> Image
+	where F: FnMut(EvenD8Translation) -> Box<dyn SplitInterval> {
+	render_antialiased(fractal, view, triangle_colouring, 1)
+}
+
+pub fn render_antialiased<F>(fractal: &FractalSpecification, view: View, triangle_colouring: F, antialiasing: usize) -> Image
+	where F: FnMut(EvenD8Translation) -> Box<dyn SplitInterval> {
+	let canvas = FloatCanvas::new(view.width, view.height);
+	draw_view(fractal, view, triangle_colouring, antialiasing, &canvas);
+	Image {
+		width: view.width,
+		height: view.height,
+		pixels: canvas.into_colours(),
+	}
+}
+
+//the same, counting pixels into tracker as they finish (its total should be view.width * view.height)
+pub fn render_tracked<F>(
+	fractal: &FractalSpecification,
+	view: View,
+	triangle_colouring: F,
+	antialiasing: usize,
+	tracker: Arc<ProgressTracker>,
+) -> Image where F: FnMut(EvenD8Translation) -> Box<dyn SplitInterval> {
+	let canvas = FloatCanvas::new(view.width, view.height);
+	draw_view(fractal, view, triangle_colouring, antialiasing, &ProgressSink::new(&canvas, tracker));
+	Image {
+		width: view.width,
+		height: view.height,
+		pixels: canvas.into_colours(),
+	}
+}
@@ -0,0 +1,416 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use super::auxiliary::{COLOURINGS, TEXTURED_COLOURINGS};
+use super::dihedral_translation::EvenD8Translation;
+use super::dihedral_translation::OddD8TranslationSmall;
+use super::dithering::Dithering;
+use super::fractal_specification::{FractalSpecification, PRESETS, ACCEPTABILITY_RULES, SQUARE_CONFIGS};
+use super::fractal_worker::RenderSettings;
+use super::interval_colouring::SplitInterval;
+use super::pixel_format::PixelFormat;
+use super::progress::ProgressTracker;
+use super::relevance_getter;
+use super::render::{self, Image, View};
+use super::texture::Texture;
+use super::zoom;
+
+//a scene file is plain "key = value" lines, with # for comments. Anything left out keeps its default. For example:
+//
+//	fractal = hydra
+//	colouring = two
+//	width = 960
+//	height = 540
+//	origin = 480 270
+//	scale = 7
+//	antialiasing = 1
+//	format = rgba16
+//	dithering = none
+//	texture = photo.ppm
+//
+//texture is what the strip and texture colourings paint with, and is read from wherever the program is run from
+//(not next to the scene file). Without one they use auxiliary::default_texture.
+//"fractal = custom" spells the fractal out instead, with each transformation written as "flip rot x y":
+//
+//	production = true 0 -1 0, false 2 1 0
+//	splitting = true 1 1 0, false 1 1 0
+//	acceptable = even
+//	square_config = dense
+//	splitting_type = false false
+
+//a fractal from a scene file rather than from PRESETS.
+//the policies can be anything, but the acceptability rule and square config have to be ones the source knows about
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct CustomFractal {
+	pub production_policy: (OddD8TranslationSmall, OddD8TranslationSmall),
+	pub splitting_policy: (OddD8TranslationSmall, OddD8TranslationSmall),
+	pub acceptable: usize, //index into fractal_specification::ACCEPTABILITY_RULES
+	pub square_config: usize, //index into fractal_specification::SQUARE_CONFIGS
+	pub splitting_type: (bool, bool),
+}
+
+impl CustomFractal {
+	//the renderer wants a &'static, so every different custom fractal gets leaked (just the once)
+	pub fn specification(&self) -> &'static FractalSpecification {
+		static BUILT: Mutex<Vec<(CustomFractal, &'static FractalSpecification)>> = Mutex::new(Vec::new());
+
+		let mut built = BUILT.lock().expect("lock poisoned");
+		if let Some((_, fractal)) = built.iter().find(|(custom, _)| custom == self) {
+			return fractal;
+		}
+
+		let fractal: &'static FractalSpecification = Box::leak(Box::new(self.build()));
+		built.push((*self, fractal));
+		fractal
+	}
+
+	//whether the policies fit together well enough to draw anything (most made up ones don't)
+	pub fn check(&self) -> Result<(), String> {
+		relevance_getter::check(&self.build())
+	}
+
+	fn build(&self) -> FractalSpecification {
+		FractalSpecification::new(
+			self.production_policy,
+			self.splitting_policy,
+			&ACCEPTABILITY_RULES[self.acceptable].1,
+			&SQUARE_CONFIGS[self.square_config].1,
+			self.splitting_type,
+		)
+	}
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SceneFractal {
+	Preset(usize), //index into fractal_specification::PRESETS
+	Custom(CustomFractal),
+}
+
+impl SceneFractal {
+	pub fn specification(&self) -> &'static FractalSpecification {
+		match self {
+			SceneFractal::Preset(index) => PRESETS[*index].1,
+			SceneFractal::Custom(custom) => custom.specification(),
+		}
+	}
+}
+
+//a texture, and the file it came from so a scene can say so again. Two are the same if they came from the same file
+#[derive(Clone)]
+pub struct SceneTexture {
+	pub path: PathBuf,
+	pub texture: Arc<Texture>,
+}
+
+impl SceneTexture {
+	pub fn load(path: &Path) -> Result<SceneTexture, String> {
+		let texture = Texture::load(path).map_err(|error| format!("couldn't load {}: {}", path.display(), error))?;
+		Ok(SceneTexture {path: path.to_path_buf(), texture: Arc::new(texture)})
+	}
+}
+
+impl PartialEq for SceneTexture {
+	fn eq(&self, other: &SceneTexture) -> bool {
+		self.path == other.path
+	}
+}
+
+impl fmt::Debug for SceneTexture {
+	fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+		write!(formatter, "SceneTexture({})", self.path.display())
+	}
+}
+
+//everything it takes to draw one particular picture and write it out, so the gui and the command line can agree on it
+#[derive(Clone, PartialEq, Debug)]
+pub struct Scene {
+	pub fractal: SceneFractal,
+	pub colouring: usize, //index into auxiliary::COLOURINGS
+	pub width: usize,
+	pub height: usize,
+	pub origin: (isize, isize), //as in render::View
+	pub scale: u32,
+	pub antialiasing: usize, //as in render::draw_view
+	pub format: PixelFormat,
+	pub dithering: Dithering,
+	pub texture: Option<SceneTexture>, //for the colourings in auxiliary::TEXTURED_COLOURINGS
+}
+
+impl Default for Scene {
+	//what the gui starts up with
+	fn default() -> Self {
+		let (width, height) = (16*60, 9*60);
+		Scene {
+			fractal: SceneFractal::Preset(index_of(&PRESETS, "hydra").expect("hydra is a preset")),
+			colouring: index_of(&COLOURINGS, "two").expect("two is a colouring"),
+			width,
+			height,
+			origin: ( (width/2) as isize, (height/2) as isize ),
+			scale: 7,
+			antialiasing: 1,
+			format: PixelFormat::Rgba16,
+			dithering: Dithering::None,
+			texture: None,
+		}
+	}
+}
+
+impl Scene {
+	pub fn view(&self) -> View {
+		View {
+			width: self.width,
+			height: self.height,
+			origin: self.origin,
+			scale: self.scale,
+		}
+	}
+
+	//the colouring, painting with the scene's texture if it has one and the colouring uses it
+	pub fn triangle_colouring(&self) -> impl Fn(EvenD8Translation) -> Box<dyn SplitInterval> + Clone + Send + Sync {
+		let (name, plain) = COLOURINGS[self.colouring];
+		let textured = self.texture.as_ref().and_then(|texture| {
+			let (_, colouring) = TEXTURED_COLOURINGS.iter().find(|(textured_name, _)| *textured_name == name)?;
+			Some((*colouring, texture.texture.clone()))
+		});
+		move |transform| match &textured {
+			Some((colouring, texture)) => colouring(texture.clone(), transform),
+			None => plain(transform),
+		}
+	}
+
+	//the gui's way of saying the same thing. Its colourings are plain fns, so the texture is always the default one
+	pub fn render_settings(&self) -> RenderSettings {
+		RenderSettings {
+			fractal: self.fractal.specification(),
+			colouring: COLOURINGS[self.colouring].1,
+			scale: self.scale,
+			offset: (
+				self.origin.0 - (self.width/2) as isize,
+				self.origin.1 - (self.height/2) as isize,
+			),
+			antialiasing: self.antialiasing,
+		}
+	}
+
+	//changes the canvas size, keeping the origin where it was relative to the middle
+	pub fn resize(&mut self, width: usize, height: usize) {
+		self.origin.0 += (width/2) as isize - (self.width/2) as isize;
+		self.origin.1 += (height/2) as isize - (self.height/2) as isize;
+		self.width = width;
+		self.height = height;
+	}
+
+	//the same scene, drawing view instead (for drawing it in pieces, like tile_pyramid does)
+	pub fn showing(&self, view: View) -> Scene {
+		Scene {
+			width: view.width,
+			height: view.height,
+			origin: view.origin,
+			scale: view.scale,
+			..self.clone()
+		}
+	}
+
+	pub fn render(&self) -> Image {
+		render::render_antialiased(self.fractal.specification(), self.view(), self.triangle_colouring(), self.antialiasing)
+	}
+
+	//tracker's total should be width * height
+	pub fn render_tracked(&self, tracker: Arc<ProgressTracker>) -> Image {
+		render::render_tracked(self.fractal.specification(), self.view(), self.triangle_colouring(), self.antialiasing, tracker)
+	}
+
+	//renders and saves in the scene's own format (the extension still picks png, pfm or exr)
+	pub fn render_to_file(&self, path: &Path) -> io::Result<()> {
+		self.render().save(path, self.format, self.dithering)
+	}
+
+	//(the scene, whether the file said what format to use), as parse
+	pub fn load(path: &Path) -> Result<(Scene, bool), String> {
+		let text = fs::read_to_string(path).map_err(|error| format!("couldn't read {}: {}", path.display(), error))?;
+		Scene::parse(&text).map_err(|error| format!("{}: {}", path.display(), error))
+	}
+
+	pub fn save(&self, path: &Path) -> io::Result<()> {
+		fs::write(path, self.to_text())
+	}
+
+	//(the scene, whether it said what format to use). Without a format, output can pick one to suit itself (see headless)
+	pub fn parse(text: &str) -> Result<(Scene, bool), String> {
+		let mut scene = Scene::default();
+		let mut format_given = false;
+
+		let mut custom_line = None; //where it said fractal = custom, for anything wrong with the fractal as a whole
+		let mut production_policy = None;
+		let mut splitting_policy = None;
+		let mut acceptable = None;
+		let mut square_config = None;
+		let mut splitting_type = None;
+		let mut first_custom_key = None; //(line, key), since those keys mean nothing without fractal = custom
+
+		for (number, line) in text.lines().enumerate() {
+			let line = match line.find('#') {
+				Some(comment) => &line[..comment],
+				None => line,
+			}.trim();
+			if line.is_empty() {
+				continue;
+			}
+
+			let at_line = |message: String| format!("line {}: {}", number + 1, message);
+			let (key, value) = line.split_once('=').ok_or_else(|| at_line("expected key = value".to_string()))?;
+			let (key, value) = (key.trim(), value.trim());
+			if first_custom_key.is_none() && CUSTOM_KEYS.contains(&key) {
+				first_custom_key = Some((number + 1, key));
+			}
+
+			match key {
+				"fractal" => {
+					if value == "custom" {
+						custom_line = Some(number + 1);
+					} else {
+						custom_line = None;
+						let index = index_of(&PRESETS, value).ok_or_else(|| at_line(format!("unknown fractal {}", value)))?;
+						scene.fractal = SceneFractal::Preset(index);
+					}
+				},
+				"production" => production_policy = Some(parse_policy(value).map_err(at_line)?),
+				"splitting" => splitting_policy = Some(parse_policy(value).map_err(at_line)?),
+				"acceptable" => acceptable = Some(index_of(&ACCEPTABILITY_RULES, value).ok_or_else(|| at_line(format!("unknown acceptability rule {}", value)))?),
+				"square_config" => square_config = Some(index_of(&SQUARE_CONFIGS, value).ok_or_else(|| at_line(format!("unknown square config {}", value)))?),
+				"splitting_type" => splitting_type = Some(parse_pair(value).map_err(at_line)?),
+				"colouring" => scene.colouring = index_of(&COLOURINGS, value).ok_or_else(|| at_line(format!("unknown colouring {}", value)))?,
+				"width" => scene.width = parse_value(value).map_err(at_line)?,
+				"height" => scene.height = parse_value(value).map_err(at_line)?,
+				"origin" => scene.origin = parse_pair(value).map_err(at_line)?,
+				"scale" => {
+					let scale: u32 = parse_value(value).map_err(at_line)?;
+					if scale > zoom::MAX_SCALE {
+						return Err(at_line(format!("scale can only go up to {}, not {}", zoom::MAX_SCALE, scale)));
+					}
+					scene.scale = scale;
+				},
+				"antialiasing" => {
+					let antialiasing: usize = parse_value(value).map_err(at_line)?;
+					if !antialiasing.is_power_of_two() {
+						return Err(at_line(format!("antialiasing has to be a power of two, not {}", antialiasing)));
+					}
+					scene.antialiasing = antialiasing;
+				},
+				"format" => {
+					scene.format = PixelFormat::from_name(value).ok_or_else(|| at_line(format!("unknown format {}", value)))?;
+					format_given = true;
+				},
+				"dithering" => scene.dithering = Dithering::from_name(value).ok_or_else(|| at_line(format!("unknown dithering {}", value)))?,
+				"texture" => scene.texture = Some(SceneTexture::load(Path::new(value)).map_err(at_line)?),
+				other => return Err(at_line(format!("unknown key {}", other))),
+			}
+		}
+
+		if let Some(line) = custom_line {
+			let missing = |key: &str| format!("line {}: a custom fractal needs {}", line, key);
+			let custom = CustomFractal {
+				production_policy: production_policy.ok_or_else(|| missing("production"))?,
+				splitting_policy: splitting_policy.ok_or_else(|| missing("splitting"))?,
+				acceptable: acceptable.ok_or_else(|| missing("acceptable"))?,
+				square_config: square_config.ok_or_else(|| missing("square_config"))?,
+				splitting_type: splitting_type.ok_or_else(|| missing("splitting_type"))?,
+			};
+			custom.check().map_err(|error| format!("line {}: the custom fractal can't be drawn: {}", line, error))?;
+			scene.fractal = SceneFractal::Custom(custom);
+		} else if let Some((line, key)) = first_custom_key {
+			return Err(format!("line {}: {} is only for fractal = custom", line, key));
+		}
+
+		if scene.width == 0 || scene.height == 0 {
+			return Err("the canvas can't be empty".to_string());
+		}
+
+		Ok((scene, format_given))
+	}
+
+	pub fn to_text(&self) -> String {
+		let mut text = String::from("# dimers scene\n");
+		match self.fractal {
+			SceneFractal::Preset(index) => {
+				text += &format!("fractal = {}\n", PRESETS[index].0);
+			},
+			SceneFractal::Custom(custom) => {
+				text += "fractal = custom\n";
+				text += &format!("production = {}\n", write_policy(custom.production_policy));
+				text += &format!("splitting = {}\n", write_policy(custom.splitting_policy));
+				text += &format!("acceptable = {}\n", ACCEPTABILITY_RULES[custom.acceptable].0);
+				text += &format!("square_config = {}\n", SQUARE_CONFIGS[custom.square_config].0);
+				text += &format!("splitting_type = {} {}\n", custom.splitting_type.0, custom.splitting_type.1);
+			},
+		}
+		text += &format!("colouring = {}\n", COLOURINGS[self.colouring].0);
+		text += &format!("width = {}\n", self.width);
+		text += &format!("height = {}\n", self.height);
+		text += &format!("origin = {} {}\n", self.origin.0, self.origin.1);
+		text += &format!("scale = {}\n", self.scale);
+		text += &format!("antialiasing = {}\n", self.antialiasing);
+		text += &format!("format = {}\n", self.format.name());
+		text += &format!("dithering = {}\n", self.dithering.name());
+		if let Some(texture) = &self.texture {
+			text += &format!("texture = {}\n", texture.path.display());
+		}
+		text
+	}
+}
+
+//the keys that spell out a custom fractal
+const CUSTOM_KEYS: [&str; 5] = ["production", "splitting", "acceptable", "square_config", "splitting_type"];
+
+//where name is in one of the (name, thing) lists (PRESETS, COLOURINGS and so on)
+pub fn index_of<T>(list: &[(&str, T)], name: &str) -> Option<usize> {
+	list.iter().position(|(list_name, _)| *list_name == name)
+}
+
+fn parse_value<T: std::str::FromStr>(text: &str) -> Result<T, String> {
+	text.parse().map_err(|_| format!("couldn't read {}", text))
+}
+
+fn parse_pair<T: std::str::FromStr>(text: &str) -> Result<(T, T), String> {
+	let parts: Vec<&str> = text.split_whitespace().collect();
+	match parts[..] {
+		[first, second] => Ok((parse_value(first)?, parse_value(second)?)),
+		_ => Err(format!("expected two values, got {}", text)),
+	}
+}
+
+//"flip rot x y, flip rot x y"
+fn parse_policy(text: &str) -> Result<(OddD8TranslationSmall, OddD8TranslationSmall), String> {
+	let parts: Vec<&str> = text.split(',').collect();
+	match parts[..] {
+		[first, second] => Ok((parse_transformation(first)?, parse_transformation(second)?)),
+		_ => Err(format!("expected two transformations separated by a comma, got {}", text)),
+	}
+}
+
+fn parse_transformation(text: &str) -> Result<OddD8TranslationSmall, String> {
+	let parts: Vec<&str> = text.split_whitespace().collect();
+	match parts[..] {
+		[flip, rot, x, y] => {
+			let rot: i8 = parse_value(rot)?;
+			if !(0..4).contains(&rot) {
+				return Err(format!("rotations go from 0 to 3, not {}", rot));
+			}
+			Ok(OddD8TranslationSmall::new(parse_value(flip)?, rot, (parse_value(x)?, parse_value(y)?)))
+		},
+		_ => Err(format!("expected flip rot x y, got {}", text.trim())),
+	}
+}
+
+fn write_policy(policy: (OddD8TranslationSmall, OddD8TranslationSmall)) -> String {
+	format!("{}, {}", write_transformation(policy.0), write_transformation(policy.1))
+}
+
+fn write_transformation(transformation: OddD8TranslationSmall) -> String {
+	let dihedral = transformation.get_dihedral();
+	let (x, y) = transformation.get_translation();
+	format!("{} {} {} {}", dihedral.flipped(), dihedral.rot(), x, y)
+}
@@ -0,0 +1,237 @@
+use std::ops::{Add, Mul};
+
+use super::dihedral_translation::{EvenD8Translation, OddD8TranslationLarge, OddD8TranslationSmall};
+
+//every transformation from dihedral_translation in one type, with the sqrt(2) scales counted instead of baked into the type.
+//so multiplication and inversion always work, and policies can be composed without worrying about which Mul exists.
+//
+//they are represented as "flip? first, then scale by (1 + i)^scale, then do rot quarter turns, then translate".
+//(1 + i) is sqrt(2) long and an eighth of a turn round, so odd scales come with the eighth turn, same as OddD8.
+//that's also why the translations don't need sqrt(2) in them: scaling by (1 + i) and its inverse (1 - i)/2 keeps
+//points with x, y whole numbers over a power of two, so that's what translations are (see DyadicPoint).
+//intrinsic: rot is 0, 1, 2, or 3.
+#[derive(Clone, Copy, Hash, Eq, PartialEq, Debug)]
+pub struct Similarity {
+	flip: bool,
+	rot: i8,
+	scale: i32,
+	translation: DyadicPoint,
+}
+
+//(x, y) / 2^halvings, with "x = right", "y = up" like dihedral_translation.
+//intrinsic: halvings is as small as it can be, so equal points are equal structs (which Hash and Eq rely on)
+#[derive(Clone, Copy, Hash, Eq, PartialEq, Debug)]
+pub struct DyadicPoint {
+	x: isize,
+	y: isize,
+	halvings: u32,
+}
+
+impl DyadicPoint {
+	pub const fn whole(point: (isize, isize)) -> DyadicPoint {
+		DyadicPoint {x: point.0, y: point.1, halvings: 0}
+	}
+
+	pub fn new(point: (isize, isize), halvings: u32) -> DyadicPoint {
+		DyadicPoint {x: point.0, y: point.1, halvings}.reduced()
+	}
+
+	fn reduced(mut self) -> DyadicPoint {
+		while self.halvings > 0 && self.x.rem_euclid(2) == 0 && self.y.rem_euclid(2) == 0 {
+			self.x /= 2;
+			self.y /= 2;
+			self.halvings -= 1;
+		}
+		self
+	}
+
+	//None if it isn't on the integer grid
+	pub fn to_whole(self) -> Option<(isize, isize)> {
+		if self.halvings == 0 {Some((self.x, self.y))} else {None}
+	}
+
+	pub fn to_f64(self) -> (f64, f64) {
+		let denominator = (self.halvings as f64).exp2();
+		(self.x as f64 / denominator, self.y as f64 / denominator)
+	}
+
+	//the numerator and the power of two it's over
+	pub fn parts(self) -> ((isize, isize), u32) {
+		((self.x, self.y), self.halvings)
+	}
+
+	fn negated(self) -> DyadicPoint {
+		DyadicPoint {x: negate(self.x), y: negate(self.y), halvings: self.halvings}
+	}
+
+	//the linear part of a Similarity (see the order above)
+	fn transformed(self, flip: bool, rot: i8, scale: i32) -> DyadicPoint {
+		let (mut x, mut y, mut halvings) = (self.x, if flip {negate(self.y)} else {self.y}, self.halvings);
+		//(1 + i)^4 is -4, so whole fours of scale are a shift (and a half turn each), and only the rest needs the loops below
+		let (fours, rest) = (scale.div_euclid(4), scale.rem_euclid(4));
+		if fours >= 0 {
+			(x, y) = (shifted(x, 2 * fours.unsigned_abs()), shifted(y, 2 * fours.unsigned_abs()));
+		} else {
+			halvings = halvings.checked_add(2 * fours.unsigned_abs()).expect("similarity too small to represent (42103)");
+		}
+		if fours.rem_euclid(2) == 1 {
+			(x, y) = (negate(x), negate(y));
+		}
+		for _ in 0..rest {
+			(x, y) = (checked(x.checked_sub(y)), checked(x.checked_add(y)));
+		}
+		for _ in 0..rot.rem_euclid(4) {
+			(x, y) = (negate(y), x);
+		}
+		DyadicPoint {x, y, halvings}.reduced()
+	}
+}
+
+//the arithmetic DyadicPoint does, but refusing to wrap round. Points that big are far past anything that can be drawn,
+//so it's a bug somewhere else (a runaway composition, most likely) rather than something to carry on from
+fn checked(result: Option<isize>) -> isize {
+	result.expect("similarity's translation too big for isize (42101)")
+}
+
+fn negate(value: isize) -> isize {
+	checked(value.checked_neg())
+}
+
+//value * 2^by
+fn shifted(value: isize, by: u32) -> isize {
+	checked(1isize.checked_shl(by).filter(|&power| power > 0).and_then(|power| value.checked_mul(power)))
+}
+
+impl Add<DyadicPoint> for DyadicPoint {
+	type Output = DyadicPoint;
+
+	fn add(self, rhs: DyadicPoint) -> Self::Output {
+		let halvings = self.halvings.max(rhs.halvings);
+		DyadicPoint {
+			x: checked(shifted(self.x, halvings - self.halvings).checked_add(shifted(rhs.x, halvings - rhs.halvings))),
+			y: checked(shifted(self.y, halvings - self.halvings).checked_add(shifted(rhs.y, halvings - rhs.halvings))),
+			halvings,
+		}.reduced()
+	}
+}
+
+impl Similarity {
+	pub const fn new(flip: bool, rot: i8, scale: i32, translation: (isize, isize)) -> Similarity {
+		Similarity {
+			flip,
+			rot: rot.rem_euclid(4),
+			scale,
+			translation: DyadicPoint::whole(translation),
+		}
+	}
+
+	pub fn with_translation(flip: bool, rot: i8, scale: i32, translation: DyadicPoint) -> Similarity {
+		Similarity {
+			flip,
+			rot: rot.rem_euclid(4),
+			scale,
+			translation,
+		}
+	}
+
+	pub fn flipped(self) -> bool {self.flip}
+	pub fn rot(self) -> i8 {self.rot}
+	//how many times sqrt(2) bigger it makes things (negative for smaller)
+	pub fn scale(self) -> i32 {self.scale}
+	pub fn get_translation(self) -> DyadicPoint {self.translation}
+
+	pub fn inv(self) -> Similarity {
+		//undoing (1 + i)^scale after a flip leaves some quarter turns behind, since the conjugate of 1 + i is -i (1 + i)
+		let (rot, scale) = if self.flip {
+			(self.rot + self.scale.rem_euclid(4) as i8, -self.scale)
+		} else {
+			(-self.rot, -self.scale)
+		};
+		let inverse = Similarity::with_translation(self.flip, rot, scale, DyadicPoint::whole((0, 0)));
+		Similarity {
+			translation: inverse.apply(self.translation.negated()),
+			..inverse
+		}
+	}
+
+	pub fn apply(self, point: DyadicPoint) -> DyadicPoint {
+		point.transformed(self.flip, self.rot, self.scale) + self.translation
+	}
+
+	//for drawing and such, where exactness doesn't matter
+	pub fn apply_f64(self, point: (f64, f64)) -> (f64, f64) {
+		let (mut x, mut y) = if self.flip {(point.0, -point.1)} else {point};
+		let angle = (self.scale as f64 + 2.0 * self.rot as f64) * std::f64::consts::FRAC_PI_4;
+		let length = (self.scale as f64 / 2.0).exp2();
+		let (sin, cos) = angle.sin_cos();
+		(x, y) = (length * (cos * x - sin * y), length * (sin * x + cos * y));
+		let translation = self.translation.to_f64();
+		(x + translation.0, y + translation.1)
+	}
+
+	//back to the types from dihedral_translation, where that's possible
+	pub fn to_even(self) -> Option<EvenD8Translation> {
+		if self.scale != 0 {
+			return None;
+		}
+		Some(EvenD8Translation::new(self.flip, self.rot, self.translation.to_whole()?))
+	}
+
+	pub fn to_large(self) -> Option<OddD8TranslationLarge> {
+		if self.scale != 1 {
+			return None;
+		}
+		Some(OddD8TranslationLarge::new(self.flip, self.rot, self.translation.to_whole()?))
+	}
+
+	pub fn to_small(self) -> Option<OddD8TranslationSmall> {
+		if self.scale != -1 {
+			return None;
+		}
+		//(1 + i)/2 is i/(1 + i), hence the quarter turn (see the From below)
+		Some(OddD8TranslationSmall::new(self.flip, self.rot - 1, self.translation.to_whole()?))
+	}
+}
+
+impl Mul<Similarity> for Similarity {
+	type Output = Similarity;
+
+	fn mul(self, rhs: Similarity) -> Self::Output {
+		//same as the D8 multiplications, except a flip also turns rhs's scale the other way round
+		let rot = if self.flip {
+			self.rot - rhs.rot - rhs.scale.rem_euclid(4) as i8
+		} else {
+			self.rot + rhs.rot
+		};
+		Similarity::with_translation(
+			self.flip != rhs.flip,
+			rot,
+			self.scale + rhs.scale,
+			self.apply(rhs.translation),
+		)
+	}
+}
+
+impl From<EvenD8Translation> for Similarity {
+	fn from(transformation: EvenD8Translation) -> Similarity {
+		let dihedral = transformation.get_dihedral();
+		Similarity::new(dihedral.flipped(), dihedral.rot(), 0, transformation.get_translation())
+	}
+}
+
+impl From<OddD8TranslationLarge> for Similarity {
+	fn from(transformation: OddD8TranslationLarge) -> Similarity {
+		let dihedral = transformation.get_dihedral();
+		Similarity::new(dihedral.flipped(), dihedral.rot(), 1, transformation.get_translation())
+	}
+}
+
+impl From<OddD8TranslationSmall> for Similarity {
+	fn from(transformation: OddD8TranslationSmall) -> Similarity {
+		//small scales by (1 + i)/2 rather than 1/(1 + i), which is an extra quarter turn
+		let dihedral = transformation.get_dihedral();
+		Similarity::new(dihedral.flipped(), dihedral.rot() + 1, -1, transformation.get_translation())
+	}
+}
+
+pub const IDENTITY: Similarity = Similarity::new(false, 0, 0, (0, 0));
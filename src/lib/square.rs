@@ -1,14 +1,12 @@
-use std::sync::{Arc, Mutex, mpsc::Sender};
-use druid::piet;
-
 use std::cmp::{min, max};
 
-use super::auxiliary::WorkingMessage;
 use super::fractal_specification::FractalSpecification;
 use super::triangle::Triangle;
 use super::interval_colouring::SplitInterval;
 use super::colour_format;
 use super::dihedral_translation::EvenD8Translation;
+use super::pixel_sink::PixelSink;
+use super::relevance_getter::Splitter;
 
 pub const SENDING_SIZE: isize = 16;
 
@@ -22,7 +20,7 @@ pub enum SquareCut {
 impl SquareCut {
 	//splitting type tells you the orientation of children.
 	//false means oriented to the right angle, and true means oriented to the 45 degree angle.
-	pub fn split(self, splitter_list: &[((usize, bool), (usize, bool))], splitting_type: (bool, bool)) -> (Self, Self, Self, Self) {
+	pub fn split(self, splitter_list: &[Splitter], splitting_type: (bool, bool)) -> (Self, Self, Self, Self) {
 		let (split_a, split_b) = splitting_type;
 
 		//left is oriented to bottom left by default, and everything else works by rotational symmetry
@@ -166,9 +164,14 @@ pub enum CropOutput {
 }
 
 impl UncroppedSquare {
+	//sidelength should be a power of two
+	pub fn new(x: isize, y: isize, sidelength: isize, triangles: SquareCut) -> UncroppedSquare {
+		UncroppedSquare {x, y, sidelength, triangles}
+	}
+
 	//CRITICAL
 	//returns in the order: TL, TR, BL, BR
-	pub fn split(self, splitter_list: &[((usize, bool), (usize, bool))], splitting_type: (bool, bool)) -> (UncroppedSquare, UncroppedSquare, UncroppedSquare, UncroppedSquare) {
+	pub fn split(self, splitter_list: &[Splitter], splitting_type: (bool, bool)) -> (UncroppedSquare, UncroppedSquare, UncroppedSquare, UncroppedSquare) {
 		
 		let half_sidelength = self.sidelength/2;
 		
@@ -184,15 +187,12 @@ impl UncroppedSquare {
 	
 	//note: most of the work goes through here and split
 	//CRITICAL
-	pub fn draw(
+	pub fn draw<S: PixelSink + ?Sized>(
 		self,
-		image_format: piet::ImageFormat,
-		canvas: Arc<Mutex<Vec<u8>>>,
+		sink: &S,
 		triangle_weights: &[f64],
-		canvas_width: usize,
-		splitter_list: &[((usize, bool), (usize, bool))],
+		splitter_list: &[Splitter],
 		splitting_type: (bool, bool),
-		sender: Sender<((usize, usize, usize, usize), WorkingMessage)>,
 	) {
 		if self.sidelength == 1 {
 			//find colour, then draw it at x, y
@@ -202,45 +202,7 @@ impl UncroppedSquare {
 				SquareCut::Backslash ((t1, _), (t2, _)) => (t1.get_colour(triangle_weights) + t2.get_colour(triangle_weights)) / 2.0,
 			};
 			
-			match image_format {
-				piet::ImageFormat::Grayscale => {
-					let brightness: u8 = square_col.grayscale();
-					let buffer_pos = (self.y as usize) * canvas_width + (self.x as usize);
-					let mut guard = canvas.lock().expect("Canvas lock poisoned");
-					guard[buffer_pos] = brightness;
-					drop(guard);
-				},
-				piet::ImageFormat::Rgb => {
-					let srgb_col: (u8, u8, u8) = square_col.rgb();
-					let buffer_pos = 3 * ((self.y as usize) * canvas_width + (self.x as usize));
-					let mut guard = canvas.lock().expect("Canvas lock poisoned");
-					guard[buffer_pos  ] = srgb_col.0;
-					guard[buffer_pos+1] = srgb_col.1;
-					guard[buffer_pos+2] = srgb_col.2;
-					drop(guard);
-				},
-				piet::ImageFormat::RgbaSeparate => {
-					let srgba_col: (u8, u8, u8, u8) = square_col.rgba_separate();
-					let buffer_pos = 4 * ((self.y as usize)*canvas_width + (self.x as usize));
-					let mut guard = canvas.lock().expect("Canvas lock poisoned");
-					guard[buffer_pos  ] = srgba_col.0;
-					guard[buffer_pos+1] = srgba_col.1;
-					guard[buffer_pos+2] = srgba_col.2;
-					guard[buffer_pos+3] = srgba_col.3;
-					drop(guard);
-				},
-				piet::ImageFormat::RgbaPremul => {
-					let srgba_col: (u8, u8, u8, u8) = square_col.rgba_premul();
-					let buffer_pos = 4 * ((self.y as usize) * canvas_width + (self.x as usize));
-					let mut guard = canvas.lock().expect("Canvas lock poisoned");
-					guard[buffer_pos  ] = srgba_col.0;
-					guard[buffer_pos+1] = srgba_col.1;
-					guard[buffer_pos+2] = srgba_col.2;
-					guard[buffer_pos+3] = srgba_col.3;
-					drop(guard);
-				},
-				_ => panic!("Unsupported colour format"),
-			}
+			sink.put(self.x as usize, self.y as usize, square_col);
 			
 			return;
 		}
@@ -249,6 +211,10 @@ impl UncroppedSquare {
 		}
 
 		let message_bounds = if self.sidelength == SENDING_SIZE {
+			if sink.cancelled() {
+				return;
+			}
+
 			let bounds = (
 				self.x as usize,
 				self.y as usize,
@@ -256,7 +222,7 @@ impl UncroppedSquare {
 				(self.y + self.sidelength) as usize,
 			);
 
-			sender.send((bounds, WorkingMessage::Begin)).expect("sender failed");
+			sink.begin_chunk(bounds);
 
 			Some(bounds)
 		} else {None};
@@ -264,14 +230,13 @@ impl UncroppedSquare {
 		//otherwise,
 		let (tl, tr, bl, br) = self.split(splitter_list, splitting_type);
 		
-		tl.draw(image_format, canvas.clone(), triangle_weights, canvas_width, splitter_list, splitting_type, sender.clone());
-		tr.draw(image_format, canvas.clone(), triangle_weights, canvas_width, splitter_list, splitting_type, sender.clone());
-		bl.draw(image_format, canvas.clone(), triangle_weights, canvas_width, splitter_list, splitting_type, sender.clone());
-		br.draw(image_format, canvas        , triangle_weights, canvas_width, splitter_list, splitting_type, sender.clone()); //I don't need this arc anymore, so this one can have it
-
-		match message_bounds {
-			Some (bounds) => {sender.send((bounds, WorkingMessage::End)).expect("sender failed");},
-			None => {},
+		tl.draw(sink, triangle_weights, splitter_list, splitting_type);
+		tr.draw(sink, triangle_weights, splitter_list, splitting_type);
+		bl.draw(sink, triangle_weights, splitter_list, splitting_type);
+		br.draw(sink, triangle_weights, splitter_list, splitting_type);
+
+		if let Some(bounds) = message_bounds {
+			sink.commit_chunk(bounds);
 		}
 	}
 	
@@ -288,42 +253,42 @@ impl UncroppedSquare {
 }
 
 impl CropOutput {
-	pub fn draw(
+	pub fn draw<S: PixelSink + ?Sized>(
 		self,
-		image_format: piet::ImageFormat,
-		canvas: Arc<Mutex<Vec<u8>>>,
+		sink: &S,
 		triangle_weights: &[f64],
-		canvas_width: usize,
-		splitter_list: &[((usize, bool), (usize, bool))],
+		splitter_list: &[Splitter],
 		splitting_type: (bool, bool),
-		sender: Sender<((usize, usize, usize, usize), WorkingMessage)>,
 	) {
 		match self {
 			//the idea is that this is the main case. We want to eventually forget about this cropping.
-			CropOutput::Unaffected(uncropped) => uncropped.draw(image_format, canvas, triangle_weights, canvas_width, splitter_list, splitting_type, sender),
+			CropOutput::Unaffected(uncropped) => uncropped.draw(sink, triangle_weights, splitter_list, splitting_type),
 			//the only reason this one exists is that we'd get nonsense by drawing off screen otherwise.
 			CropOutput::Cropped(base, bounds) => {
 				let message_bounds = if base.sidelength == SENDING_SIZE {
+					if sink.cancelled() {
+						return;
+					}
+
 					let bounds = (
 						max(bounds.0, base.x) as usize,
 						max(bounds.1, base.y) as usize,
 						min(bounds.2, base.x + base.sidelength) as usize,
 						min(bounds.3, base.y + base.sidelength) as usize
 					);
-					sender.send((bounds, WorkingMessage::Begin)).expect("sender failed");
+					sink.begin_chunk(bounds);
 
 					Some(bounds)
 				} else {None};
 
 				let (tl, tr, bl, br) = base.split(splitter_list, splitting_type);
-				tl.crop(bounds).draw(image_format, canvas.clone(), triangle_weights, canvas_width, splitter_list, splitting_type, sender.clone());
-				tr.crop(bounds).draw(image_format, canvas.clone(), triangle_weights, canvas_width, splitter_list, splitting_type, sender.clone());
-				bl.crop(bounds).draw(image_format, canvas.clone(), triangle_weights, canvas_width, splitter_list, splitting_type, sender.clone());
-				br.crop(bounds).draw(image_format, canvas        , triangle_weights, canvas_width, splitter_list, splitting_type, sender.clone());
-
-				match message_bounds {
-					Some (bounds) => {sender.send((bounds, WorkingMessage::End)).expect("sender failed");}
-					None => {}
+				tl.crop(bounds).draw(sink, triangle_weights, splitter_list, splitting_type);
+				tr.crop(bounds).draw(sink, triangle_weights, splitter_list, splitting_type);
+				bl.crop(bounds).draw(sink, triangle_weights, splitter_list, splitting_type);
+				br.crop(bounds).draw(sink, triangle_weights, splitter_list, splitting_type);
+
+				if let Some(bounds) = message_bounds {
+					sink.commit_chunk(bounds);
 				}
 			},
 			CropOutput::Empty => {},
@@ -333,32 +298,31 @@ impl CropOutput {
 
 
 //then I need to generate tiles for a bounding box, then crop them appropriately
+//sink is whatever the pixels end up in (see pixel_sink)
 //bounds say where in the canvas to draw the thing
 //origin is what you expect
 //triangle_colouring maps each triangle (represented by a EvenD8Translation) to the appropriate colouring
 //scale is 0 for triangles of sidelength 1, 1 for sidelength 2, 8 for sidelength 256, etc.
-pub fn draw_into_canvas<F>(
-	image_format: piet::ImageFormat,
-	canvas: Arc<Mutex<Vec<u8>>>,
+#[allow(clippy::too_many_arguments)]
+pub fn draw_into_canvas<F, S>(
+	sink: &S,
 	triangle_weights: &[f64],
 	requirement_list: &[EvenD8Translation],
-	canvas_width: usize,
 	bounds: (isize, isize, isize, isize),
 	origin: (isize, isize),
 	scale: u32,
 	fractal: &FractalSpecification,
 	mut triangle_colouring: F,
-	splitter_list: &[((usize, bool), (usize, bool))],
-	sender: Sender<((usize, usize, usize, usize), WorkingMessage)>
+	splitter_list: &[Splitter],
 	
-	) where F : FnMut(EvenD8Translation) -> Box<dyn SplitInterval> {
+	) where F : FnMut(EvenD8Translation) -> Box<dyn SplitInterval>, S: PixelSink + ?Sized {
 	
 	let sidelength = 1 << scale;
 	let shifted_bounds: (isize, isize, isize, isize) = (
-		(bounds.0 as isize) - origin.0,
-		(bounds.1 as isize) - origin.1,
-		(bounds.2 as isize) - origin.0,
-		(bounds.3 as isize) - origin.1,
+		bounds.0 - origin.0,
+		bounds.1 - origin.1,
+		bounds.2 - origin.0,
+		bounds.3 - origin.1,
 	);
 
 	let splitting_type = fractal.get_splitting_type();
@@ -373,6 +337,10 @@ pub fn draw_into_canvas<F>(
 	
 	for current_tile_y in highest_tile_y..=lowest_tile_y {
 		for current_tile_x in leftmost_tile_x..=rightmost_tile_x {
+			if sink.cancelled() {
+				return;
+			}
+
 			//actually, this'll give different types of tile sepending on the parity of x and y, so account for that.
 			let square_config: SquareCut = fractal.get_square_config(current_tile_x, current_tile_y, requirement_list, &mut triangle_colouring);
 			
@@ -385,7 +353,7 @@ pub fn draw_into_canvas<F>(
 			
 			let cropped_tile = uncropped_tile.crop(bounds);
 			
-			cropped_tile.draw(image_format, canvas.clone(), triangle_weights, canvas_width, splitter_list, splitting_type, sender.clone());
+			cropped_tile.draw(sink, triangle_weights, splitter_list, splitting_type);
 		}
 	}
 }
\ No newline at end of file
@@ -0,0 +1,60 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use super::dithering::Dithering;
+use super::image_output::PngWriter;
+use super::pixel_format::{self, PixelFormat};
+use super::render::{Image, View};
+
+//for pictures too big to keep in memory at once: they get drawn a band of rows at a time, and each band goes
+//into the png before the next one is started. Bands are views of their own (moved up, like tile_pyramid's tiles),
+//so the renderer only ever draws inside one band, and there's only ever one band of pixels around.
+
+//about how many pixels go in a band, unless asked otherwise. A band this big is 64MB of LinearCols
+pub const BAND_PIXELS: usize = 1 << 21;
+
+pub fn default_band_height(width: usize) -> usize {
+	(BAND_PIXELS / width.max(1)).max(1)
+}
+
+//(first row, the band's own view) for every band of view, top to bottom. The last band is cut short to fit
+pub fn bands(view: View, band_height: usize) -> Vec<(usize, View)> {
+	assert!(band_height > 0, "bands need at least one row (48101)");
+	(0..view.height).step_by(band_height).map(|top| {
+		let height = band_height.min(view.height - top);
+		(top, View {height, origin: (view.origin.0, view.origin.1 - top as isize), ..view})
+	}).collect()
+}
+
+//writes view to writer as a png, band_height rows at a time, with render drawing whatever band it's given
+pub fn write_png<W, F>(
+	writer: W,
+	view: View,
+	band_height: usize,
+	format: PixelFormat,
+	dithering: Dithering,
+	mut render: F,
+) -> io::Result<W> where W: Write, F: FnMut(View) -> Image {
+	let mut png = PngWriter::new(writer, view.width, view.height, format)?;
+	let row_bytes = view.width * format.bytes_per_pixel();
+	for (top, band) in bands(view, band_height) {
+		let image = render(band);
+		let bytes = pixel_format::encode_rows(image.pixels(), view.width, top, format, dithering);
+		for row in bytes.chunks(row_bytes) {
+			png.write_row(row)?;
+		}
+	}
+	png.finish()
+}
+
+//the same, into a file. Only png can be written like this (pfm goes bottom to top, and write_exr takes the whole picture)
+pub fn save_png<F>(path: &Path, view: View, band_height: usize, format: PixelFormat, dithering: Dithering, render: F) -> io::Result<()>
+	where F: FnMut(View) -> Image {
+	let is_png = path.extension().and_then(|extension| extension.to_str()).is_some_and(|extension| extension.eq_ignore_ascii_case("png"));
+	if !is_png {
+		return Err(io::Error::new(io::ErrorKind::InvalidInput, "streamed renders can only be saved as png"));
+	}
+	let file = BufWriter::new(File::create(path)?);
+	write_png(file, view, band_height, format, dithering, render)?.flush()
+}
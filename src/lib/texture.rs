@@ -0,0 +1,149 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use super::colour_format::{from_srgb, LinearCol};
+
+//an image to paint onto the fractal, kept as LinearCols so sampling it is just arithmetic.
+//like image_output, reading is done by hand: binary ppm (what most tools will convert a photo to) and pfm
+pub struct Texture {
+	width: usize,
+	height: usize,
+	pixels: Vec<LinearCol>, //top row first
+}
+
+impl Texture {
+	pub fn from_fn<F: Fn(f64, f64) -> LinearCol>(width: usize, height: usize, colour: F) -> Texture {
+		let mut pixels = Vec::with_capacity(width * height);
+		for y in 0..height {
+			for x in 0..width {
+				pixels.push(colour((x as f64 + 0.5) / width as f64, (y as f64 + 0.5) / height as f64));
+			}
+		}
+		Texture {width, height, pixels}
+	}
+
+	pub fn load(path: &Path) -> io::Result<Texture> {
+		let data = fs::read(path)?;
+		match data.get(0..2) {
+			Some(b"P6") => read_ppm(&data),
+			Some(b"PF") | Some(b"Pf") => read_pfm(&data),
+			_ => Err(invalid_data("unknown texture format (expected a binary ppm or a pfm)")),
+		}
+	}
+
+	pub fn get_width(&self) -> usize {self.width}
+	pub fn get_height(&self) -> usize {self.height}
+
+	//bilinear, with u and v in [0,1] running right and down. Anything outside is clamped to the edge
+	pub fn sample(&self, u: f64, v: f64) -> LinearCol {
+		let x = (u * self.width as f64 - 0.5).clamp(0.0, (self.width - 1) as f64);
+		let y = (v * self.height as f64 - 0.5).clamp(0.0, (self.height - 1) as f64);
+		let (left, top) = (x as usize, y as usize);
+		let (right, bottom) = ((left + 1).min(self.width - 1), (top + 1).min(self.height - 1));
+		let (across, down) = (x - left as f64, y - top as f64);
+
+		let pixel = |x: usize, y: usize| self.pixels[y * self.width + x];
+		let upper = (1.0 - across) * pixel(left, top) + across * pixel(right, top);
+		let lower = (1.0 - across) * pixel(left, bottom) + across * pixel(right, bottom);
+		(1.0 - down) * upper + down * lower
+	}
+}
+
+fn invalid_data(message: &str) -> io::Error {
+	io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+//the whitespace separated fields at the start of a netpbm style file, and where the pixels start after them.
+//ppm allows # comments in there, pfm doesn't but skipping them does no harm
+fn read_header(data: &[u8], fields: usize) -> io::Result<(Vec<String>, usize)> {
+	let mut found = Vec::new();
+	let mut position = 0;
+	while found.len() < fields {
+		match data.get(position) {
+			None => return Err(invalid_data("texture header ended early")),
+			Some(b'#') => {
+				while data.get(position).is_some_and(|&byte| byte != b'\n') {
+					position += 1;
+				}
+			},
+			Some(byte) if byte.is_ascii_whitespace() => position += 1,
+			Some(_) => {
+				let start = position;
+				while data.get(position).is_some_and(|byte| !byte.is_ascii_whitespace()) {
+					position += 1;
+				}
+				found.push(String::from_utf8_lossy(&data[start..position]).into_owned());
+			},
+		}
+	}
+	//exactly one whitespace byte separates the header from the pixels
+	Ok((found, position + 1))
+}
+
+fn parse_field<T: std::str::FromStr>(field: &str) -> io::Result<T> {
+	field.parse().map_err(|_| invalid_data("couldn't read a number in the texture header"))
+}
+
+fn read_ppm(data: &[u8]) -> io::Result<Texture> {
+	let (fields, start) = read_header(data, 4)?;
+	let width: usize = parse_field(&fields[1])?;
+	let height: usize = parse_field(&fields[2])?;
+	let max_value: u32 = parse_field(&fields[3])?;
+	if width == 0 || height == 0 || max_value == 0 || max_value > 65535 {
+		return Err(invalid_data("bad ppm header"));
+	}
+
+	let bytes_per_value = if max_value < 256 {1} else {2};
+	let length = width.checked_mul(height).and_then(|values| values.checked_mul(3 * bytes_per_value))
+		.ok_or_else(|| invalid_data("ppm header is too big"))?;
+	let pixels = start.checked_add(length).and_then(|end| data.get(start..end))
+		.ok_or_else(|| invalid_data("ppm is shorter than its header says"))?;
+
+	let value = |index: usize| -> f64 {
+		let raw = if bytes_per_value == 1 {
+			pixels[index] as u32
+		} else {
+			u16::from_be_bytes([pixels[2 * index], pixels[2 * index + 1]]) as u32
+		};
+		from_srgb(raw as f64 / max_value as f64)
+	};
+	let pixels = (0..width * height)
+		.map(|pixel| LinearCol::new(value(3 * pixel), value(3 * pixel + 1), value(3 * pixel + 2), 0.0))
+		.collect();
+	Ok(Texture {width, height, pixels})
+}
+
+//the counterpart to image_output::write_pfm: linear floats, bottom row first, and a negative scale means little endian
+fn read_pfm(data: &[u8]) -> io::Result<Texture> {
+	let (fields, start) = read_header(data, 4)?;
+	let channels = if fields[0] == "PF" {3} else {1};
+	let width: usize = parse_field(&fields[1])?;
+	let height: usize = parse_field(&fields[2])?;
+	let scale: f64 = parse_field(&fields[3])?;
+	if width == 0 || height == 0 || scale == 0.0 {
+		return Err(invalid_data("bad pfm header"));
+	}
+
+	let length = width.checked_mul(height).and_then(|values| values.checked_mul(channels * 4))
+		.ok_or_else(|| invalid_data("pfm header is too big"))?;
+	let floats = start.checked_add(length).and_then(|end| data.get(start..end))
+		.ok_or_else(|| invalid_data("pfm is shorter than its header says"))?;
+	let value = |index: usize| -> f64 {
+		let bytes: [u8; 4] = floats[4 * index..4 * index + 4].try_into().expect("slice has length 4 (38101)");
+		if scale < 0.0 {f32::from_le_bytes(bytes) as f64} else {f32::from_be_bytes(bytes) as f64}
+	};
+
+	let mut pixels = Vec::with_capacity(width * height);
+	for y in (0..height).rev() {
+		for x in 0..width {
+			let first = (y * width + x) * channels;
+			pixels.push(if channels == 3 {
+				LinearCol::new(value(first), value(first + 1), value(first + 2), 0.0)
+			} else {
+				LinearCol::new(value(first), value(first), value(first), 0.0)
+			});
+		}
+	}
+	Ok(Texture {width, height, pixels})
+}
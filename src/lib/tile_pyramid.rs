@@ -0,0 +1,175 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use super::colour_format::LinearCol;
+use super::dithering::Dithering;
+use super::pixel_format::PixelFormat;
+use super::render::{Image, View};
+
+//cuts renders into TILE_SIZE square pngs at several zoom levels, for deep zoom web viewers.
+//level 0 is the base view, and every level after that is twice the size (View::enlarged), so it's the same picture in more detail.
+//every tile is its own render of just its part of the level, with the origin moved so the tile's corner is (0, 0).
+//the renderers line their grids up with the origin, so the tiles come out the same as the matching part of the whole level would.
+
+pub const TILE_SIZE: usize = 256;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TileLayout {
+	//name.dzi, with tiles in name_files/level/column_row.png. The levels smaller than the base view are shrunk from it,
+	//since deep zoom wants every level down to 1x1
+	Dzi,
+	//level/column/row.png and nothing else, like web map tiles. A square base view of TILE_SIZE lines up with what map viewers expect
+	Xyz,
+}
+
+impl TileLayout {
+	pub fn from_name(name: &str) -> Option<TileLayout> {
+		match name {
+			"dzi" => Some(TileLayout::Dzi),
+			"xyz" => Some(TileLayout::Xyz),
+			_ => None,
+		}
+	}
+}
+
+pub fn level_view(base: View, level: u32) -> View {
+	base.enlarged(1 << level)
+}
+
+//(column, row, the tile's own view) for every tile in view, row by row.
+//the tiles on the right and bottom edges are cut short to fit, unless padded (then they carry on past the edge)
+pub fn tiles(view: View, padded: bool) -> Vec<(usize, usize, View)> {
+	let mut tiles = Vec::new();
+	for row in 0..view.height.div_ceil(TILE_SIZE) {
+		for column in 0..view.width.div_ceil(TILE_SIZE) {
+			tiles.push((column, row, tile_view(view, column, row, padded)));
+		}
+	}
+	tiles
+}
+
+//just the one tile (which has to be inside view, unless padded)
+pub fn tile_view(view: View, column: usize, row: usize, padded: bool) -> View {
+	let (left, top) = (column * TILE_SIZE, row * TILE_SIZE);
+	let (width, height) = if padded {
+		(TILE_SIZE, TILE_SIZE)
+	} else {
+		(TILE_SIZE.min(view.width - left), TILE_SIZE.min(view.height - top))
+	};
+	let origin = (view.origin.0 - left as isize, view.origin.1 - top as isize);
+	View {width, height, origin, scale: view.scale}
+}
+
+//how many pixels export renders, for a ProgressTracker
+pub fn pixel_total(base: View, levels: u32, layout: TileLayout) -> usize {
+	let tiled: usize = (0..levels)
+		.flat_map(|level| tiles(level_view(base, level), layout == TileLayout::Xyz))
+		.map(|(_, _, view)| view.width * view.height)
+		.sum();
+	if layout == TileLayout::Dzi && has_smaller_levels(base) {
+		tiled + base.width * base.height
+	} else {
+		tiled
+	}
+}
+
+//whether deep zoom needs any levels below the base view
+fn has_smaller_levels(base: View) -> bool {
+	base.width.max(base.height) > 1
+}
+
+//renders levels levels of the pyramid into directory, with render drawing whatever view it's given.
+//name is only for dzi, where it names the .dzi file (and the _files directory next to it)
+#[allow(clippy::too_many_arguments)]
+pub fn export<F>(
+	directory: &Path,
+	name: &str,
+	base: View,
+	levels: u32,
+	layout: TileLayout,
+	format: PixelFormat,
+	dithering: Dithering,
+	mut render: F,
+) -> io::Result<()> where F: FnMut(View) -> Image {
+	assert!(levels > 0, "a pyramid needs at least one level (46101)");
+	let padded = layout == TileLayout::Xyz;
+	let full = level_view(base, levels - 1);
+	//deep zoom counts levels up from 1x1, so the base view's level is however many halvings it takes to get there
+	let deepest = full.width.max(full.height).next_power_of_two().trailing_zeros();
+	let dzi_level = |level: u32| deepest - (levels - 1 - level);
+
+	let tile_path = |level: u32, column: usize, row: usize| match layout {
+		TileLayout::Dzi => directory.join(format!("{}_files", name)).join(dzi_level(level).to_string()).join(format!("{}_{}.png", column, row)),
+		TileLayout::Xyz => directory.join(level.to_string()).join(column.to_string()).join(format!("{}.png", row)),
+	};
+
+	for level in 0..levels {
+		for (column, row, view) in tiles(level_view(base, level), padded) {
+			let path = tile_path(level, column, row);
+			fs::create_dir_all(path.parent().expect("tiles are in a directory (46102)"))?;
+			render(view).save(&path, format, dithering)?;
+		}
+	}
+
+	if layout == TileLayout::Dzi {
+		//the levels below the base view, halving it until it's a single pixel
+		if has_smaller_levels(base) {
+			let mut image = render(base);
+			for level in (0..dzi_level(0)).rev() {
+				image = halved(&image);
+				let level_directory = directory.join(format!("{}_files", name)).join(level.to_string());
+				fs::create_dir_all(&level_directory)?;
+				for row in 0..image.get_height().div_ceil(TILE_SIZE) {
+					for column in 0..image.get_width().div_ceil(TILE_SIZE) {
+						let tile = crop(&image, column * TILE_SIZE, row * TILE_SIZE);
+						tile.save(&level_directory.join(format!("{}_{}.png", column, row)), format, dithering)?;
+					}
+				}
+			}
+		}
+
+		let descriptor = format!(
+			"<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+			<Image xmlns=\"http://schemas.microsoft.com/deepzoom/2008\" Format=\"png\" Overlap=\"0\" TileSize=\"{}\">\n\
+			\t<Size Width=\"{}\" Height=\"{}\"/>\n\
+			</Image>\n",
+			TILE_SIZE, full.width, full.height,
+		);
+		fs::write(directory.join(format!("{}.dzi", name)), descriptor)?;
+	}
+
+	Ok(())
+}
+
+//half the size (rounding up), each pixel the average of the ones it covers
+fn halved(image: &Image) -> Image {
+	let (width, height) = (image.get_width(), image.get_height());
+	let (half_width, half_height) = (width.div_ceil(2), height.div_ceil(2));
+	let mut pixels = Vec::with_capacity(half_width * half_height);
+	for y in 0..half_height {
+		for x in 0..half_width {
+			let mut total = LinearCol::new(0.0, 0.0, 0.0, 0.0);
+			let mut count = 0;
+			for (inner_x, inner_y) in [(2 * x, 2 * y), (2 * x + 1, 2 * y), (2 * x, 2 * y + 1), (2 * x + 1, 2 * y + 1)] {
+				if inner_x < width && inner_y < height {
+					total = total + image.get(inner_x, inner_y);
+					count += 1;
+				}
+			}
+			pixels.push(total / count as f64);
+		}
+	}
+	Image::from_pixels(half_width, half_height, pixels)
+}
+
+//the tile with its corner at (left, top), cut short by the edges
+fn crop(image: &Image, left: usize, top: usize) -> Image {
+	let width = TILE_SIZE.min(image.get_width() - left);
+	let height = TILE_SIZE.min(image.get_height() - top);
+	let pixels = (top..top + height)
+		.flat_map(|y| (left..left + width).map(move |x| (x, y)))
+		.map(|(x, y)| image.get(x, y))
+		.collect();
+	Image::from_pixels(width, height, pixels)
+}
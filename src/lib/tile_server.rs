@@ -0,0 +1,255 @@
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use super::auxiliary::COLOURINGS;
+use super::dithering::Dithering;
+use super::fractal_specification::{self, PRESETS};
+use super::image_output;
+use super::pixel_format::PixelFormat;
+use super::render::{self, Image, View};
+use super::scene;
+use super::tile_pyramid::{self, TILE_SIZE};
+use super::trimer;
+use super::trimer_specification::{self, TRIMER_PRESETS};
+use super::zoom;
+
+//a small http server handing out the same tiles as an xyz pyramid (see tile_pyramid), rendered when they're asked for.
+//GET /{fractal}/{colouring}/{z}/{x}/{y}.png, where z = 0 is one TILE_SIZE tile with the fractal's origin in the middle.
+//it only listens on localhost, and every connection gets its own thread and is closed after one response.
+
+//past this, the origins stop fitting in an isize (and nobody is zooming that far by hand anyway)
+pub const MAX_ZOOM: u32 = 24;
+
+const USAGE: &str = "usage: tile_server [--port N] [--scale N] [--cache N]
+serves /{fractal}/{colouring}/{z}/{x}/{y}.png on localhost, with tiles rendered as they're asked for.
+--scale is the scale of zoom level 0, and --cache is how many tiles to keep around.";
+
+//(fractal, colouring, z, x, y)
+pub type TileKey = (String, String, u32, usize, usize);
+
+//the last capacity tiles to be asked for, as png files.
+//finding the least recently used one is a search through all of them, which is nothing next to rendering a tile
+pub struct TileCache {
+	capacity: usize,
+	clock: u64,
+	tiles: HashMap<TileKey, (u64, Arc<Vec<u8>>)>,
+}
+
+impl TileCache {
+	pub fn new(capacity: usize) -> TileCache {
+		TileCache {
+			capacity,
+			clock: 0,
+			tiles: HashMap::new(),
+		}
+	}
+
+	pub fn get(&mut self, key: &TileKey) -> Option<Arc<Vec<u8>>> {
+		self.clock += 1;
+		let (last_used, png) = self.tiles.get_mut(key)?;
+		*last_used = self.clock;
+		Some(png.clone())
+	}
+
+	pub fn insert(&mut self, key: TileKey, png: Arc<Vec<u8>>) {
+		if self.capacity == 0 {
+			return;
+		}
+		if self.tiles.len() >= self.capacity && !self.tiles.contains_key(&key) {
+			let oldest = self.tiles.iter().min_by_key(|(_, (last_used, _))| *last_used).map(|(key, _)| key.clone());
+			if let Some(oldest) = oldest {
+				self.tiles.remove(&oldest);
+			}
+		}
+		self.clock += 1;
+		self.tiles.insert(key, (self.clock, png));
+	}
+
+	pub fn len(&self) -> usize {
+		self.tiles.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.tiles.is_empty()
+	}
+}
+
+//the tile a path asks for, or None if it isn't one (anything after a ? is ignored)
+pub fn parse_route(path: &str) -> Option<TileKey> {
+	let path = path.split('?').next().unwrap_or(path);
+	let parts: Vec<&str> = path.strip_prefix('/')?.split('/').collect();
+	if let [fractal, colouring, z, x, y] = parts[..] {
+		let y = y.strip_suffix(".png")?;
+		Some((fractal.to_string(), colouring.to_string(), z.parse().ok()?, x.parse().ok()?, y.parse().ok()?))
+	} else {
+		None
+	}
+}
+
+//the view of tile (x, y) at zoom level z, or None if there's no such tile
+pub fn tile_view(scale: u32, z: u32, x: usize, y: usize) -> Option<View> {
+	if z > MAX_ZOOM || x >= 1 << z || y >= 1 << z {
+		return None;
+	}
+	let level = tile_pyramid::level_view(View::centred(TILE_SIZE, TILE_SIZE, scale), z);
+	Some(tile_pyramid::tile_view(level, x, y, false))
+}
+
+//renders the tile as an 8 bit png. None if the fractal or the colouring doesn't exist, or the tile doesn't
+pub fn render_tile(key: &TileKey, scale: u32) -> Option<Vec<u8>> {
+	let (fractal, colouring, z, x, y) = key;
+	let view = tile_view(scale, *z, *x, *y)?;
+	let image = render_named(fractal, colouring, view)?;
+
+	let format = PixelFormat::RgbaSeparate;
+	let mut png = Vec::new();
+	image_output::write_png(&mut png, view.width, view.height, format, &image.to_bytes(format, Dithering::None))
+		.expect("writing to a vec doesn't fail (47101)");
+	Some(png)
+}
+
+//dimers and trimers both, each with their own colourings
+fn render_named(fractal: &str, colouring: &str, view: View) -> Option<Image> {
+	if let Some(fractal) = fractal_specification::preset(fractal) {
+		let colouring = COLOURINGS[scene::index_of(&COLOURINGS, colouring)?].1;
+		Some(render::render(fractal, view, colouring))
+	} else {
+		let fractal = trimer_specification::preset(fractal)?;
+		Some(trimer::render_antialiased(fractal, view, trimer::colouring(colouring)?, 1))
+	}
+}
+
+fn names<T>(list: &[(&str, T)]) -> String {
+	list.iter().map(|(name, _)| *name).collect::<Vec<_>>().join(", ")
+}
+
+//a plain list of what there is to ask for, for /
+fn index_page() -> String {
+	format!(
+		"tiles are at /{{fractal}}/{{colouring}}/{{z}}/{{x}}/{{y}}.png, {}x{} each, with z up to {}\n\n\
+		fractals: {}\n\
+		colourings: {}\n\n\
+		trimers: {}\n\
+		trimer colourings: {}\n",
+		TILE_SIZE, TILE_SIZE, MAX_ZOOM,
+		names(&PRESETS),
+		names(&COLOURINGS),
+		names(&TRIMER_PRESETS),
+		names(&trimer::TRIMER_COLOURINGS),
+	)
+}
+
+fn respond(stream: &mut TcpStream, status: &str, content_type: &str, body: &[u8], head_only: bool) -> io::Result<()> {
+	write!(
+		stream,
+		"HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nAccess-Control-Allow-Origin: *\r\nConnection: close\r\n\r\n",
+		status, content_type, body.len(),
+	)?;
+	if !head_only {
+		stream.write_all(body)?;
+	}
+	stream.flush()
+}
+
+//reads one request and answers it
+fn handle(mut stream: TcpStream, cache: &Mutex<TileCache>, scale: u32) -> io::Result<()> {
+	stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+	let mut reader = BufReader::new(stream.try_clone()?);
+	let mut request_line = String::new();
+	reader.read_line(&mut request_line)?;
+	//the headers don't change anything, but they still have to be read
+	loop {
+		let mut header = String::new();
+		if reader.read_line(&mut header)? == 0 || header.trim_end().is_empty() {
+			break;
+		}
+	}
+
+	let mut words = request_line.split_whitespace();
+	let (method, path) = match (words.next(), words.next()) {
+		(Some(method), Some(path)) => (method, path),
+		_ => return respond(&mut stream, "400 Bad Request", "text/plain", b"bad request\n", false),
+	};
+	let head_only = method == "HEAD";
+	if method != "GET" && !head_only {
+		return respond(&mut stream, "405 Method Not Allowed", "text/plain", b"only GET and HEAD\n", false);
+	}
+	if path == "/" {
+		return respond(&mut stream, "200 OK", "text/plain; charset=utf-8", index_page().as_bytes(), head_only);
+	}
+	let key = match parse_route(path) {
+		Some(key) => key,
+		None => return respond(&mut stream, "404 Not Found", "text/plain", b"not a tile\n", head_only),
+	};
+
+	let cached = cache.lock().expect("tile cache lock poisoned (47102)").get(&key);
+	let png = match cached {
+		Some(png) => png,
+		//rendered without holding the lock, so other tiles aren't held up
+		None => match render_tile(&key, scale) {
+			Some(png) => {
+				let png = Arc::new(png);
+				cache.lock().expect("tile cache lock poisoned (47103)").insert(key, png.clone());
+				png
+			},
+			None => return respond(&mut stream, "404 Not Found", "text/plain", b"no such fractal, colouring or tile\n", head_only),
+		},
+	};
+	respond(&mut stream, "200 OK", "image/png", &png, head_only)
+}
+
+//serves forever (or until the port can't be listened on)
+pub fn serve(port: u16, scale: u32, cache_capacity: usize) -> io::Result<()> {
+	let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+	eprintln!("serving tiles on http://{}", listener.local_addr()?);
+	let cache = Arc::new(Mutex::new(TileCache::new(cache_capacity)));
+	for stream in listener.incoming() {
+		let stream = match stream {
+			Ok(stream) => stream,
+			Err(error) => {
+				eprintln!("couldn't accept a connection: {}", error);
+				continue;
+			},
+		};
+		let cache = cache.clone();
+		thread::spawn(move || {
+			//the other end hanging up early isn't worth stopping for
+			if let Err(error) = handle(stream, &cache, scale) {
+				eprintln!("{}", error);
+			}
+		});
+	}
+	Ok(())
+}
+
+//the command line for the tile_server binary
+pub fn run(args: &[String]) -> Result<(), String> {
+	let mut port: u16 = 8080;
+	let mut scale: u32 = 6;
+	let mut cache_capacity: usize = 256;
+
+	let mut args = args.iter();
+	while let Some(flag) = args.next() {
+		let value = args.next().ok_or_else(|| format!("missing value for {}\n{}", flag, USAGE));
+		match flag.as_str() {
+			"--port" => port = parse_number(value?)?,
+			"--scale" => scale = parse_number(value?)?,
+			"--cache" => cache_capacity = parse_number(value?)?,
+			other => return Err(format!("unknown argument {}\n{}", other, USAGE)),
+		}
+	}
+	//the deepest tiles are drawn MAX_ZOOM doublings in from scale
+	if scale > zoom::MAX_SCALE - MAX_ZOOM {
+		return Err(format!("--scale can only go up to {}, so that zoom level {} still fits\n{}", zoom::MAX_SCALE - MAX_ZOOM, MAX_ZOOM, USAGE));
+	}
+
+	serve(port, scale, cache_capacity).map_err(|error| error.to_string())
+}
+
+fn parse_number<T: std::str::FromStr>(text: &str) -> Result<T, String> {
+	text.parse().map_err(|_| format!("expected a number, got {}", text))
+}
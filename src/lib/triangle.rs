@@ -3,6 +3,7 @@ use std::iter::zip;
 use super::colour_format::LinearCol;
 use super::dihedral_translation::{EvenD8Translation};
 use super::interval_colouring::SplitInterval;
+use super::relevance_getter::Splitter;
 
 pub struct Triangle {
 	required: Vec<Box<dyn SplitInterval>>
@@ -26,7 +27,7 @@ impl Triangle {
 	//(far, close) for a triangle whose "core" lives in its bottom left
 	//splitter_list tells each child triangle piece how it came to be
 	//CRITICAL
-	pub fn split(&self, splitter_list: &[((usize, bool), (usize, bool))]) -> (Triangle, Triangle) {
+	pub fn split(&self, splitter_list: &[Splitter]) -> (Triangle, Triangle) {
 		let mut next_required_a : Vec<Box<dyn SplitInterval>> = Vec::new();
 		let mut next_required_b: Vec<Box<dyn SplitInterval>> = Vec::new();
 		
@@ -0,0 +1,352 @@
+use std::f64::consts::PI;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use super::auxiliary::{self, CopyTranslation};
+use super::colour_format::LinearCol;
+use super::dihedral12_translation::{EvenD12Translation, OddD12TranslationSmall, IDENTITY};
+use super::interval_colouring::{ConstantInterval, FunctionInterval, TernaryInterval, TernaryTreeInterval};
+use super::pixel_sink::{FloatCanvas, PixelSink, Supersampled};
+use super::progress::{ProgressSink, ProgressTracker};
+use super::relevance_getter;
+use super::render::{Image, View};
+use super::square::SENDING_SIZE;
+use super::trimer_specification::TrimerSpecification;
+
+//the renderer for trimers. There's no square grid for the triangles to line up with, so rather than cutting squares,
+//every triangle just keeps splitting until it's smaller than a pixel, and gets added to whichever pixel it lands in.
+//the same View as the dimer renderer, except the base triangle's grid (multiples of 3) is 2^scale pixels across
+
+//eisenstein coordinates to the plane (x right, y up)
+fn to_plane((a, b): (f64, f64)) -> (f64, f64) {
+	(a + b / 2.0, b * 3f64.sqrt() / 2.0)
+}
+
+//flip (in the x axis), then turn by angle and scale, then translate
+fn similarity(point: (f64, f64), flip: bool, angle: f64, scale: f64, translation: (isize, isize)) -> (f64, f64) {
+	let (x, y) = if flip {(point.0, -point.1)} else {point};
+	let (sin, cos) = angle.sin_cos();
+	let offset = to_plane((translation.0 as f64, translation.1 as f64));
+	(scale * (cos * x - sin * y) + offset.0, scale * (sin * x + cos * y) + offset.1)
+}
+
+fn apply_even(transformation: EvenD12Translation, point: (f64, f64)) -> (f64, f64) {
+	let dihedral = transformation.get_dihedral();
+	similarity(point, dihedral.flipped(), dihedral.rot() as f64 * PI / 3.0, 1.0, transformation.get_translation())
+}
+
+//see dihedral12 for where the twelfth of a turn comes from
+fn apply_small(transformation: OddD12TranslationSmall, point: (f64, f64)) -> (f64, f64) {
+	let dihedral = transformation.get_dihedral();
+	let angle = PI / 6.0 + dihedral.rot() as f64 * PI / 3.0;
+	similarity(point, dihedral.flipped(), angle, 1.0 / 3f64.sqrt(), transformation.get_translation())
+}
+
+//point in terms of the corners of triangle (barycentric coordinates)
+fn barycentric(triangle: [(f64, f64); 3], point: (f64, f64)) -> [f64; 3] {
+	let [a, b, c] = triangle;
+	let determinant = (b.0 - a.0) * (c.1 - a.1) - (c.0 - a.0) * (b.1 - a.1);
+	let along_b = ((point.0 - a.0) * (c.1 - a.1) - (c.0 - a.0) * (point.1 - a.1)) / determinant;
+	let along_c = ((b.0 - a.0) * (point.1 - a.1) - (point.0 - a.0) * (b.1 - a.1)) / determinant;
+	[1.0 - along_b - along_c, along_b, along_c]
+}
+
+//everything the pieces need while they're being drawn into one block
+struct Drawing<'a> {
+	triangle_weights: &'a [f64],
+	splitter_list: &'a [Vec<(usize, usize)>],
+	children: [[[f64; 3]; 3]; 3], //children[child][corner] is where that corner is, in terms of the parent's corners
+	bounds: (usize, usize, usize, usize), //the block
+	totals: Vec<(LinearCol, f64)>, //(colour times area, area) for each pixel of the block
+}
+
+//a triangle in pixel space, with its corners in the same order as the specification's tile
+struct Piece {
+	corners: [(f64, f64); 3],
+	required: Vec<Box<dyn TernaryInterval>>,
+}
+
+impl Piece {
+	fn new<F>(location: EvenD12Translation, corners: [(f64, f64); 3], colouring: &mut F, requirement_list: &[EvenD12Translation]) -> Piece
+		where F: FnMut(EvenD12Translation) -> Box<dyn TernaryInterval> {
+		Piece {
+			corners,
+			required: requirement_list.iter().map(|&requirement| colouring(location * requirement)).collect(),
+		}
+	}
+
+	//same as Triangle::split, but three ways, and the pieces know where they are
+	fn split(&self, drawing: &Drawing) -> [Piece; 3] {
+		//every split gets shared out between the children, so do each one once
+		let pieces: Vec<[Rc<dyn TernaryInterval>; 3]> = self.required
+			.iter()
+			.map(|interval| {
+				let (first, second, third) = interval.split();
+				[first.into(), second.into(), third.into()]
+			})
+			.collect();
+
+		std::array::from_fn(|child| {
+			let corners = drawing.children[child].map(|weights| {
+				let mut corner = (0.0, 0.0);
+				for (weight, parent_corner) in weights.iter().zip(self.corners) {
+					corner.0 += weight * parent_corner.0;
+					corner.1 += weight * parent_corner.1;
+				}
+				corner
+			});
+			let required = drawing.splitter_list
+				.iter()
+				.map(|entry| {
+					let (parent, piece) = entry[child];
+					Box::new(pieces[parent][piece].clone()) as Box<dyn TernaryInterval>
+				})
+				.collect();
+			Piece {corners, required}
+		})
+	}
+
+	fn get_colour(&self, triangle_weights: &[f64]) -> LinearCol {
+		let mut output_col = LinearCol::new(0.0, 0.0, 0.0, 0.0);
+		for (weight, colouring) in triangle_weights.iter().zip(&self.required) {
+			output_col = output_col + *weight * colouring.get();
+		}
+		output_col
+	}
+
+	fn draw(&self, drawing: &mut Drawing) {
+		let [a, b, c] = self.corners;
+		let (left, top, right, bottom) = drawing.bounds;
+		if a.0.max(b.0).max(c.0) < left as f64 || a.0.min(b.0).min(c.0) >= right as f64
+			|| a.1.max(b.1).max(c.1) < top as f64 || a.1.min(b.1).min(c.1) >= bottom as f64 {
+			return;
+		}
+
+		let length_squared = |p: (f64, f64), q: (f64, f64)| (p.0 - q.0).powi(2) + (p.1 - q.1).powi(2);
+		let longest = length_squared(a, b).max(length_squared(b, c)).max(length_squared(c, a));
+		if longest > 1.0 {
+			for child in self.split(drawing) {
+				child.draw(drawing);
+			}
+			return;
+		}
+
+		//small enough (a pixel across) to just go wherever its middle is
+		let middle = ((a.0 + b.0 + c.0) / 3.0, (a.1 + b.1 + c.1) / 3.0);
+		if middle.0 < left as f64 || middle.0 >= right as f64 || middle.1 < top as f64 || middle.1 >= bottom as f64 {
+			return;
+		}
+		let area = ((b.0 - a.0) * (c.1 - a.1) - (c.0 - a.0) * (b.1 - a.1)).abs() / 2.0;
+		let index = (middle.1 as usize - top) * (right - left) + (middle.0 as usize - left);
+		let total = &mut drawing.totals[index];
+		total.0 = total.0 + area * self.get_colour(drawing.triangle_weights);
+		total.1 += area;
+	}
+}
+
+//the trimer version of headless::render_region: just the pixels inside bounds (left, top, right, bottom), a SENDING_SIZE block at a time
+pub fn render_region<F, S>(
+	fractal: &TrimerSpecification,
+	mut triangle_colouring: F,
+	bounds: (usize, usize, usize, usize),
+	origin: (isize, isize),
+	scale: u32,
+	sink: &S,
+) where F: FnMut(EvenD12Translation) -> Box<dyn TernaryInterval>, S: PixelSink + ?Sized {
+	//trimers only come from the presets in trimer_specification, which all pass the searches
+	let relevance_list = relevance_getter::find_relevance_list(fractal).unwrap_or_else(|error| panic!("{} (32202)", error));
+	let requirement_list = relevance_getter::find_requirement_list::<TrimerSpecification>(&relevance_list);
+	let splitter_list = relevance_getter::find_splitter_list(fractal, &requirement_list).unwrap_or_else(|error| panic!("{} (32202)", error));
+	let triangle_weights = relevance_getter::find_weights(fractal, &relevance_list).unwrap_or_else(|error| panic!("{} (32202)", error));
+
+	let tile = fractal.tile().map(to_plane);
+	let splitting_policy = fractal.splitting_policy();
+	let children = splitting_policy.map(|split| tile.map(|corner| barycentric(tile, apply_small(split, corner))));
+	//how far the tile reaches from its own origin, so how far away a tile can be and still be seen
+	let reach = tile.iter().map(|corner| corner.0.hypot(corner.1)).fold(0.0, f64::max);
+
+	let unit = (1u64 << scale) as f64 / 3.0; //pixels per eisenstein unit
+	let to_pixels = |point: (f64, f64)| (origin.0 as f64 + point.0 * unit, origin.1 as f64 - point.1 * unit);
+	let step = SENDING_SIZE as usize;
+	if bounds.0 >= bounds.2 || bounds.1 >= bounds.3 {
+		return;
+	}
+	let first_block = (bounds.0 / step * step, bounds.1 / step * step);
+	let columns = (bounds.2 - first_block.0).div_ceil(step);
+	let rows = (bounds.3 - first_block.1).div_ceil(step);
+
+	//every tile that reaches into bounds gets made once, and each block gets a list of the ones that reach into it.
+	//that's the bounds in the plane, grown by reach, then every grid point that could be a tile's origin in there
+	let mut tiles = Vec::new();
+	let mut in_block: Vec<Vec<usize>> = vec![Vec::new(); columns * rows];
+	let plane_left = (bounds.0 as f64 - origin.0 as f64) / unit - reach;
+	let plane_right = (bounds.2 as f64 - origin.0 as f64) / unit + reach;
+	let plane_top = (origin.1 as f64 - bounds.1 as f64) / unit + reach;
+	let plane_bottom = (origin.1 as f64 - bounds.3 as f64) / unit - reach;
+	let lowest_b = (plane_bottom * 2.0 / 3f64.sqrt()).floor() as isize;
+	let highest_b = (plane_top * 2.0 / 3f64.sqrt()).ceil() as isize;
+	for b in lowest_b..=highest_b {
+		let row_y = b as f64 * 3f64.sqrt() / 2.0;
+		let lowest_a = (plane_left - row_y / 3f64.sqrt()).floor() as isize;
+		let highest_a = (plane_right - row_y / 3f64.sqrt()).ceil() as isize;
+		for a in lowest_a..=highest_a {
+			for flip in [false, true] {
+				for rot in 0..6 {
+					let location = EvenD12Translation::new(flip, rot, (a, b));
+					if !fractal.acceptable(location) {
+						continue;
+					}
+					let corners = tile.map(|corner| to_pixels(apply_even(location, corner)));
+
+					//the blocks its corners' bounding box reaches into (the same test Piece::draw starts with)
+					let xs = corners.map(|corner| corner.0);
+					let ys = corners.map(|corner| corner.1);
+					let (left, right) = (xs.into_iter().fold(f64::INFINITY, f64::min), xs.into_iter().fold(f64::NEG_INFINITY, f64::max));
+					let (top, bottom) = (ys.into_iter().fold(f64::INFINITY, f64::min), ys.into_iter().fold(f64::NEG_INFINITY, f64::max));
+					if right < bounds.0 as f64 || left >= bounds.2 as f64 || bottom < bounds.1 as f64 || top >= bounds.3 as f64 {
+						continue;
+					}
+					let block_of = |position: f64, low: usize, high: usize, first: usize| {
+						(position.clamp(low as f64, (high - 1) as f64) as usize - first) / step
+					};
+					let (first_column, last_column) = (block_of(left, bounds.0, bounds.2, first_block.0), block_of(right, bounds.0, bounds.2, first_block.0));
+					let (first_row, last_row) = (block_of(top, bounds.1, bounds.3, first_block.1), block_of(bottom, bounds.1, bounds.3, first_block.1));
+					for row in first_row..=last_row {
+						for column in first_column..=last_column {
+							in_block[row * columns + column].push(tiles.len());
+						}
+					}
+					tiles.push(Piece::new(location, corners, &mut triangle_colouring, &requirement_list));
+				}
+			}
+		}
+	}
+
+	for (row, block_top) in (first_block.1..bounds.3).step_by(step).enumerate() {
+		for (column, block_left) in (first_block.0..bounds.2).step_by(step).enumerate() {
+			if sink.cancelled() {
+				return;
+			}
+			let block = (
+				block_left.max(bounds.0),
+				block_top.max(bounds.1),
+				(block_left + step).min(bounds.2),
+				(block_top + step).min(bounds.3),
+			);
+
+			let mut drawing = Drawing {
+				triangle_weights: &triangle_weights,
+				splitter_list: &splitter_list,
+				children,
+				bounds: block,
+				totals: vec![(LinearCol::new(0.0, 0.0, 0.0, 0.0), 0.0); (block.2 - block.0) * (block.3 - block.1)],
+			};
+			for &index in &in_block[row * columns + column] {
+				tiles[index].draw(&mut drawing);
+			}
+
+			sink.begin_chunk(block);
+			for y in block.1..block.3 {
+				for x in block.0..block.2 {
+					let (colour, area) = drawing.totals[(y - block.1) * (block.2 - block.0) + (x - block.0)];
+					sink.put(x, y, if area > 0.0 {colour / area} else {colour});
+				}
+			}
+			sink.commit_chunk(block);
+		}
+	}
+}
+
+//the same as render::draw_region, for trimers
+pub fn draw_region<F, S>(
+	fractal: &TrimerSpecification,
+	view: View,
+	bounds: (usize, usize, usize, usize),
+	triangle_colouring: F,
+	antialiasing: usize,
+	sink: &S,
+) where F: FnMut(EvenD12Translation) -> Box<dyn TernaryInterval>, S: PixelSink + ?Sized {
+	if antialiasing > 1 {
+		let large = view.enlarged(antialiasing);
+		let large_bounds = (bounds.0 * antialiasing, bounds.1 * antialiasing, bounds.2 * antialiasing, bounds.3 * antialiasing);
+		let supersampled = Supersampled::new(sink, view.width, view.height, antialiasing);
+		render_region(fractal, triangle_colouring, large_bounds, large.origin, large.scale, &supersampled);
+	} else {
+		render_region(fractal, triangle_colouring, bounds, view.origin, view.scale, sink);
+	}
+}
+
+pub fn render_antialiased<F>(fractal: &TrimerSpecification, view: View, triangle_colouring: F, antialiasing: usize) -> Image
+	where F: FnMut(EvenD12Translation) -> Box<dyn TernaryInterval> {
+	let canvas = FloatCanvas::new(view.width, view.height);
+	draw_region(fractal, view, (0, 0, view.width, view.height), triangle_colouring, antialiasing, &canvas);
+	Image::from_pixels(view.width, view.height, canvas.into_colours())
+}
+
+//tracker's total should be view.width * view.height
+pub fn render_tracked<F>(
+	fractal: &TrimerSpecification,
+	view: View,
+	triangle_colouring: F,
+	antialiasing: usize,
+	tracker: Arc<ProgressTracker>,
+) -> Image where F: FnMut(EvenD12Translation) -> Box<dyn TernaryInterval> {
+	let canvas = FloatCanvas::new(view.width, view.height);
+	draw_region(fractal, view, (0, 0, view.width, view.height), triangle_colouring, antialiasing, &ProgressSink::new(&canvas, tracker));
+	Image::from_pixels(view.width, view.height, canvas.into_colours())
+}
+
+
+//colourings. Most are auxiliary's, which work for trimers too through this
+impl CopyTranslation for EvenD12Translation {
+	type Interval = dyn TernaryInterval;
+
+	const IDENTITY: EvenD12Translation = IDENTITY;
+	const ROTATIONS: i8 = 6;
+
+	fn get_translation(self) -> (isize, isize) {self.get_translation()}
+	fn orientation(self) -> (bool, i8) {(self.get_dihedral().flipped(), self.get_dihedral().rot())}
+
+	fn constant(colour: LinearCol) -> Box<dyn TernaryInterval> {
+		Box::<ConstantInterval>::new(colour.into())
+	}
+	fn function<F: Fn(f64) -> LinearCol + Clone + 'static>(func: F) -> Box<dyn TernaryInterval> {
+		Box::<FunctionInterval<F>>::new(func.into())
+	}
+}
+
+//two_colouring, but with a colour for each of the three pieces
+pub fn three_colouring (transform: EvenD12Translation) -> Box<dyn TernaryInterval> {
+	let background = LinearCol::new(0.0, 0.0, 0.0, 0.0);
+	if transform != IDENTITY {
+		Box::<ConstantInterval>::new(background.into())
+	} else {
+		let colours = [
+			LinearCol::new(1.0, 0.0, 0.0, 0.0),
+			LinearCol::new(0.0, 1.0, 0.0, 0.0),
+			LinearCol::new(0.0, 0.0, 1.0, 0.0),
+		];
+		let mid_colour = (colours[0] + colours[1] + colours[2]) / 3.0;
+		let children = colours.map(|colour| Rc::new(ConstantInterval::from(colour)) as Rc<dyn TernaryInterval>);
+		Box::new(TernaryTreeInterval::new(mid_colour, children))
+	}
+}
+
+pub type TrimerColouring = fn(EvenD12Translation) -> Box<dyn TernaryInterval>;
+
+pub static TRIMER_COLOURINGS: [(&str, TrimerColouring); 7] = [
+	("simple", auxiliary::simple_colouring),
+	("three", three_colouring),
+	("continuum", auxiliary::simple_continuum_colouring),
+	("rainbow", auxiliary::rainbow_colouring),
+	("fire", auxiliary::fire_colouring),
+	("orientation", auxiliary::orientation_colouring),
+	("copies", auxiliary::copies_colouring),
+];
+
+pub fn colouring(name: &str) -> Option<TrimerColouring> {
+	TRIMER_COLOURINGS
+		.iter()
+		.find(|(colouring_name, _)| *colouring_name == name)
+		.map(|(_, colouring)| *colouring)
+}
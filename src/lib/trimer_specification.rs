@@ -0,0 +1,133 @@
+use super::dihedral12_translation::{EvenD12Translation, OddD12TranslationSmall, OddD12TranslationLarge};
+use super::relevance_getter::SelfSimilar;
+
+//the trimer version of FractalSpecification: everything splits into three pieces, sqrt(3) smaller, instead of two.
+//the triangles are 30-60-90 ones (which split into three copies of themselves), tiling the plane like the
+//lines of symmetry of a hexagonal grid do. Transformations are in eisenstein coordinates (see dihedral12).
+//
+//there's no square grid to line the triangles up with, so there's no square config either.
+//instead, tile is the base triangle itself, and the triangles drawn are every acceptable transformation of it.
+pub struct TrimerSpecification {
+	production_policy: [OddD12TranslationSmall; 3],
+	splitting_policy: [OddD12TranslationSmall; 3],
+	internal_acceptable: &'static (dyn Fn(EvenD12Translation) -> bool + Sync),
+	tile: [(f64, f64); 3], //corners in eisenstein coordinates, at (30 degrees, 60 degrees, 90 degrees)
+}
+
+impl TrimerSpecification {
+	pub const fn new(
+		production_policy: [OddD12TranslationSmall; 3],
+		splitting_policy: [OddD12TranslationSmall; 3],
+		acceptable: &'static (dyn Fn(EvenD12Translation) -> bool + Sync),
+		tile: [(f64, f64); 3],
+	) -> TrimerSpecification {
+		TrimerSpecification {
+			production_policy,
+			splitting_policy,
+			internal_acceptable: acceptable,
+			tile,
+		}
+	}
+
+	pub fn splitting_policy(&self) -> [OddD12TranslationSmall; 3] {
+		self.splitting_policy
+	}
+
+	pub fn production_policy(&self) -> [OddD12TranslationSmall; 3] {
+		self.production_policy
+	}
+
+	pub fn acceptable(&self, transformation: EvenD12Translation) -> bool {
+		(self.internal_acceptable)(transformation)
+	}
+
+	pub fn tile(&self) -> [(f64, f64); 3] {
+		self.tile
+	}
+}
+
+impl SelfSimilar for TrimerSpecification {
+	type Even = EvenD12Translation;
+	type Small = OddD12TranslationSmall;
+	type Large = OddD12TranslationLarge;
+
+	const IDENTITY: EvenD12Translation = super::dihedral12_translation::IDENTITY;
+	fn inverse(transformation: EvenD12Translation) -> EvenD12Translation {transformation.inv()}
+	fn unsplit(piece: OddD12TranslationSmall) -> OddD12TranslationLarge {piece.inv()}
+
+	fn production_pieces(&self) -> Vec<OddD12TranslationSmall> {
+		self.production_policy.to_vec()
+	}
+
+	fn splitting_pieces(&self) -> Vec<OddD12TranslationSmall> {
+		self.splitting_policy.to_vec()
+	}
+
+	fn is_acceptable(&self, transformation: EvenD12Translation) -> bool {
+		self.acceptable(transformation)
+	}
+}
+
+//a triangle in the grid whose corners are multiples of 3 (any way round): the smallest grid a 30-60-90 triangle
+//can split into three on without any of the pieces' corners leaving whole numbers
+pub fn multiple_of_three(transformation: EvenD12Translation) -> bool {
+	let (a, b) = transformation.get_translation();
+	a.rem_euclid(3) == 0 && b.rem_euclid(3) == 0
+}
+
+//the triangle that goes with multiple_of_three: 30 degrees at the origin, 60 at the middle of a grid triangle, 90 halfway along an edge
+pub const SMALL_TILE: [(f64, f64); 3] = [(0.0, 0.0), (1.0, 1.0), (1.5, 0.0)];
+
+//how SMALL_TILE splits. The 60 degree corner gets cut in half, and so does the hypotenuse
+pub const SMALL_TILE_SPLIT: [OddD12TranslationSmall; 3] = [
+	OddD12TranslationSmall::new(true , 0, (0, 0)),
+	OddD12TranslationSmall::new(false, 3, (1, 1)),
+	OddD12TranslationSmall::new(true , 4, (1, 1)),
+];
+
+//found by trying every production policy near the origin and keeping the ones the relevance search could finish
+pub const FLAKE: TrimerSpecification = TrimerSpecification::new(
+	[
+		OddD12TranslationSmall::new(false, 0, (-2, 1)),
+		OddD12TranslationSmall::new(false, 2, (-4, 2)),
+		OddD12TranslationSmall::new(false, 3, (-3, 0)),
+	],
+	SMALL_TILE_SPLIT,
+	&multiple_of_three,
+	SMALL_TILE,
+);
+
+pub const CURL: TrimerSpecification = TrimerSpecification::new(
+	[
+		OddD12TranslationSmall::new(false, 0, (-1, -1)),
+		OddD12TranslationSmall::new(false, 2, (-2, -2)),
+		OddD12TranslationSmall::new(true , 5, ( 0, -3)),
+	],
+	SMALL_TILE_SPLIT,
+	&multiple_of_three,
+	SMALL_TILE,
+);
+
+pub const CREST: TrimerSpecification = TrimerSpecification::new(
+	[
+		OddD12TranslationSmall::new(false, 0, (-2, 1)),
+		OddD12TranslationSmall::new(true , 3, (-3, 0)),
+		OddD12TranslationSmall::new(true , 4, (-1, -1)),
+	],
+	SMALL_TILE_SPLIT,
+	&multiple_of_three,
+	SMALL_TILE,
+);
+
+pub static TRIMER_PRESETS: [(&str, &TrimerSpecification); 3] = [
+	("flake", &FLAKE),
+	("curl", &CURL),
+	("crest", &CREST),
+];
+
+pub fn preset(name: &str) -> Option<&'static TrimerSpecification> {
+	TRIMER_PRESETS
+		.iter()
+		.find(|(preset_name, _)| *preset_name == name)
+		.map(|(_, fractal)| *fractal)
+}
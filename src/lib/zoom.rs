@@ -0,0 +1,201 @@
+use std::rc::Rc;
+
+use super::colour_format::LinearCol;
+use super::dihedral_translation::{EvenD8Translation, IDENTITY};
+use super::fractal_specification::FractalSpecification;
+use super::interval_colouring::{ConstantInterval, SplitInterval};
+use super::interval_rules;
+use super::render::{self, Image, View};
+
+//zooming in on one point of the fractal, from the scene's view through levels doublings of scale.
+//points are kept in scale 0 pixels from the origin (y = down), so they mean the same thing at every scale.
+//scale only comes in whole steps, so a frame between two of them is drawn at the bigger one and shrunk to fit
+//(see frame_view and resampled). The point starts where the scene shows it and drifts to the middle as it's zoomed into.
+
+//past this, the renderer's isize pixel coordinates (and f64 points) run out
+pub const MAX_SCALE: u32 = 48;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Easing {
+	Linear,
+	//starts and stops gently, going fastest halfway
+	Smooth,
+}
+
+impl Easing {
+	pub fn from_name(name: &str) -> Option<Easing> {
+		match name {
+			"linear" => Some(Easing::Linear),
+			"smooth" => Some(Easing::Smooth),
+			_ => None,
+		}
+	}
+
+	//t and the result both go from 0 to 1
+	pub fn apply(self, t: f64) -> f64 {
+		match self {
+			Easing::Linear => t,
+			Easing::Smooth => (1.0 - (t * std::f64::consts::PI).cos()) / 2.0,
+		}
+	}
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Zoom {
+	target: (f64, f64),
+	levels: f64,
+	easing: Easing,
+}
+
+impl Zoom {
+	//levels can be fractional, so long as the scene's scale plus levels stays under MAX_SCALE
+	pub fn new(target: (f64, f64), levels: f64, easing: Easing) -> Zoom {
+		Zoom {target, levels, easing}
+	}
+
+	pub fn get_target(&self) -> (f64, f64) {
+		self.target
+	}
+
+	//(the view to render at t, where the frame's top left corner is in it, how many of its pixels go across one of the frame's).
+	//base is the scene's view, which is also the frame's size
+	pub fn frame_view(&self, base: View, t: f64) -> (View, (f64, f64), f64) {
+		let eased = self.easing.apply(t.clamp(0.0, 1.0));
+		let zoom = base.scale as f64 + eased * self.levels;
+		let scale = zoom.ceil() as u32;
+		let ratio = (scale as f64 - zoom).exp2();
+
+		//where the target is in the frame
+		let start = (
+			base.origin.0 as f64 + self.target.0 * (base.scale as f64).exp2(),
+			base.origin.1 as f64 + self.target.1 * (base.scale as f64).exp2(),
+		);
+		let middle = (base.width as f64 / 2.0, base.height as f64 / 2.0);
+		let on_screen = (start.0 + eased * (middle.0 - start.0), start.1 + eased * (middle.1 - start.1));
+
+		//the frame's corner, in the render's pixels if the origin were at 0, split into whole pixels and what's left
+		let corner = (
+			self.target.0 * (scale as f64).exp2() - on_screen.0 * ratio,
+			self.target.1 * (scale as f64).exp2() - on_screen.1 * ratio,
+		);
+		let offset = (corner.0 - corner.0.floor(), corner.1 - corner.1.floor());
+		let view = View {
+			width: (offset.0 + base.width as f64 * ratio).ceil() as usize,
+			height: (offset.1 + base.height as f64 * ratio).ceil() as usize,
+			origin: (-corner.0.floor() as isize, -corner.1.floor() as isize),
+			scale,
+		};
+		(view, offset, ratio)
+	}
+}
+
+//pixel (x, y) of view, as a point
+pub fn canvas_point(view: View, (x, y): (isize, isize)) -> (f64, f64) {
+	let size = (view.scale as f64).exp2();
+	(
+		(x as f64 + 0.5 - view.origin.0 as f64) / size,
+		(y as f64 + 0.5 - view.origin.1 as f64) / size,
+	)
+}
+
+//how big the renders address_point looks through are, and the scale it starts at
+const SEARCH_SIZE: usize = 256;
+const SEARCH_SCALE: u32 = 4;
+
+//past this many digits, the piece is smaller than anything MAX_SCALE can show
+pub const MAX_ADDRESS_DIGITS: usize = 2 * (MAX_SCALE - SEARCH_SCALE) as usize;
+
+//the middle (going by area) of the piece of the identity's copy at address, a string of 0s and 1s as in interval_rules.
+//it's found by drawing the piece lit up against everything else and taking the middle of the light.
+//every two digits halves the piece's size, so that's done two digits at a time with the scale going up by one each time,
+//each render centred on the last one's answer. That way the piece always fills about the same part of the render.
+pub fn address_point(fractal: &FractalSpecification, address: &str) -> Result<(f64, f64), String> {
+	if address.len() > MAX_ADDRESS_DIGITS {
+		return Err(format!("addresses can only be {} digits long", MAX_ADDRESS_DIGITS));
+	}
+	if let Some(digit) = address.chars().find(|digit| !matches!(digit, '0' | '1')) {
+		return Err(format!("addresses are made of 0s and 1s, not {:?}", digit));
+	}
+	let lit = LinearCol::new(1.0, 1.0, 1.0, 0.0);
+	let dark = LinearCol::new(0.0, 0.0, 0.0, 0.0);
+
+	let mut point = (0.0, 0.0);
+	let mut depth = 0;
+	loop {
+		let prefix = &address[..depth];
+		let automaton = Rc::new(interval_rules::prefix_rules(&[(prefix, lit)], dark)?);
+		let marker = |transform: EvenD8Translation| -> Box<dyn SplitInterval> {
+			if transform == IDENTITY {
+				Box::new(automaton.interval())
+			} else {
+				Box::<ConstantInterval>::new(dark.into())
+			}
+		};
+
+		let scale = SEARCH_SCALE + depth as u32 / 2;
+		let size = (scale as f64).exp2();
+		let view = View {
+			width: SEARCH_SIZE,
+			height: SEARCH_SIZE,
+			origin: (
+				(SEARCH_SIZE as f64 / 2.0 - point.0 * size).round() as isize,
+				(SEARCH_SIZE as f64 / 2.0 - point.1 * size).round() as isize,
+			),
+			scale,
+		};
+		let image = render::render(fractal, view, marker);
+
+		let (mut total, mut x_total, mut y_total) = (0.0, 0.0, 0.0);
+		for y in 0..view.height {
+			for x in 0..view.width {
+				let light = image.get(x, y).y_lin();
+				total += light;
+				x_total += light * x as f64;
+				y_total += light * y as f64;
+			}
+		}
+		if total <= 0.0 {
+			return Err(format!("couldn't find the piece at {:?}", prefix));
+		}
+		//like canvas_point, but without rounding to a whole pixel
+		point = (
+			(x_total / total + 0.5 - view.origin.0 as f64) / size,
+			(y_total / total + 0.5 - view.origin.1 as f64) / size,
+		);
+
+		if depth == address.len() {
+			return Ok(point);
+		}
+		depth = (depth + 2).min(address.len());
+	}
+}
+
+//image shrunk by ratio (at least 1) to width x height, starting offset into it. Every pixel is the average of what it covers
+pub fn resampled(image: &Image, offset: (f64, f64), ratio: f64, width: usize, height: usize) -> Image {
+	//for each pixel along one direction, the pixels of image it covers and how much of it each one is
+	let coverage = |start: f64, count: usize, limit: usize| -> Vec<Vec<(usize, f64)>> {
+		(0..count).map(|index| {
+			let (from, to) = (start + index as f64 * ratio, start + (index + 1) as f64 * ratio);
+			(from.floor() as usize..to.ceil() as usize).map(|source| {
+				let covered = (to.min(source as f64 + 1.0) - from.max(source as f64)) / ratio;
+				(source.min(limit - 1), covered)
+			}).collect()
+		}).collect()
+	};
+	let columns = coverage(offset.0, width, image.get_width());
+	let rows = coverage(offset.1, height, image.get_height());
+
+	let mut pixels = Vec::with_capacity(width * height);
+	for row in &rows {
+		for column in &columns {
+			let mut colour = LinearCol::new(0.0, 0.0, 0.0, 0.0);
+			for &(y, down) in row {
+				for &(x, across) in column {
+					colour = colour + (down * across) * image.get(x, y);
+				}
+			}
+			pixels.push(colour);
+		}
+	}
+	Image::from_pixels(width, height, pixels)
+}
@@ -1,30 +1,62 @@
-#![windows_subsystem = "windows"]
+#![cfg_attr(feature = "gui", windows_subsystem = "windows")]
 
+#[cfg(feature = "gui")]
 use druid::widget::prelude::*;
+#[cfg(feature = "gui")]
 use druid::{AppLauncher, WindowDesc, piet};
+#[cfg(feature = "gui")]
+use druid::widget::Flex;
 
-mod lib;
-use lib::canvas::{FractalBuilder, FractalCanvas};
-
-const WINDOW_WIDTH: usize = 16*60;
-const WINDOW_HEIGHT: usize = 9*60;
+#[cfg(feature = "gui")]
+use dimers::canvas::{FractalBuilder, FractalCanvas};
+#[cfg(feature = "gui")]
+use dimers::controls::{self, AppState};
 
 pub fn main() {
+	// any arguments at all means a headless render (and without the gui, that's the only option)
+	let args: Vec<String> = std::env::args().skip(1).collect();
+	if !args.is_empty() || cfg!(not(feature = "gui")) {
+		if let Err(message) = dimers::headless::run(&args) {
+			eprintln!("{}", message);
+			std::process::exit(1);
+		}
+	} else {
+		#[cfg(feature = "gui")]
+		launch_gui();
+	}
+}
+
+#[cfg(feature = "gui")]
+fn launch_gui() {
+	// the window starts out showing scene::Scene::default
+	let initial_state = AppState::default();
+	let window_size = (initial_state.width as f64 + controls::PANEL_WIDTH, initial_state.height as f64);
+	let root_state = initial_state.clone();
+
     // describe the main window
-    let main_window = WindowDesc::new(build_root_widget)
+    let main_window = WindowDesc::new(move || build_root_widget(&root_state))
         .title("Dimers")
-        .window_size((WINDOW_WIDTH as f64, WINDOW_HEIGHT as f64));
-
-	let initial_state = ();
+        .menu(controls::build_menu())
+        .window_size(window_size);
 
     // start the application. Here we pass in the application state.
     AppLauncher::with_window(main_window)
+        .delegate(controls::Delegate)
         .use_simple_logger()
         .launch(initial_state)
         .expect("Failed to launch application");
 }
 
-fn build_root_widget() -> impl Widget<()> {
-    let builder = FractalBuilder::new(WINDOW_WIDTH, WINDOW_HEIGHT, piet::ImageFormat::RgbaSeparate);
-	FractalCanvas::new(builder)
+#[cfg(feature = "gui")]
+fn build_root_widget(state: &AppState) -> impl Widget<AppState> {
+    let builder = FractalBuilder::new(
+		state.width,
+		state.height,
+		piet::ImageFormat::RgbaSeparate,
+		controls::DISPLAY_DITHERING,
+		state.render_settings()
+	);
+	Flex::row()
+		.with_child(controls::build_side_panel())
+		.with_flex_child(FractalCanvas::new(builder), 1.0)
 }
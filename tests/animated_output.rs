@@ -0,0 +1,284 @@
+//the animated writers in image_output, read back by decoders written here from the specs rather than from the writers:
+//gif's lzw (including the table filling up and starting over) and apng's chunk order, sequence numbers and crcs.
+
+use dimers::dithering::Dithering;
+use dimers::image_output::{write_png, ApngWriter, GifWriter};
+use dimers::pixel_format::PixelFormat;
+
+//xorshift64, as in group_laws
+struct Random(u64);
+
+impl Random {
+	fn below(&mut self, n: u64) -> u64 {
+		self.0 ^= self.0 << 13;
+		self.0 ^= self.0 >> 7;
+		self.0 ^= self.0 << 17;
+		self.0 % n
+	}
+}
+
+//----------------------------------------------------------------- gif
+
+//rgb bytes that land exactly on the writer's 6 x 6 x 6 palette, and the palette index each pixel should become
+fn palette_pixels(levels: &[(u8, u8, u8)]) -> (Vec<u8>, Vec<u8>) {
+	let rgb = levels.iter().flat_map(|&(r, g, b)| [r * 51, g * 51, b * 51]).collect();
+	let indices = levels.iter().map(|&(r, g, b)| r * 36 + g * 6 + b).collect();
+	(rgb, indices)
+}
+
+//the lzw data of every image in a gif, with the sub-blocks joined back together
+fn gif_images(gif: &[u8]) -> Vec<Vec<u8>> {
+	assert_eq!(&gif[..6], b"GIF89a");
+	let flags = gif[10];
+	let mut position = 13;
+	if flags & 0x80 != 0 {
+		position += 3 << ((flags & 7) + 1);
+	}
+
+	let mut images = Vec::new();
+	let sub_blocks = |position: &mut usize| -> Vec<u8> {
+		let mut data = Vec::new();
+		loop {
+			let length = gif[*position] as usize;
+			*position += 1;
+			if length == 0 {
+				return data;
+			}
+			data.extend_from_slice(&gif[*position..*position + length]);
+			*position += length;
+		}
+	};
+	loop {
+		match gif[position] {
+			0x21 => {
+				position += 2;
+				sub_blocks(&mut position);
+			},
+			0x2c => {
+				assert_eq!(gif[position + 9] & 0x80, 0, "no local palettes");
+				assert_eq!(gif[position + 10], 8, "8 bit codes");
+				position += 11;
+				images.push(sub_blocks(&mut position));
+			},
+			0x3b => {
+				assert_eq!(position + 1, gif.len(), "nothing after the trailer");
+				return images;
+			},
+			other => panic!("unexpected gif block {:#x} at {}", other, position),
+		}
+	}
+}
+
+//(the palette indices, how many clear codes there were). Codes are read least significant bit first, 9 bits wide to start with,
+//and a bit wider every time the table gets to the next power of two, up to 12. The table stops growing at 4096 until a clear
+fn lzw_decode(data: &[u8]) -> (Vec<u8>, usize) {
+	const CLEAR: usize = 256;
+	const END: usize = 257;
+	let fresh_table = || -> Vec<Vec<u8>> {(0..=255).map(|index| vec![index]).chain([vec![], vec![]]).collect()};
+
+	let mut table = fresh_table();
+	let mut output = Vec::new();
+	let mut clears = 0;
+	let mut previous: Option<Vec<u8>> = None;
+	let mut bit = 0;
+	loop {
+		let width = (usize::BITS - table.len().leading_zeros()).clamp(9, 12) as usize;
+		assert!(bit + width <= data.len() * 8, "ran out of data before the end code");
+		let code = (0..width).fold(0, |code, place| code | ((data[(bit + place) / 8] as usize >> ((bit + place) % 8)) & 1) << place);
+		bit += width;
+
+		if code == CLEAR {
+			table = fresh_table();
+			previous = None;
+			clears += 1;
+			continue;
+		}
+		if code == END {
+			assert!(data.len() * 8 - bit < 8, "data after the end code");
+			return (output, clears);
+		}
+		let entry = match (&previous, table.get(code)) {
+			(_, Some(entry)) if code != CLEAR && code != END => entry.clone(),
+			//the one code that can be used in the same step it's made
+			(Some(previous), None) if code == table.len() => [&previous[..], &previous[..1]].concat(),
+			_ => panic!("code {} isn't in the table (which has {})", code, table.len()),
+		};
+		if let Some(previous) = &previous {
+			if table.len() < 4096 {
+				table.push([&previous[..], &entry[..1]].concat());
+			}
+		}
+		output.extend_from_slice(&entry);
+		previous = Some(entry);
+	}
+}
+
+#[test]
+fn gif_frames_decode_to_their_palette_indices() {
+	let (width, height) = (5, 3);
+	let mut random = Random(0x853c_49e6_748f_ea9b);
+	let frames: Vec<Vec<(u8, u8, u8)>> = vec![
+		(0..width * height).map(|_| (random.below(6) as u8, random.below(6) as u8, random.below(6) as u8)).collect(),
+		vec![(5, 0, 2); width * height],
+	];
+
+	let mut gif = GifWriter::new(Vec::new(), width, height, 4, Dithering::None).expect("small enough for a gif");
+	for frame in &frames {
+		gif.write_frame(&palette_pixels(frame).0).expect("right size");
+	}
+	let gif = gif.finish().expect("writing to a vec");
+
+	let images = gif_images(&gif);
+	assert_eq!(images.len(), frames.len());
+	for (image, frame) in images.iter().zip(&frames) {
+		let (indices, clears) = lzw_decode(image);
+		assert_eq!(indices, palette_pixels(frame).1);
+		assert_eq!(clears, 1, "just the one at the start");
+	}
+}
+
+#[test]
+fn gif_lzw_starts_over_when_the_table_fills() {
+	//noise makes a new code for nearly every pixel, so this goes through the 4096 code table a few times over
+	let (width, height) = (160, 120);
+	let mut random = Random(0xda94_2042_e4dd_58b5);
+	let noise: Vec<(u8, u8, u8)> = (0..width * height).map(|_| (random.below(6) as u8, random.below(6) as u8, random.below(6) as u8)).collect();
+
+	let mut gif = GifWriter::new(Vec::new(), width, height, 4, Dithering::None).expect("small enough for a gif");
+	gif.write_frame(&palette_pixels(&noise).0).expect("right size");
+	let gif = gif.finish().expect("writing to a vec");
+
+	let images = gif_images(&gif);
+	let (indices, clears) = lzw_decode(&images[0]);
+	assert!(clears > 2, "only {} clear codes", clears);
+	assert_eq!(indices, palette_pixels(&noise).1);
+}
+
+#[test]
+fn gif_frames_have_to_be_the_right_size() {
+	let mut gif = GifWriter::new(Vec::new(), 4, 4, 4, Dithering::None).expect("small enough for a gif");
+	assert!(gif.write_frame(&[0; 3 * 15]).is_err());
+	assert!(GifWriter::new(Vec::new(), 70_000, 4, 4, Dithering::None).is_err());
+}
+
+//----------------------------------------------------------------- apng
+
+//the crc png uses, a bit at a time
+fn crc32(bytes: &[u8]) -> u32 {
+	let mut crc = 0xffff_ffffu32;
+	for &byte in bytes {
+		crc ^= byte as u32;
+		for _ in 0..8 {
+			crc = if crc & 1 == 1 {(crc >> 1) ^ 0xedb8_8320} else {crc >> 1};
+		}
+	}
+	!crc
+}
+
+//every chunk as (type, data), checking each crc on the way
+fn png_chunks(png: &[u8]) -> Vec<(String, Vec<u8>)> {
+	assert_eq!(&png[..8], &[0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n']);
+	let mut chunks = Vec::new();
+	let mut position = 8;
+	while position < png.len() {
+		let length = u32::from_be_bytes(png[position..position + 4].try_into().expect("4 bytes")) as usize;
+		let typed = &png[position + 4..position + 8 + length];
+		let crc = u32::from_be_bytes(png[position + 8 + length..position + 12 + length].try_into().expect("4 bytes"));
+		let chunk_type = String::from_utf8(typed[..4].to_vec()).expect("ascii chunk type");
+		assert_eq!(crc, crc32(typed), "bad crc on {} at {}", chunk_type, position);
+		chunks.push((chunk_type, typed[4..].to_vec()));
+		position += 12 + length;
+	}
+	chunks
+}
+
+fn be_u32(bytes: &[u8]) -> u32 {
+	u32::from_be_bytes(bytes[..4].try_into().expect("4 bytes"))
+}
+
+//the rows back out of a zlib stream of stored blocks (all the writers make), with the filter bytes checked and taken off
+fn unstored(stream: &[u8], row_bytes: usize) -> Vec<u8> {
+	assert_eq!((stream[0] as u16 * 256 + stream[1] as u16) % 31, 0, "bad zlib header");
+	let mut filtered = Vec::new();
+	let mut position = 2;
+	loop {
+		let header = stream[position];
+		assert_eq!(header & 6, 0, "stored blocks only");
+		let length = u16::from_le_bytes([stream[position + 1], stream[position + 2]]);
+		let check = u16::from_le_bytes([stream[position + 3], stream[position + 4]]);
+		assert_eq!(length, !check);
+		filtered.extend_from_slice(&stream[position + 5..position + 5 + length as usize]);
+		position += 5 + length as usize;
+		if header & 1 == 1 {
+			break;
+		}
+	}
+	let (mut a, mut b) = (1u32, 0u32);
+	for &byte in &filtered {
+		a = (a + byte as u32) % 65521;
+		b = (b + a) % 65521;
+	}
+	assert_eq!(be_u32(&stream[position..]), b << 16 | a, "bad adler32");
+	assert_eq!(position + 4, stream.len());
+
+	filtered.chunks(row_bytes + 1).flat_map(|row| {
+		assert_eq!(row[0], 0, "no filtering");
+		row[1..].to_vec()
+	}).collect()
+}
+
+#[test]
+fn apng_chunks_are_numbered_and_checked() {
+	let (width, height, delay) = (5, 4, 70);
+	let frames: Vec<Vec<u8>> = (0..3u8).map(|frame| (0..3 * width * height).map(|byte| (byte as u8).wrapping_mul(7).wrapping_add(frame * 40)).collect()).collect();
+
+	let mut apng = ApngWriter::new(Vec::new(), width, height, PixelFormat::Rgb, frames.len() as u32, delay).expect("rgb is a png format");
+	for frame in &frames {
+		apng.write_frame(frame).expect("right size");
+	}
+	let apng = apng.finish().expect("all the frames");
+
+	let chunks = png_chunks(&apng);
+	let types: Vec<&str> = chunks.iter().map(|(chunk_type, _)| chunk_type.as_str()).collect();
+	assert_eq!(types, ["IHDR", "acTL", "fcTL", "IDAT", "fcTL", "fdAT", "fcTL", "fdAT", "IEND"]);
+
+	let animation_control = &chunks[1].1;
+	assert_eq!(be_u32(animation_control), frames.len() as u32);
+	assert_eq!(be_u32(&animation_control[4..]), 0, "loops forever");
+
+	//fcTL and fdAT share one count, from 0 with no gaps
+	let sequence: Vec<u32> = chunks.iter()
+		.filter(|(chunk_type, _)| chunk_type == "fcTL" || chunk_type == "fdAT")
+		.map(|(_, data)| be_u32(data))
+		.collect();
+	assert_eq!(sequence, (0..sequence.len() as u32).collect::<Vec<_>>());
+
+	let mut decoded = Vec::new();
+	for (chunk_type, data) in &chunks {
+		match chunk_type.as_str() {
+			"fcTL" => {
+				assert_eq!((be_u32(&data[4..]), be_u32(&data[8..])), (width as u32, height as u32));
+				assert_eq!(u16::from_be_bytes([data[20], data[21]]), delay);
+				assert_eq!(u16::from_be_bytes([data[22], data[23]]), 1000);
+			},
+			"IDAT" => decoded.push(unstored(data, 3 * width)),
+			"fdAT" => decoded.push(unstored(&data[4..], 3 * width)),
+			_ => {},
+		}
+	}
+	assert_eq!(decoded, frames);
+}
+
+#[test]
+fn apng_frame_count_is_kept_to() {
+	let frame = [0; 3 * 2 * 2];
+	let mut apng = ApngWriter::new(Vec::new(), 2, 2, PixelFormat::Rgb, 1, 10).expect("rgb is a png format");
+	apng.write_frame(&frame).expect("right size");
+	assert!(apng.write_frame(&frame).is_err(), "one frame too many");
+
+	let short = ApngWriter::new(Vec::new(), 2, 2, PixelFormat::Rgb, 2, 10).expect("rgb is a png format");
+	assert!(short.finish().is_err(), "no frames at all");
+	assert!(ApngWriter::new(Vec::new(), 2, 2, PixelFormat::Rgb, 0, 10).is_err());
+	assert!(ApngWriter::new(Vec::new(), 0, 2, PixelFormat::Rgb, 1, 10).is_err(), "no width");
+	assert!(write_png(&mut Vec::new(), 2, 0, PixelFormat::Rgb, &[]).is_err(), "no height");
+}
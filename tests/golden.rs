@@ -0,0 +1,129 @@
+//renders every preset small and compares it with the reference in tests/golden, so changes to the renderer
+//(SquareCut::split especially) show up without looking at every fractal by eye.
+//
+//references only get written with DIMERS_BLESS=1 set (for a new preset, or when a change to the pictures is on purpose),
+//so a missing one is a failure like any other. Failures leave a diff image in cargo's test tmpdir.
+//references are binary ppm, since it's the simplest thing that image viewers open.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use dimers::auxiliary;
+use dimers::dithering::Dithering;
+use dimers::fractal_specification::PRESETS;
+use dimers::pixel_format::PixelFormat;
+use dimers::pixel_sink::ByteCanvas;
+use dimers::relevance_getter;
+use dimers::square::draw_into_canvas;
+
+const WIDTH: usize = 128;
+const HEIGHT: usize = 96;
+const SCALE: u32 = 5;
+//per channel, out of 255. Rounding differences between platforms' float maths shouldn't fail anything
+const TOLERANCE: u8 = 2;
+
+fn render(name: &str) -> Vec<u8> {
+	let fractal = PRESETS.iter().find(|(preset_name, _)| *preset_name == name).expect("preset exists").1;
+	let relevance_list = relevance_getter::get_relevance_list(fractal).expect("presets pass the relevance search");
+	let requirement_list = relevance_getter::to_requirement_list(&relevance_list);
+	let splitter_list = relevance_getter::get_splitter_list(fractal, &requirement_list).expect("presets pass the relevance search");
+	let triangle_weights = relevance_getter::get_weights(fractal, &relevance_list).expect("presets pass the relevance search");
+
+	let canvas = ByteCanvas::new(WIDTH, HEIGHT, PixelFormat::Rgb, Dithering::None);
+	draw_into_canvas(
+		&canvas,
+		&triangle_weights,
+		&requirement_list,
+		(0, 0, WIDTH as isize, HEIGHT as isize),
+		((WIDTH / 2) as isize, (HEIGHT / 2) as isize),
+		SCALE,
+		fractal,
+		auxiliary::rainbow_colouring,
+		&splitter_list,
+	);
+	canvas.into_bytes()
+}
+
+fn write_ppm(path: &Path, width: usize, height: usize, rgb: &[u8]) {
+	let mut data = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+	data.extend_from_slice(rgb);
+	fs::write(path, data).unwrap_or_else(|error| panic!("couldn't write {}: {}", path.display(), error));
+}
+
+//only reads what write_ppm writes
+fn read_ppm(path: &Path) -> (usize, usize, Vec<u8>) {
+	let data = fs::read(path).unwrap_or_else(|error| panic!("couldn't read {}: {}", path.display(), error));
+	let mut fields = Vec::new();
+	let mut start = 0;
+	for (position, byte) in data.iter().enumerate() {
+		if byte.is_ascii_whitespace() {
+			if position > start {
+				fields.push(String::from_utf8_lossy(&data[start..position]).into_owned());
+			}
+			start = position + 1;
+			if fields.len() == 4 {
+				break;
+			}
+		}
+	}
+	assert_eq!(fields[0], "P6", "{} isn't a binary ppm", path.display());
+	let width = fields[1].parse().expect("ppm width");
+	let height = fields[2].parse().expect("ppm height");
+	(width, height, data[start..].to_vec())
+}
+
+//grey where they agree, red where they don't (brighter for bigger differences)
+fn diff_image(expected: &[u8], actual: &[u8]) -> Vec<u8> {
+	expected.chunks(3).zip(actual.chunks(3)).flat_map(|(expected, actual)| {
+		let difference = expected.iter().zip(actual).map(|(a, b)| a.abs_diff(*b)).max().unwrap_or(0);
+		if difference > TOLERANCE {
+			[128u8.saturating_add(difference / 2).max(160), 0, 0]
+		} else {
+			let grey = (expected.iter().map(|&channel| channel as u32).sum::<u32>() / 12) as u8;
+			[grey, grey, grey]
+		}
+	}).collect()
+}
+
+fn reference_path(name: &str) -> PathBuf {
+	Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("golden").join(format!("{}.ppm", name))
+}
+
+#[test]
+fn presets_match_their_references() {
+	let bless = env::var_os("DIMERS_BLESS").is_some();
+	let mut failures = Vec::new();
+
+	for (name, _) in PRESETS.iter() {
+		let actual = render(name);
+		let path = reference_path(name);
+		if bless {
+			fs::create_dir_all(path.parent().expect("golden directory")).expect("couldn't make tests/golden");
+			write_ppm(&path, WIDTH, HEIGHT, &actual);
+			eprintln!("wrote reference {}", path.display());
+			continue;
+		}
+		if !path.exists() {
+			failures.push(format!("{}: missing reference {}, rerun with DIMERS_BLESS=1", name, path.display()));
+			continue;
+		}
+
+		let (width, height, expected) = read_ppm(&path);
+		assert_eq!((width, height), (WIDTH, HEIGHT), "{} is the wrong size, rerun with DIMERS_BLESS=1 if that's on purpose", path.display());
+		let mismatched = expected
+			.chunks(3)
+			.zip(actual.chunks(3))
+			.filter(|(expected, actual)| expected.iter().zip(actual.iter()).any(|(a, b)| a.abs_diff(*b) > TOLERANCE))
+			.count();
+		if mismatched > 0 {
+			let diff_path = Path::new(env!("CARGO_TARGET_TMPDIR")).join(format!("{}-diff.ppm", name));
+			let actual_path = Path::new(env!("CARGO_TARGET_TMPDIR")).join(format!("{}-actual.ppm", name));
+			write_ppm(&diff_path, WIDTH, HEIGHT, &diff_image(&expected, &actual));
+			write_ppm(&actual_path, WIDTH, HEIGHT, &actual);
+			failures.push(format!("{}: {} pixels differ (see {} and {})", name, mismatched, diff_path.display(), actual_path.display()));
+		}
+	}
+
+	assert!(failures.is_empty(), "renders changed:\n{}", failures.join("\n"));
+}
@@ -0,0 +1,560 @@
+//the group laws that dihedral.rs and dihedral_translation.rs (and the trimer versions, dihedral12.rs and
+//dihedral12_translation.rs) are supposed to follow.
+//the dihedral groups are small enough to check everything, the translations get random samples instead.
+
+use dimers::dihedral::{self, EvenD8, OddD8};
+use dimers::dihedral12::{self, EvenD12, OddD12};
+use dimers::dihedral12_translation::{self, EvenD12Translation, OddD12TranslationLarge, OddD12TranslationSmall};
+use dimers::dihedral_translation::{self, EvenD8Translation, OddD8TranslationLarge, OddD8TranslationSmall};
+use dimers::similarity::{self, DyadicPoint, Similarity};
+use nalgebra::{Matrix2, Vector2};
+
+//xorshift64, so the samples are the same every run without pulling in a crate for it
+struct Random(u64);
+
+impl Random {
+	fn next(&mut self) -> u64 {
+		self.0 ^= self.0 << 13;
+		self.0 ^= self.0 >> 7;
+		self.0 ^= self.0 << 17;
+		self.0
+	}
+
+	fn below(&mut self, n: u64) -> u64 {
+		self.next() % n
+	}
+
+	fn translation(&mut self) -> (isize, isize) {
+		(self.below(13) as isize - 6, self.below(13) as isize - 6)
+	}
+
+	fn even(&mut self) -> EvenD8Translation {
+		EvenD8Translation::new(self.below(2) == 1, self.below(4) as i8, self.translation())
+	}
+
+	fn small(&mut self) -> OddD8TranslationSmall {
+		OddD8TranslationSmall::new(self.below(2) == 1, self.below(4) as i8, self.translation())
+	}
+
+	fn large(&mut self) -> OddD8TranslationLarge {
+		OddD8TranslationLarge::new(self.below(2) == 1, self.below(4) as i8, self.translation())
+	}
+
+	fn even12(&mut self) -> EvenD12Translation {
+		EvenD12Translation::new(self.below(2) == 1, self.below(6) as i8, self.translation())
+	}
+
+	fn small12(&mut self) -> OddD12TranslationSmall {
+		OddD12TranslationSmall::new(self.below(2) == 1, self.below(6) as i8, self.translation())
+	}
+
+	fn large12(&mut self) -> OddD12TranslationLarge {
+		OddD12TranslationLarge::new(self.below(2) == 1, self.below(6) as i8, self.translation())
+	}
+}
+
+const SAMPLES: usize = 20000;
+
+fn evens() -> Vec<EvenD8> {
+	[false, true].iter().flat_map(|&flip| (0..4).map(move |rot| EvenD8::new(flip, rot))).collect()
+}
+
+fn odds() -> Vec<OddD8> {
+	[false, true].iter().flat_map(|&flip| (0..4).map(move |rot| OddD8::new(flip, rot))).collect()
+}
+
+fn points() -> Vec<(isize, isize)> {
+	(-4..=4).flat_map(|x| (-4..=4).map(move |y| (x, y))).collect()
+}
+
+//the matrices the elements are supposed to be: flip (in the x axis), then the eighth turn and sqrt(2) for odd ones, then quarter turns
+fn quarter_turns(rot: i8) -> Matrix2<isize> {
+	let turn = Matrix2::new(0, -1, 1, 0);
+	(0..rot).fold(Matrix2::identity(), |matrix, _| turn * matrix)
+}
+
+fn flip_matrix(flip: bool) -> Matrix2<isize> {
+	if flip {Matrix2::new(1, 0, 0, -1)} else {Matrix2::identity()}
+}
+
+fn even_matrix(element: EvenD8) -> Matrix2<isize> {
+	quarter_turns(element.rot()) * flip_matrix(element.flipped())
+}
+
+//as large, so sqrt(2) bigger
+fn odd_matrix(element: OddD8) -> Matrix2<isize> {
+	quarter_turns(element.rot()) * Matrix2::new(1, -1, 1, 1) * flip_matrix(element.flipped())
+}
+
+fn times(matrix: Matrix2<isize>, point: (isize, isize)) -> (isize, isize) {
+	let product = matrix * Vector2::new(point.0, point.1);
+	(product.x, product.y)
+}
+
+#[test]
+fn apply_agrees_with_matrices() {
+	for element in evens() {
+		for point in points() {
+			assert_eq!(element.apply(point), times(even_matrix(element), point), "{:?} on {:?}", element, point);
+		}
+	}
+	for element in odds() {
+		for point in points() {
+			let large = times(odd_matrix(element), point);
+			assert_eq!(element.apply_as_large(point), large, "{:?} on {:?}", element, point);
+			let small = if large.0 % 2 == 0 && large.1 % 2 == 0 {Some((large.0 / 2, large.1 / 2))} else {None};
+			assert_eq!(element.apply_as_small(point), small, "{:?} on {:?}", element, point);
+		}
+	}
+}
+
+#[test]
+fn multiplication_agrees_with_matrices() {
+	for a in evens() {
+		for b in evens() {
+			assert_eq!(even_matrix(a * b), even_matrix(a) * even_matrix(b), "{:?} * {:?}", a, b);
+		}
+		for b in odds() {
+			assert_eq!(odd_matrix(a * b), even_matrix(a) * odd_matrix(b), "{:?} * {:?}", a, b);
+			assert_eq!(odd_matrix(b * a), odd_matrix(b) * even_matrix(a), "{:?} * {:?}", b, a);
+		}
+	}
+	//two sqrt(2)s make a 2, which OddD8 * OddD8 leaves out
+	for a in odds() {
+		for b in odds() {
+			assert_eq!(2 * even_matrix(a * b), odd_matrix(a) * odd_matrix(b), "{:?} * {:?}", a, b);
+		}
+	}
+}
+
+#[test]
+fn odd_times_odd_is_large_after_small() {
+	for a in odds() {
+		for b in odds() {
+			for point in points() {
+				if let Some(small) = b.apply_as_small(point) {
+					assert_eq!(a.apply_as_large(small), (a * b).apply(point), "{:?} * {:?} on {:?}", a, b, point);
+				}
+			}
+		}
+	}
+}
+
+#[test]
+fn multiplication_is_associative() {
+	let (evens, odds) = (evens(), odds());
+	for &a in &evens {
+		for &b in &evens {
+			for &c in &evens {
+				assert_eq!((a * b) * c, a * (b * c));
+			}
+			for &c in &odds {
+				assert_eq!((a * b) * c, a * (b * c));
+				assert_eq!((a * c) * b, a * (c * b));
+				assert_eq!((c * a) * b, c * (a * b));
+			}
+		}
+	}
+	for &a in &odds {
+		for &b in &odds {
+			for &c in &evens {
+				assert_eq!((a * b) * c, a * (b * c));
+				assert_eq!((a * c) * b, a * (c * b));
+				assert_eq!((c * a) * b, c * (a * b));
+			}
+			for &c in &odds {
+				assert_eq!((a * b) * c, a * (b * c));
+			}
+		}
+	}
+}
+
+#[test]
+fn identity_does_nothing() {
+	for element in evens() {
+		assert_eq!(dihedral::IDENTITY * element, element);
+		assert_eq!(element * dihedral::IDENTITY, element);
+	}
+	for element in odds() {
+		assert_eq!(dihedral::IDENTITY * element, element);
+		assert_eq!(element * dihedral::IDENTITY, element);
+	}
+}
+
+#[test]
+fn inverses_are_inverses() {
+	for element in evens() {
+		assert_eq!(element * element.inv(), dihedral::IDENTITY, "{:?}", element);
+		assert_eq!(element.inv() * element, dihedral::IDENTITY, "{:?}", element);
+		assert_eq!(element.inv().inv(), element);
+	}
+	for element in odds() {
+		assert_eq!(element * element.inv(), dihedral::IDENTITY, "{:?}", element);
+		assert_eq!(element.inv() * element, dihedral::IDENTITY, "{:?}", element);
+		assert_eq!(element.inv().inv(), element);
+	}
+}
+
+
+//how the translation types act on points, to check multiplication against
+fn act_even(transformation: EvenD8Translation, point: (isize, isize)) -> (isize, isize) {
+	let moved = transformation.get_dihedral().apply(point);
+	let translation = transformation.get_translation();
+	(moved.0 + translation.0, moved.1 + translation.1)
+}
+
+fn act_small(transformation: OddD8TranslationSmall, point: (isize, isize)) -> Option<(isize, isize)> {
+	let moved = transformation.get_dihedral().apply_as_small(point)?;
+	let translation = transformation.get_translation();
+	Some((moved.0 + translation.0, moved.1 + translation.1))
+}
+
+fn act_large(transformation: OddD8TranslationLarge, point: (isize, isize)) -> (isize, isize) {
+	let moved = transformation.get_dihedral().apply_as_large(point);
+	let translation = transformation.get_translation();
+	(moved.0 + translation.0, moved.1 + translation.1)
+}
+
+#[test]
+fn translation_products_compose() {
+	let mut random = Random(0x2545_f491_4f6c_dd1d);
+	for _ in 0..SAMPLES {
+		let (a, b, small, large) = (random.even(), random.even(), random.small(), random.large());
+		let point = random.translation();
+
+		assert_eq!(act_even(a * b, point), act_even(a, act_even(b, point)));
+		assert_eq!(act_large(a * large, point), act_even(a, act_large(large, point)));
+		assert_eq!(act_large(large * a, point), act_large(large, act_even(a, point)));
+		assert_eq!(act_small(a * small, point), act_small(small, point).map(|moved| act_even(a, moved)));
+		if let Some(moved) = act_small(small, point) {
+			assert_eq!(act_even(large * small, point), act_large(large, moved));
+		}
+		if let Some(product) = small * a {
+			assert_eq!(act_small(product, point), act_small(small, act_even(a, point)));
+		}
+		if let Some(product) = small * large {
+			assert_eq!(Some(act_even(product, point)), act_small(small, act_large(large, point)));
+		}
+	}
+}
+
+#[test]
+fn translation_products_are_associative() {
+	let mut random = Random(0x9e37_79b9_7f4a_7c15);
+	for _ in 0..SAMPLES {
+		let (a, b, c) = (random.even(), random.even(), random.even());
+		let (small, large) = (random.small(), random.large());
+
+		assert_eq!((a * b) * c, a * (b * c));
+		assert_eq!((a * b) * small, a * (b * small));
+		assert_eq!((a * b) * large, a * (b * large));
+		assert_eq!((a * large) * b, a * (large * b));
+		assert_eq!((large * a) * b, large * (a * b));
+		assert_eq!((a * large) * small, a * (large * small));
+		if let Some(product) = small * a {
+			assert_eq!(large * product, (large * small) * a);
+		}
+		if let (Some(left), Some(right)) = ((small * large).map(|even| even * a), small * (large * a)) {
+			assert_eq!(left, right);
+		}
+	}
+}
+
+#[test]
+fn translation_identity_and_inverses() {
+	let identity = dihedral_translation::IDENTITY;
+	let mut random = Random(0xd1b5_4a32_d192_ed03);
+	for _ in 0..SAMPLES {
+		let (even, small, large) = (random.even(), random.small(), random.large());
+
+		assert_eq!(identity * even, even);
+		assert_eq!(even * identity, even);
+		assert_eq!(even * even.inv(), identity);
+		assert_eq!(even.inv() * even, identity);
+
+		//small to large and back always works
+		assert_eq!(small.inv().inv(), Some(small));
+		assert_eq!(small.inv() * small, identity);
+		assert_eq!(small * small.inv(), Some(identity));
+
+		//large to small only works when the translation can be halved after the eighth turn
+		let (x, y) = large.get_translation();
+		assert_eq!(large.inv().is_some(), (x + y) % 2 == 0, "{:?}", large);
+		if let Some(inverse) = large.inv() {
+			assert_eq!(inverse.inv(), large);
+			assert_eq!(large * inverse, identity);
+			assert_eq!(inverse * large, Some(identity));
+		}
+	}
+}
+
+#[test]
+fn similarity_agrees_with_the_split_types() {
+	let mut random = Random(0x94d0_49bb_1331_11eb);
+	for _ in 0..SAMPLES {
+		let (a, b, small, large) = (random.even(), random.even(), random.small(), random.large());
+
+		assert_eq!(Similarity::from(a) * Similarity::from(b), Similarity::from(a * b));
+		assert_eq!(Similarity::from(large) * Similarity::from(small), Similarity::from(large * small));
+		assert_eq!(Similarity::from(a) * Similarity::from(small), Similarity::from(a * small));
+		assert_eq!(Similarity::from(large) * Similarity::from(a), Similarity::from(large * a));
+		assert_eq!(Similarity::from(small).inv(), Similarity::from(small.inv()));
+		if let Some(inverse) = large.inv() {
+			assert_eq!(Similarity::from(large).inv(), Similarity::from(inverse));
+		}
+		assert_eq!(Similarity::from(small).to_small(), Some(small));
+		assert_eq!(Similarity::from(large).to_large(), Some(large));
+
+		//and the products the split types can't do at all
+		let twice_small = Similarity::from(small) * Similarity::from(small);
+		assert_eq!(twice_small.scale(), -2);
+		assert_eq!(twice_small * twice_small.inv(), similarity::IDENTITY);
+		let point = DyadicPoint::whole(random.translation());
+		assert_eq!(twice_small.inv().apply(twice_small.apply(point)), point);
+	}
+}
+
+#[test]
+fn similarity_scales_compose_one_step_at_a_time() {
+	let mut random = Random(0x2545_f491_4f6c_dd1d);
+	let (up, down) = (Similarity::new(false, 0, 1, (0, 0)), Similarity::new(false, 0, -1, (0, 0)));
+	for _ in 0..SAMPLES {
+		let point = DyadicPoint::whole(random.translation());
+		let (mut grown, mut shrunk) = (point, point);
+		for scale in 1..=9 {
+			grown = up.apply(grown);
+			shrunk = down.apply(shrunk);
+			assert_eq!(Similarity::new(false, 0, scale, (0, 0)).apply(point), grown, "scale {}", scale);
+			assert_eq!(Similarity::new(false, 0, -scale, (0, 0)).apply(point), shrunk, "scale {}", -scale);
+		}
+	}
+}
+
+#[test]
+#[should_panic(expected = "too big for isize")]
+fn similarity_overflow_is_caught() {
+	let huge = Similarity::new(false, 0, 200, (0, 0));
+	huge.apply(DyadicPoint::whole((1, 0)));
+}
+
+#[test]
+#[should_panic(expected = "too big for isize")]
+fn dyadic_addition_overflow_is_caught() {
+	let _ = DyadicPoint::new((1, 0), 70) + DyadicPoint::whole((1, 0));
+}
+
+
+//----------------------------------------------------------------- d12
+
+fn evens12() -> Vec<EvenD12> {
+	[false, true].iter().flat_map(|&flip| (0..6).map(move |rot| EvenD12::new(flip, rot))).collect()
+}
+
+fn odds12() -> Vec<OddD12> {
+	[false, true].iter().flat_map(|&flip| (0..6).map(move |rot| OddD12::new(flip, rot))).collect()
+}
+
+//the same again in eisenstein coordinates (a, b) = a + b w: conjugating, then 1 + w for odd ones, then sixth turns (times w)
+fn sixth_turns(rot: i8) -> Matrix2<isize> {
+	let turn = Matrix2::new(0, -1, 1, 1);
+	(0..rot).fold(Matrix2::identity(), |matrix, _| turn * matrix)
+}
+
+fn conjugate_matrix(flip: bool) -> Matrix2<isize> {
+	if flip {Matrix2::new(1, 1, 0, -1)} else {Matrix2::identity()}
+}
+
+fn even12_matrix(element: EvenD12) -> Matrix2<isize> {
+	sixth_turns(element.rot()) * conjugate_matrix(element.flipped())
+}
+
+//as large, so sqrt(3) bigger
+fn odd12_matrix(element: OddD12) -> Matrix2<isize> {
+	sixth_turns(element.rot()) * Matrix2::new(1, -1, 1, 2) * conjugate_matrix(element.flipped())
+}
+
+#[test]
+fn d12_apply_agrees_with_matrices() {
+	for element in evens12() {
+		for point in points() {
+			assert_eq!(element.apply(point), times(even12_matrix(element), point), "{:?} on {:?}", element, point);
+		}
+	}
+	for element in odds12() {
+		for point in points() {
+			let large = times(odd12_matrix(element), point);
+			assert_eq!(element.apply_as_large(point), large, "{:?} on {:?}", element, point);
+			let small = if large.0 % 3 == 0 && large.1 % 3 == 0 {Some((large.0 / 3, large.1 / 3))} else {None};
+			assert_eq!(element.apply_as_small(point), small, "{:?} on {:?}", element, point);
+		}
+	}
+}
+
+#[test]
+fn d12_multiplication_agrees_with_matrices() {
+	for a in evens12() {
+		for b in evens12() {
+			assert_eq!(even12_matrix(a * b), even12_matrix(a) * even12_matrix(b), "{:?} * {:?}", a, b);
+		}
+		for b in odds12() {
+			assert_eq!(odd12_matrix(a * b), even12_matrix(a) * odd12_matrix(b), "{:?} * {:?}", a, b);
+			assert_eq!(odd12_matrix(b * a), odd12_matrix(b) * even12_matrix(a), "{:?} * {:?}", b, a);
+		}
+	}
+	//two sqrt(3)s make a 3, which OddD12 * OddD12 leaves out
+	for a in odds12() {
+		for b in odds12() {
+			assert_eq!(3 * even12_matrix(a * b), odd12_matrix(a) * odd12_matrix(b), "{:?} * {:?}", a, b);
+		}
+	}
+}
+
+#[test]
+fn d12_odd_times_odd_is_large_after_small() {
+	for a in odds12() {
+		for b in odds12() {
+			for point in points() {
+				if let Some(small) = b.apply_as_small(point) {
+					assert_eq!(a.apply_as_large(small), (a * b).apply(point), "{:?} * {:?} on {:?}", a, b, point);
+				}
+			}
+		}
+	}
+}
+
+#[test]
+fn d12_multiplication_is_associative() {
+	let (evens, odds) = (evens12(), odds12());
+	for &a in &evens {
+		for &b in &evens {
+			for &c in &evens {
+				assert_eq!((a * b) * c, a * (b * c));
+			}
+			for &c in &odds {
+				assert_eq!((a * b) * c, a * (b * c));
+				assert_eq!((a * c) * b, a * (c * b));
+				assert_eq!((c * a) * b, c * (a * b));
+			}
+		}
+	}
+	for &a in &odds {
+		for &b in &odds {
+			for &c in &evens {
+				assert_eq!((a * b) * c, a * (b * c));
+				assert_eq!((a * c) * b, a * (c * b));
+				assert_eq!((c * a) * b, c * (a * b));
+			}
+			for &c in &odds {
+				assert_eq!((a * b) * c, a * (b * c));
+			}
+		}
+	}
+}
+
+#[test]
+fn d12_identity_and_inverses() {
+	for element in evens12() {
+		assert_eq!(dihedral12::IDENTITY * element, element);
+		assert_eq!(element * dihedral12::IDENTITY, element);
+		assert_eq!(element * element.inv(), dihedral12::IDENTITY, "{:?}", element);
+		assert_eq!(element.inv() * element, dihedral12::IDENTITY, "{:?}", element);
+		assert_eq!(element.inv().inv(), element);
+	}
+	for element in odds12() {
+		assert_eq!(dihedral12::IDENTITY * element, element);
+		assert_eq!(element * dihedral12::IDENTITY, element);
+		assert_eq!(element * element.inv(), dihedral12::IDENTITY, "{:?}", element);
+		assert_eq!(element.inv() * element, dihedral12::IDENTITY, "{:?}", element);
+		assert_eq!(element.inv().inv(), element);
+	}
+}
+
+fn act_even12(transformation: EvenD12Translation, point: (isize, isize)) -> (isize, isize) {
+	let moved = transformation.get_dihedral().apply(point);
+	let translation = transformation.get_translation();
+	(moved.0 + translation.0, moved.1 + translation.1)
+}
+
+fn act_small12(transformation: OddD12TranslationSmall, point: (isize, isize)) -> Option<(isize, isize)> {
+	let moved = transformation.get_dihedral().apply_as_small(point)?;
+	let translation = transformation.get_translation();
+	Some((moved.0 + translation.0, moved.1 + translation.1))
+}
+
+fn act_large12(transformation: OddD12TranslationLarge, point: (isize, isize)) -> (isize, isize) {
+	let moved = transformation.get_dihedral().apply_as_large(point);
+	let translation = transformation.get_translation();
+	(moved.0 + translation.0, moved.1 + translation.1)
+}
+
+#[test]
+fn d12_translation_products_compose() {
+	let mut random = Random(0x5851_f42d_4c95_7f2d);
+	for _ in 0..SAMPLES {
+		let (a, b, small, large) = (random.even12(), random.even12(), random.small12(), random.large12());
+		let point = random.translation();
+
+		assert_eq!(act_even12(a * b, point), act_even12(a, act_even12(b, point)));
+		assert_eq!(act_large12(a * large, point), act_even12(a, act_large12(large, point)));
+		assert_eq!(act_large12(large * a, point), act_large12(large, act_even12(a, point)));
+		assert_eq!(act_small12(a * small, point), act_small12(small, point).map(|moved| act_even12(a, moved)));
+		if let Some(moved) = act_small12(small, point) {
+			assert_eq!(act_even12(large * small, point), act_large12(large, moved));
+		}
+		if let Some(product) = small * a {
+			assert_eq!(act_small12(product, point), act_small12(small, act_even12(a, point)));
+		}
+		if let Some(product) = small * large {
+			assert_eq!(Some(act_even12(product, point)), act_small12(small, act_large12(large, point)));
+		}
+	}
+}
+
+#[test]
+fn d12_translation_products_are_associative() {
+	let mut random = Random(0x1405_7b7e_f767_814f);
+	for _ in 0..SAMPLES {
+		let (a, b, c) = (random.even12(), random.even12(), random.even12());
+		let (small, large) = (random.small12(), random.large12());
+
+		assert_eq!((a * b) * c, a * (b * c));
+		assert_eq!((a * b) * small, a * (b * small));
+		assert_eq!((a * b) * large, a * (b * large));
+		assert_eq!((a * large) * b, a * (large * b));
+		assert_eq!((large * a) * b, large * (a * b));
+		assert_eq!((a * large) * small, a * (large * small));
+		if let Some(product) = small * a {
+			assert_eq!(large * product, (large * small) * a);
+		}
+		if let (Some(left), Some(right)) = ((small * large).map(|even| even * a), small * (large * a)) {
+			assert_eq!(left, right);
+		}
+	}
+}
+
+#[test]
+fn d12_translation_identity_and_inverses() {
+	let identity = dihedral12_translation::IDENTITY;
+	let mut random = Random(0x9fb2_1c65_1e98_df25);
+	for _ in 0..SAMPLES {
+		let (even, small, large) = (random.even12(), random.small12(), random.large12());
+
+		assert_eq!(identity * even, even);
+		assert_eq!(even * identity, even);
+		assert_eq!(even * even.inv(), identity);
+		assert_eq!(even.inv() * even, identity);
+
+		assert_eq!(small.inv().inv(), Some(small));
+		assert_eq!(small.inv() * small, identity);
+		assert_eq!(small * small.inv(), Some(identity));
+
+		//large to small only works when the translation is a multiple of 1 + w, which is when a and b match mod 3
+		let (x, y) = large.get_translation();
+		assert_eq!(large.inv().is_some(), (x - y) % 3 == 0, "{:?}", large);
+		if let Some(inverse) = large.inv() {
+			assert_eq!(inverse.inv(), large);
+			assert_eq!(large * inverse, identity);
+			assert_eq!(inverse * large, Some(identity));
+		}
+	}
+}
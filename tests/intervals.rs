@@ -0,0 +1,213 @@
+//the interval wrappers that are only there to make other intervals cheaper, checked against what they wrap:
+//CachedFunctionInterval should only call its function once per piece, and DepthLimitedInterval should stop changing past its depth.
+//also that interval_rules' substitutions keep Automaton's promise of every piece looking like what's inside it,
+//and what depth StabilisationInterval says things settle at (which it only ever checks one split down).
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use dimers::auxiliary;
+use dimers::colour_format::LinearCol;
+use dimers::dihedral_translation::{EvenD8Translation, IDENTITY};
+use dimers::interval_colouring::{
+	CachedFunctionInterval, ConstantInterval, DepthLimitedInterval, FunctionInterval, SplitInterval, StabilisationInterval, TreeInterval,
+};
+use dimers::interval_rules;
+
+//a colour that says where it was asked for, so different pieces can't be mistaken for each other
+fn at(x: f64) -> LinearCol {
+	LinearCol::new(x, 1.0 - x, 0.0, 0.0)
+}
+
+//the piece reached by going left (false) or right (true) along path
+fn piece(interval: &dyn SplitInterval, path: &[bool]) -> Box<dyn SplitInterval> {
+	let (left, right) = interval.split();
+	let half = if path[0] {right} else {left};
+	if path.len() == 1 {half} else {piece(half.as_ref(), &path[1..])}
+}
+
+#[test]
+fn cached_pieces_only_call_the_function_once() {
+	let calls = Rc::new(Cell::new(0));
+	let counted = {
+		let calls = calls.clone();
+		move |x: f64| {
+			calls.set(calls.get() + 1);
+			at(x)
+		}
+	};
+	let interval = CachedFunctionInterval::new(counted, 64);
+
+	assert_eq!(interval.get().r_lin(), 0.5);
+	interval.get();
+	assert_eq!(calls.get(), 1, "the whole interval twice");
+
+	//the same piece, split off twice over, is the same (start, end) and so the same cache entry
+	let path = [false, true, true];
+	let first = piece(&interval, &path).get();
+	let again = piece(&interval, &path).get();
+	assert_eq!(calls.get(), 2, "one new piece, asked for twice");
+	assert_eq!(first.r_lin(), again.r_lin());
+	assert_eq!(first.r_lin(), 0.4375, "the middle of [0.375, 0.5]");
+
+	//and a different piece is a miss
+	piece(&interval, &[true]).get();
+	assert_eq!(calls.get(), 3);
+}
+
+#[test]
+fn full_cache_stops_growing() {
+	let calls = Rc::new(Cell::new(0));
+	let counted = {
+		let calls = calls.clone();
+		move |x: f64| {
+			calls.set(calls.get() + 1);
+			at(x)
+		}
+	};
+	let interval = CachedFunctionInterval::new(counted, 1);
+
+	interval.get();
+	interval.get();
+	assert_eq!(calls.get(), 1, "the first piece fits");
+	let left = piece(&interval, &[false]);
+	left.get();
+	left.get();
+	assert_eq!(calls.get(), 3, "the second doesn't, so it's worked out every time");
+}
+
+#[test]
+fn depth_limited_pieces_match_the_inner_interval_above_the_limit() {
+	const MAX_DEPTH: u32 = 3;
+	let inner: FunctionInterval<_> = (at as fn(f64) -> LinearCol).into();
+	let limited = DepthLimitedInterval::new(Box::new(inner), MAX_DEPTH);
+	let inner: FunctionInterval<_> = (at as fn(f64) -> LinearCol).into();
+
+	for path in [&[false][..], &[true, false], &[true, true, false]] {
+		assert_eq!(piece(&limited, path).get().r_lin(), piece(&inner, path).get().r_lin(), "{:?}", path);
+	}
+}
+
+#[test]
+fn depth_limited_pieces_are_constant_below_the_limit() {
+	const MAX_DEPTH: u32 = 3;
+	let inner: FunctionInterval<_> = (at as fn(f64) -> LinearCol).into();
+	let limited = DepthLimitedInterval::new(Box::new(inner), MAX_DEPTH);
+
+	for limit_path in [[false, false, false], [false, true, true], [true, true, false]] {
+		let limit_piece = piece(&limited, &limit_path);
+		let colour = limit_piece.get();
+		for deeper in [&[false][..], &[true], &[true, false, true, true], &[false; 8]] {
+			let below = piece(limit_piece.as_ref(), deeper).get();
+			assert_eq!(
+				(below.r_lin(), below.g_lin()),
+				(colour.r_lin(), colour.g_lin()),
+				"{:?} then {:?}", limit_path, deeper,
+			);
+		}
+	}
+}
+
+//whether every piece down to depth is the average of its halves, and the deepest pieces (returned) in order
+fn check_averages(interval: &dyn SplitInterval, depth: u32, leaves: &mut Vec<f64>) {
+	if depth == 0 {
+		leaves.push(interval.get().r_lin());
+		return;
+	}
+	let (left, right) = interval.split();
+	let average = (left.get().r_lin() + right.get().r_lin()) / 2.0;
+	assert!((interval.get().r_lin() - average).abs() < 1e-9, "{} isn't the average of its halves, {}", interval.get().r_lin(), average);
+	check_averages(left.as_ref(), depth - 1, leaves);
+	check_averages(right.as_ref(), depth - 1, leaves);
+}
+
+#[test]
+fn substitutions_look_like_whats_inside_them() {
+	let (black, white) = (LinearCol::new(0.0, 0.0, 0.0, 0.0), LinearCol::new(1.0, 1.0, 1.0, 0.0));
+	let thue_morse = [('a', "ab", black), ('b', "ba", white)];
+
+	//three rounds, so the pieces three splits down are the first 8 symbols, abbabaab, and are flat from there on
+	let rounds = Rc::new(interval_rules::substitution(&thue_morse, 'a', Some(3)).expect("valid rules"));
+	let mut leaves = Vec::new();
+	check_averages(&rounds.interval(), 5, &mut leaves);
+	let symbols: Vec<f64> = leaves.chunks(4).map(|chunk| chunk[0]).collect();
+	assert_eq!(symbols, [0.0, 1.0, 1.0, 0.0, 1.0, 0.0, 0.0, 1.0]);
+
+	//forever, where every piece is half and half
+	let forever = Rc::new(interval_rules::substitution(&thue_morse, 'a', None).expect("valid rules"));
+	let mut leaves = Vec::new();
+	check_averages(&forever.interval(), 5, &mut leaves);
+	assert!(leaves.iter().all(|leaf| (leaf - 0.5).abs() < 1e-9), "{:?}", leaves);
+
+	//and one that isn't balanced, where a is three quarters a every round but b takes over in the end
+	let uneven = [('a', "aaab", black), ('b', "bbbb", white)];
+	let uneven = Rc::new(interval_rules::substitution(&uneven, 'a', None).expect("valid rules"));
+	check_averages(&uneven.interval(), 6, &mut Vec::new());
+}
+
+//the depth it settled at as the red, or -1 for unsettled
+fn settled_depth(interval: Box<dyn SplitInterval>) -> StabilisationInterval<impl Fn(u32) -> LinearCol + Clone> {
+	StabilisationInterval::new(interval, |depth| at(depth as f64), at(-1.0))
+}
+
+fn depth_at(interval: &dyn SplitInterval, path: &[bool]) -> f64 {
+	let colour = if path.is_empty() {interval.get()} else {piece(interval, path).get()};
+	colour.r_lin()
+}
+
+#[test]
+fn two_colouring_settles_a_split_late_on_the_identity() {
+	let identity = settled_depth(auxiliary::two_colouring(IDENTITY));
+	assert_eq!(depth_at(&identity, &[]), -1.0, "its halves are different colours");
+	for path in [&[false][..], &[true], &[true, false, true]] {
+		assert_eq!(depth_at(&identity, path), 1.0, "{:?}", path);
+	}
+
+	let elsewhere = settled_depth(auxiliary::two_colouring(EvenD8Translation::new(false, 1, (0, 0))));
+	assert_eq!(depth_at(&elsewhere, &[]), 0.0);
+	assert_eq!(depth_at(&elsewhere, &[false, true]), 0.0);
+}
+
+#[test]
+fn stabilisation_only_looks_one_split_down() {
+	let (red, blue) = (at(0.25), at(0.75));
+	let purple = (red + blue) / 2.0;
+	let mixed = || -> Rc<dyn SplitInterval> {Rc::new(TreeInterval::new(purple, Rc::new(ConstantInterval::from(red)), Rc::new(ConstantInterval::from(blue))))};
+
+	//left is mixed, right is flat and a different colour, so only right settles at 1 and left's halves wait until 2
+	let deeper = TreeInterval::new((purple + red) / 2.0, mixed(), Rc::new(ConstantInterval::from(red)));
+	let deeper = settled_depth(Box::new(deeper));
+	assert_eq!(depth_at(&deeper, &[]), -1.0);
+	assert_eq!(depth_at(&deeper, &[false]), -1.0);
+	assert_eq!(depth_at(&deeper, &[true]), 1.0);
+	assert_eq!(depth_at(&deeper, &[false, false]), 2.0);
+	assert_eq!(depth_at(&deeper, &[false, true, true, false]), 2.0);
+
+	//but when the halves average to the whole's colour, what's further in doesn't count
+	let hidden = settled_depth(Box::new(TreeInterval::new(purple, mixed(), mixed())));
+	for path in [&[][..], &[false], &[true, false]] {
+		assert_eq!(depth_at(&hidden, path), 0.0, "{:?}", path);
+	}
+	let thue_morse = settled_depth(auxiliary::thue_morse_colouring(IDENTITY));
+	for path in [&[][..], &[false], &[true, true, false]] {
+		assert_eq!(depth_at(&thue_morse, path), 0.0, "{:?}", path);
+	}
+}
+
+#[test]
+fn substitutions_turn_away_what_they_cant_do() {
+	let (black, white) = (LinearCol::new(0.0, 0.0, 0.0, 0.0), LinearCol::new(1.0, 1.0, 1.0, 0.0));
+	let error = interval_rules::substitution(&[('a', "ab", black), ('b', "ba", white), ('a', "aa", white)], 'a', Some(2)).err();
+	assert_eq!(error.as_deref(), Some("more than one rule for 'a'"));
+
+	//a long loop of symbols, each replaced by two of the next, where the one white symbol's colour only spreads round
+	//the loop a little each time, far too slowly to settle in time
+	let symbols: Vec<char> = (0..64).map(|index| char::from_u32(0x100 + index).expect("a letter")).collect();
+	let replacements: Vec<String> = (0..64).map(|index| symbols[(index + 1) % 64].to_string().repeat(2)).collect();
+	let slow: Vec<(char, &str, LinearCol)> = (0..64)
+		.map(|index| (symbols[index], replacements[index].as_str(), if index == 0 {white} else {black}))
+		.collect();
+	let error = interval_rules::substitution(&slow, symbols[0], None).err().unwrap_or_default();
+	assert!(error.contains("settled"), "{:?}", error);
+	assert!(interval_rules::substitution(&slow, symbols[0], Some(100)).is_ok(), "any number of rounds is fine");
+}
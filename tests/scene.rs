@@ -0,0 +1,106 @@
+//scene files: what parse makes of them, that to_text writes something parse reads back as the same scene,
+//and that the things the renderer can't cope with (like scales past zoom::MAX_SCALE) are turned away with a line number.
+//also that a scene's texture is the one its textured colourings paint with.
+
+use std::fs;
+use std::path::Path;
+
+use dimers::dithering::Dithering;
+use dimers::render::Image;
+use dimers::scene::{Scene, SceneFractal};
+use dimers::zoom;
+
+fn round_trip(scene: &Scene) {
+	let (again, format_given) = Scene::parse(&scene.to_text()).expect("to_text should always parse");
+	assert_eq!(&again, scene, "from\n{}", scene.to_text());
+	assert!(format_given, "to_text always says the format");
+}
+
+#[test]
+fn keys_set_what_they_say() {
+	let text = "
+		# a comment, and a blank line after it
+
+		fractal = hydra
+		width = 30   # comments can go after values too
+		height = 20
+		origin = 4 -5
+		scale = 3
+		antialiasing = 4
+		dithering = bayer
+	";
+	let (scene, format_given) = Scene::parse(text).expect("valid scene");
+	assert_eq!((scene.width, scene.height, scene.origin, scene.scale, scene.antialiasing), (30, 20, (4, -5), 3, 4));
+	assert_eq!(scene.dithering, Dithering::from_name("bayer").expect("bayer is a dithering"));
+	assert!(!format_given);
+	assert_eq!(scene.format, Scene::default().format, "left out, so left alone");
+}
+
+#[test]
+fn scenes_survive_being_written_out() {
+	round_trip(&Scene::default());
+
+	let (scene, _) = Scene::parse("width = 7\nheight = 3\norigin = -2 9\nscale = 48\nformat = rgb8\ndithering = none").expect("valid scene");
+	round_trip(&scene);
+
+	let custom = "
+		fractal = custom
+		production = true 0 -1 0, false 2 1 0
+		splitting = true 1 1 0, false 1 1 0
+		acceptable = even
+		square_config = dense
+		splitting_type = false false
+	";
+	let (scene, _) = Scene::parse(custom).expect("the example custom fractal");
+	assert!(matches!(scene.fractal, SceneFractal::Custom(_)));
+	round_trip(&scene);
+}
+
+#[test]
+fn scale_stops_at_the_renderers_limit() {
+	assert!(Scene::parse(&format!("scale = {}", zoom::MAX_SCALE)).is_ok());
+	for scale in [zoom::MAX_SCALE + 1, 64, 70] {
+		let error = Scene::parse(&format!("width = 4\nscale = {}", scale)).expect_err("too far in to draw");
+		assert!(error.starts_with("line 2:"), "{}", error);
+	}
+}
+
+#[test]
+fn mistakes_say_which_line() {
+	for (text, line) in [
+		("colour = two", 1),
+		("width = 4\nheight", 2),
+		("width = -4", 1),
+		("\n\nantialiasing = 3", 3),
+		("format = jpeg", 1),
+		("fractal = nothing", 1),
+		("acceptable = even", 1),
+		("fractal = custom\nacceptable = even", 1),
+	] {
+		let error = Scene::parse(text).expect_err(text);
+		assert!(error.starts_with(&format!("line {}:", line)), "{:?} gave {}", text, error);
+	}
+	assert!(Scene::parse("width = 0").is_err(), "nothing to draw");
+}
+
+#[test]
+fn textures_are_kept_and_painted_with() {
+	//a flat green 2 x 2 ppm, nothing like the default rainbow
+	let path = Path::new(env!("CARGO_TARGET_TMPDIR")).join("scene-green.ppm");
+	fs::write(&path, [&b"P6 2 2 255\n"[..], &[0, 255, 0].repeat(4)].concat()).expect("couldn't write the texture");
+
+	let text = format!("colouring = texture\nwidth = 24\nheight = 24\norigin = 12 12\nscale = 4\ntexture = {}", path.display());
+	let (scene, _) = Scene::parse(&text).expect("valid scene");
+	assert_eq!(scene.texture.as_ref().map(|texture| texture.path.as_path()), Some(path.as_path()));
+	round_trip(&scene);
+
+	let green = scene.render();
+	let default = Scene {texture: None, ..scene.clone()}.render();
+	let tinted = |image: &Image| image.pixels().iter().any(|pixel| pixel.r_lin() > 1e-9 || pixel.b_lin() > 1e-9);
+	assert!(!tinted(&green), "only green (or the black background) should be drawn");
+	assert!(green.pixels().iter().any(|pixel| pixel.g_lin() > 1e-9), "the texture should show up somewhere");
+	assert!(tinted(&default), "the default texture is a rainbow");
+
+	let error = Scene::parse("\ntexture = there/is/no/such.ppm").expect_err("no such file");
+	assert!(error.starts_with("line 2:"), "{}", error);
+}
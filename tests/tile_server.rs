@@ -0,0 +1,91 @@
+//the parts of tile_server that don't need a socket: which tiles the cache keeps, and which paths are tiles at all.
+
+use std::sync::Arc;
+
+use dimers::tile_server::{self, TileCache, TileKey};
+
+fn key(z: u32, x: usize, y: usize) -> TileKey {
+	("hydra".to_string(), "two".to_string(), z, x, y)
+}
+
+//a png stand in that says which tile it was for
+fn png(tag: u8) -> Arc<Vec<u8>> {
+	Arc::new(vec![tag])
+}
+
+#[test]
+fn cache_drops_the_least_recently_used() {
+	let mut cache = TileCache::new(3);
+	cache.insert(key(0, 0, 0), png(0));
+	cache.insert(key(1, 0, 0), png(1));
+	cache.insert(key(1, 1, 0), png(2));
+
+	//asking for the oldest makes it the newest, so the next one in line goes instead
+	assert_eq!(cache.get(&key(0, 0, 0)), Some(png(0)));
+	cache.insert(key(1, 0, 1), png(3));
+	assert_eq!(cache.len(), 3);
+	assert_eq!(cache.get(&key(1, 0, 0)), None);
+	assert_eq!(cache.get(&key(0, 0, 0)), Some(png(0)));
+
+	//and misses don't count as using anything
+	assert_eq!(cache.get(&key(5, 5, 5)), None);
+	cache.insert(key(1, 1, 1), png(4));
+	assert_eq!(cache.get(&key(1, 1, 0)), None);
+	for (tile, tag) in [(key(0, 0, 0), 0), (key(1, 0, 1), 3), (key(1, 1, 1), 4)] {
+		assert_eq!(cache.get(&tile), Some(png(tag)), "{:?}", tile);
+	}
+}
+
+#[test]
+fn cache_of_nothing_keeps_nothing() {
+	let mut cache = TileCache::new(0);
+	cache.insert(key(0, 0, 0), png(0));
+	assert!(cache.is_empty());
+	assert_eq!(cache.get(&key(0, 0, 0)), None);
+}
+
+#[test]
+fn reinserting_replaces_without_evicting() {
+	let mut cache = TileCache::new(2);
+	cache.insert(key(0, 0, 0), png(0));
+	cache.insert(key(1, 0, 0), png(1));
+
+	//already there, so nothing else has to make room, and it counts as used
+	cache.insert(key(0, 0, 0), png(9));
+	assert_eq!(cache.len(), 2);
+	assert_eq!(cache.get(&key(1, 0, 0)), Some(png(1)));
+	assert_eq!(cache.get(&key(0, 0, 0)), Some(png(9)));
+
+	cache.insert(key(1, 1, 0), png(2));
+	assert_eq!(cache.get(&key(1, 0, 0)), None, "the least recently used of the two");
+}
+
+#[test]
+fn routes_are_tiles_or_nothing() {
+	assert_eq!(tile_server::parse_route("/hydra/two/3/1/2.png"), Some(("hydra".to_string(), "two".to_string(), 3, 1, 2)));
+	assert_eq!(tile_server::parse_route("/flake/three/0/0/0.png?cache=no"), Some(("flake".to_string(), "three".to_string(), 0, 0, 0)));
+
+	for path in [
+		"",
+		"/",
+		"hydra/two/3/1/2.png",
+		"/hydra/two/3/1/2",
+		"/hydra/two/3/1/2.jpg",
+		"/hydra/two/3/1.png",
+		"/hydra/two/3/1/2/4.png",
+		"/hydra/two/-1/1/2.png",
+		"/hydra/two/3/x/2.png",
+		"/hydra/two/3/1/.png",
+		"/hydra/two/99999999999/1/2.png",
+	] {
+		assert_eq!(tile_server::parse_route(path), None, "{:?}", path);
+	}
+}
+
+#[test]
+fn tiles_past_the_edge_dont_exist() {
+	assert!(tile_server::tile_view(6, 2, 3, 3).is_some());
+	assert!(tile_server::tile_view(6, 2, 4, 0).is_none());
+	assert!(tile_server::tile_view(6, tile_server::MAX_ZOOM + 1, 0, 0).is_none());
+	assert!(tile_server::run(&["--scale".to_string(), "30".to_string()]).is_err(), "zoomed all the way in would be past zoom::MAX_SCALE");
+}